@@ -101,6 +101,8 @@ struct Cli {
     rpc_bind_ip: Option<IpAddr>,
     #[clap(short = 'p', long, env)]
     rpc_port: Option<u16>,
+    #[clap(long, env)]
+    ws_port: Option<u16>,
     #[clap(short = 'w', long, env)]
     checkpoint: Option<String>,
     #[clap(short, long, env)]
@@ -131,6 +133,7 @@ impl Cli {
             data_dir: self.get_data_dir(),
             rpc_bind_ip: self.rpc_bind_ip,
             rpc_port: self.rpc_port,
+            ws_port: self.ws_port,
             fallback: self.fallback.clone(),
             load_external_fallback: self.load_external_fallback,
             strict_checkpoint_age: self.strict_checkpoint_age,
@@ -2,13 +2,19 @@ use std::net::IpAddr;
 use std::sync::Arc;
 
 use config::networks::Network;
+use config::{PrefetchStrategy, SignerConfig};
 use ethers::prelude::{Address, U256};
-use ethers::types::{Filter, Log, SyncingStatus, Transaction, TransactionReceipt, H256};
+use ethers::types::{
+    Bytes, EIP1186ProofResponse, FeeHistory, Filter, Log, Signature, SyncingStatus, Transaction,
+    TransactionReceipt, H256,
+};
 use eyre::{eyre, Result};
+use tokio::sync::broadcast;
 
 use common::types::{Block, BlockTag};
 use config::Config;
-use execution::types::CallOpts;
+use execution::tracker::{Confirmation, TransactionEvent, TransactionStatus};
+use execution::types::{CallOpts, FilterChanges, SimulatedBlock, SimulationBlock, TransactionOpts};
 use tracing::{info, warn};
 
 use crate::node::Node;
@@ -30,11 +36,21 @@ pub struct ClientBuilder {
     #[cfg(not(target_arch = "wasm32"))]
     rpc_port: Option<u16>,
     #[cfg(not(target_arch = "wasm32"))]
+    ws_port: Option<u16>,
+    #[cfg(not(target_arch = "wasm32"))]
     data_dir: Option<PathBuf>,
     config: Option<Config>,
     fallback: Option<String>,
     load_external_fallback: bool,
     strict_checkpoint_age: bool,
+    prefetch_strategy: Option<PrefetchStrategy>,
+    execution_rpc_batch_size: Option<usize>,
+    max_logs: Option<usize>,
+    logs_completeness: bool,
+    signer: Option<SignerConfig>,
+    rebroadcast_transactions: bool,
+    strict_transactions: bool,
+    fork: bool,
 }
 
 impl ClientBuilder {
@@ -70,6 +86,13 @@ impl ClientBuilder {
         self
     }
 
+    /// Serves the JSON-RPC methods and `eth_subscribe` over a WebSocket on this port.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ws_port(mut self, port: u16) -> Self {
+        self.ws_port = Some(port);
+        self
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn data_dir(mut self, data_dir: PathBuf) -> Self {
         self.data_dir = Some(data_dir);
@@ -96,6 +119,51 @@ impl ClientBuilder {
         self
     }
 
+    pub fn prefetch_strategy(mut self, prefetch_strategy: PrefetchStrategy) -> Self {
+        self.prefetch_strategy = Some(prefetch_strategy);
+        self
+    }
+
+    pub fn execution_rpc_batch_size(mut self, batch_size: usize) -> Self {
+        self.execution_rpc_batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn max_logs(mut self, max_logs: usize) -> Self {
+        self.max_logs = Some(max_logs);
+        self
+    }
+
+    pub fn logs_completeness(mut self) -> Self {
+        self.logs_completeness = true;
+        self
+    }
+
+    /// Signs transactions sent through `eth_sendTransaction` with a local key.
+    pub fn signer(mut self, signer: SignerConfig) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Periodically rebroadcasts the transactions sent through helios until they are included.
+    pub fn rebroadcast_transactions(mut self) -> Self {
+        self.rebroadcast_transactions = true;
+        self
+    }
+
+    /// Validates raw transactions against verified state before broadcasting them.
+    pub fn strict_transactions(mut self) -> Self {
+        self.strict_transactions = true;
+        self
+    }
+
+    /// Serves a local fork of the latest verified block over the rpc.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn fork(mut self) -> Self {
+        self.fork = true;
+        self
+    }
+
     pub fn build(self) -> Result<Client> {
         let base_config = if let Some(network) = self.network {
             network.to_base_config()
@@ -155,6 +223,11 @@ impl ClientBuilder {
             None
         };
 
+        #[cfg(not(target_arch = "wasm32"))]
+        let ws_port = self
+            .ws_port
+            .or(self.config.as_ref().and_then(|config| config.ws_port));
+
         #[cfg(not(target_arch = "wasm32"))]
         let data_dir = if self.data_dir.is_some() {
             self.data_dir
@@ -184,6 +257,58 @@ impl ClientBuilder {
             self.strict_checkpoint_age
         };
 
+        let prefetch_strategy = if let Some(prefetch_strategy) = self.prefetch_strategy {
+            prefetch_strategy
+        } else if let Some(config) = &self.config {
+            config.prefetch_strategy
+        } else {
+            PrefetchStrategy::default()
+        };
+
+        let execution_rpc_batch_size = self.execution_rpc_batch_size.or(self
+            .config
+            .as_ref()
+            .and_then(|config| config.execution_rpc_batch_size));
+
+        let execution_rpc_strategies = self
+            .config
+            .as_ref()
+            .map(|config| config.execution_rpc_strategies.clone())
+            .unwrap_or_default();
+
+        let max_logs = self
+            .max_logs
+            .or(self.config.as_ref().and_then(|config| config.max_logs));
+
+        let logs_completeness = if let Some(config) = &self.config {
+            self.logs_completeness || config.logs_completeness
+        } else {
+            self.logs_completeness
+        };
+
+        let signer = self.signer.or(self
+            .config
+            .as_ref()
+            .and_then(|config| config.signer.clone()));
+
+        let rebroadcast_transactions = if let Some(config) = &self.config {
+            self.rebroadcast_transactions || config.rebroadcast_transactions
+        } else {
+            self.rebroadcast_transactions
+        };
+
+        let strict_transactions = if let Some(config) = &self.config {
+            self.strict_transactions || config.strict_transactions
+        } else {
+            self.strict_transactions
+        };
+
+        let fork = if let Some(config) = &self.config {
+            self.fork || config.fork
+        } else {
+            self.fork
+        };
+
         let config = Config {
             consensus_rpc,
             execution_rpc,
@@ -198,6 +323,10 @@ impl ClientBuilder {
             #[cfg(target_arch = "wasm32")]
             rpc_port: None,
             #[cfg(not(target_arch = "wasm32"))]
+            ws_port,
+            #[cfg(target_arch = "wasm32")]
+            ws_port: None,
+            #[cfg(not(target_arch = "wasm32"))]
             data_dir,
             #[cfg(target_arch = "wasm32")]
             data_dir: None,
@@ -207,6 +336,15 @@ impl ClientBuilder {
             fallback,
             load_external_fallback,
             strict_checkpoint_age,
+            prefetch_strategy,
+            execution_rpc_batch_size,
+            execution_rpc_strategies,
+            max_logs,
+            logs_completeness,
+            signer,
+            rebroadcast_transactions,
+            strict_transactions,
+            fork,
         };
 
         Client::new(config)
@@ -227,8 +365,13 @@ impl Client {
         let mut rpc: Option<Rpc> = None;
 
         #[cfg(not(target_arch = "wasm32"))]
-        if config.rpc_bind_ip.is_some() || config.rpc_port.is_some() {
-            rpc = Some(Rpc::new(node.clone(), config.rpc_bind_ip, config.rpc_port));
+        if config.rpc_bind_ip.is_some() || config.rpc_port.is_some() || config.ws_port.is_some() {
+            let mut inner = Rpc::new(node.clone(), config.rpc_bind_ip, config.rpc_port);
+            if let Some(port) = config.ws_port {
+                inner = inner.with_ws_port(port);
+            }
+
+            rpc = Some(inner);
         }
 
         Ok(Client {
@@ -262,6 +405,17 @@ impl Client {
         self.node.estimate_gas(opts).await.map_err(|err| err.into())
     }
 
+    pub async fn simulate(
+        &self,
+        blocks: &[SimulationBlock],
+        block: BlockTag,
+    ) -> Result<Vec<SimulatedBlock>> {
+        self.node
+            .simulate(blocks, block)
+            .await
+            .map_err(|err| err.into())
+    }
+
     pub async fn get_balance(&self, address: &Address, block: BlockTag) -> Result<U256> {
         self.node.get_balance(address, block).await
     }
@@ -282,6 +436,15 @@ impl Client {
         self.node.get_code(address, block).await
     }
 
+    pub async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        block: BlockTag,
+    ) -> Result<EIP1186ProofResponse> {
+        self.node.get_proof(address, slots, block).await
+    }
+
     pub async fn get_storage_at(
         &self,
         address: &Address,
@@ -295,6 +458,42 @@ impl Client {
         self.node.send_raw_transaction(bytes).await
     }
 
+    pub fn get_accounts(&self) -> Vec<Address> {
+        self.node.get_accounts()
+    }
+
+    pub fn sign(&self, address: &Address, message: &[u8]) -> Result<Signature> {
+        self.node.sign(address, message)
+    }
+
+    pub async fn sign_transaction(&self, opts: &TransactionOpts) -> Result<Bytes> {
+        self.node.sign_transaction(opts).await
+    }
+
+    pub async fn send_transaction(&self, opts: &TransactionOpts) -> Result<H256> {
+        self.node.send_transaction(opts).await
+    }
+
+    /// Returns the status of a transaction sent through helios, or `None` if it is not tracked.
+    pub async fn get_transaction_status(&self, hash: &H256) -> Option<TransactionStatus> {
+        self.node.get_transaction_status(hash).await
+    }
+
+    /// Returns a receiver of every status change of the transactions sent through helios.
+    pub fn subscribe_transactions(&self) -> broadcast::Receiver<TransactionEvent> {
+        self.node.subscribe_transactions()
+    }
+
+    /// Waits until a transaction sent through helios meets `confirmation`, and returns its
+    /// verified receipt.
+    pub async fn wait_for_receipt(
+        &self,
+        hash: &H256,
+        confirmation: Confirmation,
+    ) -> Result<TransactionReceipt> {
+        self.node.wait_for_receipt(hash, confirmation).await
+    }
+
     pub async fn get_transaction_receipt(
         &self,
         tx_hash: &H256,
@@ -302,7 +501,14 @@ impl Client {
         self.node.get_transaction_receipt(tx_hash).await
     }
 
-    pub async fn get_transaction_by_hash(&self, tx_hash: &H256) -> Option<Transaction> {
+    pub async fn get_block_receipts(
+        &self,
+        block: BlockTag,
+    ) -> Result<Option<Vec<TransactionReceipt>>> {
+        self.node.get_block_receipts(block).await
+    }
+
+    pub async fn get_transaction_by_hash(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
         self.node.get_transaction_by_hash(tx_hash).await
     }
 
@@ -310,8 +516,8 @@ impl Client {
         self.node.get_logs(filter).await
     }
 
-    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<bool> {
-        self.node.uninstall_filter(filter_id).await
+    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<FilterChanges> {
+        self.node.get_filter_changes(filter_id).await
     }
 
     pub async fn uninstall_filter(&self, filter_id: &U256) -> Result<bool> {
@@ -330,12 +536,33 @@ impl Client {
         self.node.get_new_pending_transaction_filter().await
     }
 
+    /// Returns a receiver of every new verified block, as sent to `newHeads` subscribers.
+    pub fn subscribe_new_heads(&self) -> broadcast::Receiver<Block> {
+        self.node.subscribe_new_heads()
+    }
+
+    /// Returns a receiver of every new verified finalized block.
+    pub fn subscribe_finalized_blocks(&self) -> broadcast::Receiver<Block> {
+        self.node.subscribe_finalized_blocks()
+    }
+
     pub async fn get_gas_price(&self) -> Result<U256> {
         self.node.get_gas_price().await
     }
 
     pub async fn get_priority_fee(&self) -> Result<U256> {
-        self.node.get_priority_fee()
+        self.node.get_priority_fee().await
+    }
+
+    pub async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: BlockTag,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        self.node
+            .get_fee_history(block_count, last_block, reward_percentiles)
+            .await
     }
 
     pub async fn get_block_number(&self) -> Result<U256> {
@@ -350,6 +577,10 @@ impl Client {
         self.node.get_block_by_number(block, full_tx).await
     }
 
+    pub async fn get_raw_header(&self, block: BlockTag) -> Result<Option<Vec<u8>>> {
+        self.node.get_raw_header(block).await
+    }
+
     pub async fn get_block_by_hash(&self, hash: &H256, full_tx: bool) -> Result<Option<Block>> {
         self.node.get_block_by_hash(hash, full_tx).await
     }
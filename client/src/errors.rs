@@ -1,4 +1,5 @@
 use common::errors::BlockNotFoundError;
+use ethers::types::{Address, U256};
 use execution::errors::EvmError;
 use eyre::Report;
 use thiserror::Error;
@@ -35,6 +36,52 @@ pub enum NodeError {
 
     #[error(transparent)]
     BlockNotFoundError(#[from] BlockNotFoundError),
+
+    #[error("signer creation error: {0}")]
+    SignerCreationError(Report),
+
+    #[error("no signer configured")]
+    NoSigner,
+
+    #[error("unknown account: {0:?}")]
+    UnknownAccount(Address),
+}
+
+/// Reasons a raw transaction is rejected before it is broadcast. Values are read from the
+/// verified state of the latest block.
+#[derive(Debug, Error)]
+pub enum TransactionError {
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(Report),
+
+    #[error("invalid chain id: {found}, expected: {expected}")]
+    InvalidChainId { expected: u64, found: u64 },
+
+    #[error("max priority fee per gas higher than max fee per gas: maxPriorityFeePerGas: {max_priority_fee}, maxFeePerGas: {max_fee}")]
+    TipAboveFeeCap {
+        max_priority_fee: U256,
+        max_fee: U256,
+    },
+
+    #[error("exceeds block gas limit: gas: {gas}, limit: {limit}")]
+    GasLimitExceeded { gas: U256, limit: u64 },
+
+    #[error("nonce too low: address {address:?}, tx: {nonce} state: {expected}")]
+    NonceTooLow {
+        address: Address,
+        nonce: U256,
+        expected: u64,
+    },
+
+    #[error("insufficient funds for gas * price + value: address {address:?} have {balance} want {cost}")]
+    InsufficientFunds {
+        address: Address,
+        balance: U256,
+        cost: U256,
+    },
+
+    #[error("simulation failed: {0}")]
+    SimulationFailed(EvmError),
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -7,3 +7,5 @@ pub mod errors;
 pub mod rpc;
 
 pub mod node;
+
+mod signer;
@@ -1,31 +1,45 @@
 use std::sync::Arc;
 
 use ethers::prelude::{Address, U256};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::Eip2930TransactionRequest;
 use ethers::types::{
-    Filter, Log, SyncProgress, SyncingStatus, Transaction, TransactionReceipt, H256,
+    Bytes, EIP1186ProofResponse, Eip1559TransactionRequest, FeeHistory, Filter, Log, Signature,
+    SyncProgress, SyncingStatus, Transaction, TransactionReceipt, TransactionRequest, H256,
 };
 use eyre::{eyre, Result};
+use tokio::sync::broadcast;
+use tracing::warn;
 use wasm_timer::{SystemTime, UNIX_EPOCH};
 
+use common::errors::BlockNotFoundError;
 use common::types::{Block, BlockTag};
+use common::utils::{blob_transaction_fields, decode_transaction};
 use config::Config;
 use execution::state::State;
 
 use consensus::database::FileDB;
 use consensus::rpc::nimbus_rpc::NimbusRpc;
 use consensus::ConsensusClient;
+use execution::constants::GAS_PER_BLOB;
 use execution::evm::Evm;
-use execution::rpc::http_rpc::HttpRpc;
-use execution::types::CallOpts;
+use execution::rpc::any_rpc::AnyRpc;
+use execution::rpc::multi_rpc::MultiRpc;
+use execution::rpc::ExecutionRpc;
+use execution::tracker::{Confirmation, TransactionEvent, TransactionStatus, TransactionTracker};
+use execution::types::{CallOpts, FilterChanges, SimulatedBlock, SimulationBlock, TransactionOpts};
 use execution::ExecutionClient;
 
-use crate::errors::NodeError;
+use crate::errors::{NodeError, TransactionError};
+use crate::signer::Signer;
 
 pub struct Node {
     pub consensus: ConsensusClient<NimbusRpc, FileDB>,
-    pub execution: Arc<ExecutionClient<HttpRpc>>,
+    pub execution: Arc<ExecutionClient<MultiRpc<AnyRpc>>>,
     pub config: Arc<Config>,
     pub history_size: usize,
+    pub tracker: TransactionTracker<MultiRpc<AnyRpc>>,
+    signer: Option<Signer>,
 }
 
 impl Node {
@@ -38,18 +52,43 @@ impl Node {
 
         let block_recv = consensus.block_recv.take().unwrap();
         let finalized_block_recv = consensus.finalized_block_recv.take().unwrap();
+        let safe_block_recv = consensus.safe_block_recv.take().unwrap();
 
-        let state = State::new(block_recv, finalized_block_recv, 256);
-        let execution = Arc::new(
-            ExecutionClient::new(execution_rpc, state)
-                .map_err(NodeError::ExecutionClientCreationError)?,
-        );
+        let state = State::new(block_recv, finalized_block_recv, safe_block_recv, 256);
+        let mut execution = ExecutionClient::new(execution_rpc, state)
+            .map_err(NodeError::ExecutionClientCreationError)?
+            .with_prefetch_strategy(config.prefetch_strategy)
+            .with_logs_completeness(config.logs_completeness);
+
+        if let Some(batch_size) = config.execution_rpc_batch_size {
+            execution = execution.with_batch_size(batch_size);
+        }
+
+        if let Some(max_logs) = config.max_logs {
+            execution = execution.with_max_logs(max_logs);
+        }
+
+        for (method, strategy) in &config.execution_rpc_strategies {
+            execution.rpc = execution.rpc.with_strategy(method, *strategy);
+        }
+
+        let execution = Arc::new(execution);
+        let tracker = TransactionTracker::new(execution.clone(), config.rebroadcast_transactions);
+
+        let signer = config
+            .signer
+            .as_ref()
+            .map(|signer| Signer::new(signer, config.chain.chain_id))
+            .transpose()
+            .map_err(NodeError::SignerCreationError)?;
 
         Ok(Node {
             consensus,
             execution,
             config,
             history_size: 64,
+            tracker,
+            signer,
         })
     }
 
@@ -71,6 +110,20 @@ impl Node {
             .map_err(NodeError::ExecutionEvmError)
     }
 
+    pub async fn simulate(
+        &self,
+        blocks: &[SimulationBlock],
+        block: BlockTag,
+    ) -> Result<Vec<SimulatedBlock>, NodeError> {
+        self.check_blocktag_age(&block).await?;
+
+        let mut evm = Evm::new(self.execution.clone(), self.chain_id(), block);
+
+        evm.simulate(blocks)
+            .await
+            .map_err(NodeError::ExecutionEvmError)
+    }
+
     pub async fn get_balance(&self, address: &Address, tag: BlockTag) -> Result<U256> {
         self.check_blocktag_age(&tag).await?;
 
@@ -106,6 +159,16 @@ impl Node {
         Ok(account.code)
     }
 
+    pub async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        tag: BlockTag,
+    ) -> Result<EIP1186ProofResponse> {
+        self.check_blocktag_age(&tag).await?;
+        self.execution.get_proof(address, slots, tag).await
+    }
+
     pub async fn get_storage_at(
         &self,
         address: &Address,
@@ -126,8 +189,191 @@ impl Node {
         }
     }
 
+    /// Sends a raw transaction through the execution rpc and tracks it until it settles. In
+    /// strict mode the transaction is validated against verified state first.
     pub async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
-        self.execution.send_raw_transaction(bytes).await
+        let hash = if self.config.strict_transactions {
+            self.check_head_age().await?;
+            send_validated_transaction(&self.execution, self.chain_id(), bytes).await?
+        } else {
+            self.execution.send_raw_transaction(bytes).await?
+        };
+
+        if let Err(err) = self.tracker.track(bytes).await {
+            warn!(target: "helios::client", "cannot track transaction {:?}: {}", hash, err);
+        }
+
+        Ok(hash)
+    }
+
+    pub async fn get_transaction_status(&self, hash: &H256) -> Option<TransactionStatus> {
+        self.tracker.status(hash).await
+    }
+
+    pub fn subscribe_transactions(&self) -> broadcast::Receiver<TransactionEvent> {
+        self.tracker.subscribe()
+    }
+
+    /// Waits until a transaction sent through helios meets `confirmation`, and returns its
+    /// verified receipt. Fails if the transaction is dropped or replaced first.
+    pub async fn wait_for_receipt(
+        &self,
+        hash: &H256,
+        confirmation: Confirmation,
+    ) -> Result<TransactionReceipt> {
+        self.tracker.wait(hash, confirmation).await?;
+
+        self.execution
+            .get_transaction_receipt(hash)
+            .await?
+            .ok_or(eyre!("missing receipt for transaction: {:?}", hash))
+    }
+
+    /// Returns the accounts of the configured signer.
+    pub fn get_accounts(&self) -> Vec<Address> {
+        self.signer.iter().map(Signer::address).collect()
+    }
+
+    pub fn sign(&self, address: &Address, message: &[u8]) -> Result<Signature> {
+        self.get_signer(address)?.sign_message(message)
+    }
+
+    /// Completes a transaction from verified data and signs it without sending it. The nonce
+    /// is not reserved, so the transaction is expected to be sent right away.
+    pub async fn sign_transaction(&self, opts: &TransactionOpts) -> Result<Bytes> {
+        let signer = self.get_signer(&self.get_sender(opts)?)?;
+        let mut tx = self.fill_transaction(opts).await?;
+
+        if tx.nonce().is_none() {
+            let nonce = self.get_nonce(&signer.address(), BlockTag::Latest).await?;
+            tx.set_nonce(signer.peek_nonce(nonce).await);
+        }
+
+        signer.sign_transaction(&tx)
+    }
+
+    /// Completes a transaction from verified data, signs it locally and broadcasts it through
+    /// the execution rpc. Nonces are reserved by the signer, so concurrent sends never reuse
+    /// one, and a nonce is handed back if its transaction could not be sent.
+    pub async fn send_transaction(&self, opts: &TransactionOpts) -> Result<H256> {
+        let signer = self.get_signer(&self.get_sender(opts)?)?;
+        let mut tx = self.fill_transaction(opts).await?;
+
+        let reserved = if tx.nonce().is_none() {
+            let nonce = self.get_nonce(&signer.address(), BlockTag::Latest).await?;
+            let nonce = signer.reserve_nonce(nonce).await;
+            tx.set_nonce(nonce);
+            Some(nonce)
+        } else {
+            None
+        };
+
+        let result = match signer.sign_transaction(&tx) {
+            Ok(raw_tx) => self.send_raw_transaction(&raw_tx).await,
+            Err(err) => Err(err),
+        };
+
+        if let (Err(_), Some(nonce)) = (&result, reserved) {
+            signer.release_nonce(nonce).await;
+        }
+
+        result
+    }
+
+    /// Fills in every field of a transaction except its nonce. Gas is estimated against the
+    /// latest verified block and fees come from the verified fee data. Transactions without
+    /// a gas price are sent as EIP-1559 transactions with a max fee of twice the latest base
+    /// fee plus the priority fee.
+    async fn fill_transaction(&self, opts: &TransactionOpts) -> Result<TypedTransaction> {
+        let from = self.get_sender(opts)?;
+
+        if let Some(chain_id) = opts.chain_id {
+            if chain_id.as_u64() != self.chain_id() {
+                return Err(eyre!(
+                    "invalid chain id: {}, expected: {}",
+                    chain_id,
+                    self.chain_id()
+                ));
+            }
+        }
+
+        let gas = match opts.gas {
+            Some(gas) => gas,
+            None => {
+                let call_opts = CallOpts {
+                    from: Some(from),
+                    to: opts.to,
+                    gas: None,
+                    gas_price: opts.gas_price,
+                    value: opts.value,
+                    data: opts.data.clone(),
+                };
+
+                self.estimate_gas(&call_opts).await?.into()
+            }
+        };
+
+        let mut tx: TypedTransaction = match (opts.gas_price, &opts.access_list) {
+            (Some(gas_price), None) => TransactionRequest::new().gas_price(gas_price).into(),
+            (Some(gas_price), Some(access_list)) => Eip2930TransactionRequest::new(
+                TransactionRequest::new().gas_price(gas_price),
+                access_list.clone(),
+            )
+            .into(),
+            (None, access_list) => {
+                let suggested_priority_fee = self.get_priority_fee().await?;
+                let max_priority_fee = match (opts.max_priority_fee_per_gas, opts.max_fee_per_gas) {
+                    (Some(max_priority_fee), _) => max_priority_fee,
+                    (None, Some(max_fee)) => suggested_priority_fee.min(max_fee),
+                    (None, None) => suggested_priority_fee,
+                };
+
+                let max_fee = match opts.max_fee_per_gas {
+                    Some(max_fee) => max_fee,
+                    None => {
+                        let block = self.execution.get_block(BlockTag::Latest, false).await?;
+                        block.base_fee_per_gas * 2 + max_priority_fee
+                    }
+                };
+
+                Eip1559TransactionRequest::new()
+                    .max_priority_fee_per_gas(max_priority_fee)
+                    .max_fee_per_gas(max_fee)
+                    .access_list(access_list.clone().unwrap_or_default())
+                    .into()
+            }
+        };
+
+        tx.set_from(from)
+            .set_gas(gas)
+            .set_value(opts.value.unwrap_or_default())
+            .set_data(opts.data.clone().unwrap_or_default())
+            .set_chain_id(self.chain_id());
+
+        if let Some(to) = opts.to {
+            tx.set_to(to);
+        }
+
+        if let Some(nonce) = opts.nonce {
+            tx.set_nonce(nonce);
+        }
+
+        Ok(tx)
+    }
+
+    fn get_sender(&self, opts: &TransactionOpts) -> Result<Address, NodeError> {
+        opts.from
+            .or(self.signer.as_ref().map(Signer::address))
+            .ok_or(NodeError::NoSigner)
+    }
+
+    fn get_signer(&self, address: &Address) -> Result<&Signer, NodeError> {
+        let signer = self.signer.as_ref().ok_or(NodeError::NoSigner)?;
+        if signer.address() != *address {
+            return Err(NodeError::UnknownAccount(*address));
+        }
+
+        Ok(signer)
     }
 
     pub async fn get_transaction_receipt(
@@ -137,7 +383,16 @@ impl Node {
         self.execution.get_transaction_receipt(tx_hash).await
     }
 
-    pub async fn get_transaction_by_hash(&self, tx_hash: &H256) -> Option<Transaction> {
+    pub async fn get_block_receipts(
+        &self,
+        block: BlockTag,
+    ) -> Result<Option<Vec<TransactionReceipt>>> {
+        self.check_blocktag_age(&block).await?;
+
+        self.execution.get_block_receipts(block).await
+    }
+
+    pub async fn get_transaction_by_hash(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
         self.execution.get_transaction(*tx_hash).await
     }
 
@@ -155,7 +410,7 @@ impl Node {
         self.execution.get_logs(filter).await
     }
 
-    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<Vec<Log>> {
+    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<FilterChanges> {
         self.execution.get_filter_changes(filter_id).await
     }
 
@@ -175,20 +430,41 @@ impl Node {
         self.execution.get_new_pending_transaction_filter().await
     }
 
-    // assumes tip of 1 gwei to prevent having to prove out every tx in the block
+    pub fn subscribe_new_heads(&self) -> broadcast::Receiver<Block> {
+        self.execution.subscribe_blocks()
+    }
+
+    pub fn subscribe_finalized_blocks(&self) -> broadcast::Receiver<Block> {
+        self.execution.subscribe_finalized_blocks()
+    }
+
+    pub async fn get_block_logs(&self, block: &Block, filter: &Filter) -> Result<Vec<Log>> {
+        self.execution.get_block_logs(block, filter).await
+    }
+
     pub async fn get_gas_price(&self) -> Result<U256> {
         self.check_head_age().await?;
 
-        let block = self.execution.get_block(BlockTag::Latest, false).await?;
-        let base_fee = block.base_fee_per_gas;
-        let tip = U256::from(10_u64.pow(9));
-        Ok(base_fee + tip)
+        self.execution.get_gas_price().await
     }
 
-    // assumes tip of 1 gwei to prevent having to prove out every tx in the block
-    pub fn get_priority_fee(&self) -> Result<U256> {
-        let tip = U256::from(10_u64.pow(9));
-        Ok(tip)
+    pub async fn get_priority_fee(&self) -> Result<U256> {
+        self.check_head_age().await?;
+
+        self.execution.get_priority_fee().await
+    }
+
+    pub async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: BlockTag,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        self.check_blocktag_age(&last_block).await?;
+
+        self.execution
+            .get_fee_history(block_count, last_block, reward_percentiles)
+            .await
     }
 
     pub async fn get_block_number(&self) -> Result<U256> {
@@ -207,6 +483,19 @@ impl Node {
         }
     }
 
+    /// Returns the RLP encoded header of a verified block, whose hash has been checked against
+    /// the block hash committed to by the beacon chain, or `None` if the block is not in the
+    /// block window.
+    pub async fn get_raw_header(&self, tag: BlockTag) -> Result<Option<Vec<u8>>> {
+        self.check_blocktag_age(&tag).await?;
+
+        match self.execution.get_block(tag, false).await {
+            Ok(block) => block.header_rlp().map(Some),
+            Err(err) if err.is::<BlockNotFoundError>() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     pub async fn get_block_by_hash(&self, hash: &H256, full_tx: bool) -> Result<Option<Block>> {
         let block = self.execution.get_block_by_hash(*hash, full_tx).await;
 
@@ -283,9 +572,311 @@ impl Node {
 
     async fn check_blocktag_age(&self, block: &BlockTag) -> Result<(), NodeError> {
         match block {
-            BlockTag::Latest => self.check_head_age().await,
-            BlockTag::Finalized => Ok(()),
-            BlockTag::Number(_) => Ok(()),
+            BlockTag::Latest | BlockTag::Pending => self.check_head_age().await,
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Validates a raw transaction against verified state and sends it through the execution rpc.
+/// Transactions that fail validation are never sent.
+async fn send_validated_transaction<R: ExecutionRpc>(
+    execution: &Arc<ExecutionClient<R>>,
+    chain_id: u64,
+    bytes: &[u8],
+) -> Result<H256> {
+    let tx = decode_transaction(bytes).map_err(TransactionError::InvalidTransaction)?;
+    validate_transaction(execution, chain_id, &tx).await?;
+    execution.send_raw_transaction(bytes).await
+}
+
+/// Checks a transaction against the verified state of the latest block: its chain id,
+/// fees and gas limit, that its nonce has not been used yet, and that its sender can pay
+/// for its maximum cost. The transaction is then executed locally and rejected if it
+/// fails. Contract creations are not executed.
+async fn validate_transaction<R: ExecutionRpc>(
+    execution: &Arc<ExecutionClient<R>>,
+    chain_id: u64,
+    tx: &Transaction,
+) -> Result<()> {
+    if let Some(found) = tx.chain_id {
+        if found.as_u64() != chain_id {
+            return Err(TransactionError::InvalidChainId {
+                expected: chain_id,
+                found: found.as_u64(),
+            }
+            .into());
+        }
+    }
+
+    if let (Some(max_fee), Some(max_priority_fee)) =
+        (tx.max_fee_per_gas, tx.max_priority_fee_per_gas)
+    {
+        if max_priority_fee > max_fee {
+            return Err(TransactionError::TipAboveFeeCap {
+                max_priority_fee,
+                max_fee,
+            }
+            .into());
+        }
+    }
+
+    let block = execution.get_block(BlockTag::Latest, false).await?;
+    let gas_limit = block.gas_limit.as_u64();
+    if tx.gas > U256::from(gas_limit) {
+        return Err(TransactionError::GasLimitExceeded {
+            gas: tx.gas,
+            limit: gas_limit,
+        }
+        .into());
+    }
+
+    let account = execution
+        .get_account(&tx.from, None, BlockTag::Latest)
+        .await?;
+
+    if tx.nonce < U256::from(account.nonce) {
+        return Err(TransactionError::NonceTooLow {
+            address: tx.from,
+            nonce: tx.nonce,
+            expected: account.nonce,
+        }
+        .into());
+    }
+
+    let fee_cap = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+    let (max_fee_per_blob_gas, blob_versioned_hashes) = blob_transaction_fields(tx);
+    let blob_gas = U256::from(GAS_PER_BLOB) * blob_versioned_hashes.len();
+    let cost = tx
+        .gas
+        .saturating_mul(fee_cap)
+        .saturating_add(blob_gas.saturating_mul(max_fee_per_blob_gas))
+        .saturating_add(tx.value);
+
+    if account.balance < cost {
+        return Err(TransactionError::InsufficientFunds {
+            address: tx.from,
+            balance: account.balance,
+            cost,
         }
+        .into());
+    }
+
+    if let Some(to) = tx.to {
+        let opts = CallOpts {
+            from: Some(tx.from),
+            to: Some(to),
+            gas: Some(tx.gas),
+            gas_price: None,
+            value: Some(tx.value),
+            data: Some(tx.input.clone()),
+        };
+
+        let mut evm = Evm::new(execution.clone(), chain_id, BlockTag::Latest);
+        evm.call(&opts)
+            .await
+            .map_err(TransactionError::SimulationFailed)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ethers::signers::{LocalWallet, Signer as _};
+    use execution::errors::EvmError;
+    use execution::rpc::mock_rpc::MockRpc;
+    use tokio::sync::{mpsc::channel, watch};
+
+    use super::*;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn proof_address() -> Address {
+        Address::from_str("14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap()
+    }
+
+    async fn create_client() -> Arc<ExecutionClient<MockRpc>> {
+        let (_, block_recv) = channel(256);
+        let (_, finalized_recv) = watch::channel(None);
+        let (_, safe_recv) = watch::channel(None);
+        let state = State::new(block_recv, finalized_recv, safe_recv, 64);
+
+        // the mock serves the proof of the sender against this state root
+        state
+            .push_block(Block {
+                number: 7530933.into(),
+                state_root: H256::from_str(
+                    "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+                )
+                .unwrap(),
+                gas_limit: 30_000_000.into(),
+                miner: proof_address(),
+                ..Default::default()
+            })
+            .await;
+
+        Arc::new(ExecutionClient::new("../execution/testdata/", state).unwrap())
+    }
+
+    /// A transaction from the account proven by the mock, which holds 0x48c27395000 wei at
+    /// nonce 1.
+    fn transaction() -> Transaction {
+        Transaction {
+            from: proof_address(),
+            to: Some(proof_address()),
+            nonce: 1.into(),
+            gas: 21_000.into(),
+            max_fee_per_gas: Some(1.into()),
+            max_priority_fee_per_gas: Some(1.into()),
+            chain_id: Some(1.into()),
+            ..Default::default()
+        }
+    }
+
+    fn sign(tx: Eip1559TransactionRequest) -> Vec<u8> {
+        let wallet = KEY.parse::<LocalWallet>().unwrap();
+        let tx = TypedTransaction::Eip1559(tx.from(wallet.address()));
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        tx.rlp_signed(&signature).to_vec()
+    }
+
+    async fn validation_error(tx: &Transaction) -> TransactionError {
+        let execution = create_client().await;
+        let err = validate_transaction(&execution, 1, tx).await.unwrap_err();
+        err.downcast::<TransactionError>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validate_transaction() {
+        let execution = create_client().await;
+
+        // contract creations are checked against the sender but not executed
+        let tx = Transaction {
+            to: None,
+            ..transaction()
+        };
+
+        validate_transaction(&execution, 1, &tx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validate_invalid_chain_id() {
+        let tx = Transaction {
+            chain_id: Some(5.into()),
+            ..transaction()
+        };
+
+        assert!(matches!(
+            validation_error(&tx).await,
+            TransactionError::InvalidChainId {
+                expected: 1,
+                found: 5
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_tip_above_fee_cap() {
+        let tx = Transaction {
+            max_priority_fee_per_gas: Some(2.into()),
+            ..transaction()
+        };
+
+        assert!(matches!(
+            validation_error(&tx).await,
+            TransactionError::TipAboveFeeCap { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_gas_limit_exceeded() {
+        let tx = Transaction {
+            gas: 30_000_001.into(),
+            ..transaction()
+        };
+
+        assert!(matches!(
+            validation_error(&tx).await,
+            TransactionError::GasLimitExceeded {
+                limit: 30_000_000,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_nonce_too_low() {
+        let tx = Transaction {
+            nonce: 0.into(),
+            ..transaction()
+        };
+
+        assert!(matches!(
+            validation_error(&tx).await,
+            TransactionError::NonceTooLow { expected: 1, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_insufficient_funds() {
+        let tx = Transaction {
+            max_fee_per_gas: Some(1_000_000_000.into()),
+            ..transaction()
+        };
+
+        match validation_error(&tx).await {
+            TransactionError::InsufficientFunds { balance, cost, .. } => {
+                assert_eq!(balance, U256::from_str_radix("48c27395000", 16).unwrap());
+                assert_eq!(cost, U256::from(21_000_000_000_000_u64));
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_simulation_failed() {
+        // the intrinsic gas leaves nothing to run the code of the recipient
+        let tx = transaction();
+
+        assert!(matches!(
+            validation_error(&tx).await,
+            TransactionError::SimulationFailed(EvmError::Revert(None))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_transaction_not_sent() {
+        let execution = create_client().await;
+
+        let wrong_chain = sign(
+            Eip1559TransactionRequest::new()
+                .to(proof_address())
+                .chain_id(5_u64)
+                .gas(21_000_u64)
+                .max_fee_per_gas(1_u64)
+                .max_priority_fee_per_gas(1_u64),
+        );
+
+        let tip_above_cap = sign(
+            Eip1559TransactionRequest::new()
+                .to(proof_address())
+                .chain_id(1_u64)
+                .gas(21_000_u64)
+                .max_fee_per_gas(1_u64)
+                .max_priority_fee_per_gas(2_u64),
+        );
+
+        for raw in [wrong_chain, tip_above_cap] {
+            let err = send_validated_transaction(&execution, 1, &raw)
+                .await
+                .unwrap_err();
+
+            assert!(err.downcast_ref::<TransactionError>().is_some());
+        }
+
+        assert!(execution.rpc.sent_transactions().is_empty());
     }
 }
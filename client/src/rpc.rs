@@ -1,30 +1,47 @@
 use ethers::{
     abi::AbiEncode,
-    types::{Address, Filter, Log, SyncingStatus, Transaction, TransactionReceipt, H256, U256},
+    types::{
+        Address, Bytes, EIP1186ProofResponse, FeeHistory, Filter, Log, SyncingStatus, Transaction,
+        TransactionReceipt, H256, U256, U64,
+    },
 };
 use eyre::Result;
+use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr};
 use std::{fmt::Display, net::SocketAddr, str::FromStr, sync::Arc};
-use tracing::info;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
 
 use jsonrpsee::{
-    core::{async_trait, server::rpc_module::Methods, Error},
+    core::{
+        async_trait,
+        server::rpc_module::{Methods, PendingSubscription},
+        Error,
+    },
     http_server::{HttpServerBuilder, HttpServerHandle},
     proc_macros::rpc,
+    ws_server::{WsServerBuilder, WsServerHandle},
 };
 
 use crate::{errors::NodeError, node::Node};
 
 use common::{
-    types::{Block, BlockTag},
+    types::{Block, BlockTag, Transactions},
     utils::{hex_str_to_bytes, u64_to_hex_string},
 };
-use execution::types::CallOpts;
+use execution::fork::Fork;
+use execution::rpc::{any_rpc::AnyRpc, multi_rpc::MultiRpc};
+use execution::types::{
+    CallOpts, FilterChanges, SimulatedBlock, SimulationPayload, TransactionOpts,
+};
 
 pub struct Rpc {
     node: Arc<Node>,
     handle: Option<HttpServerHandle>,
+    ws_handle: Option<WsServerHandle>,
     address: SocketAddr,
+    ws_address: Option<SocketAddr>,
 }
 
 impl Rpc {
@@ -36,21 +53,37 @@ impl Rpc {
         Rpc {
             node,
             handle: None,
+            ws_handle: None,
             address,
+            ws_address: None,
         }
     }
 
+    /// Also serves the rpc over a WebSocket on `port`, which adds `eth_subscribe` support.
+    pub fn with_ws_port(mut self, port: u16) -> Self {
+        self.ws_address = Some(SocketAddr::new(self.address.ip(), port));
+        self
+    }
+
     pub async fn start(&mut self) -> Result<SocketAddr> {
         let rpc_inner = RpcInner {
             node: self.node.clone(),
             address: self.address,
+            fork: self.node.config.fork.then(|| Arc::new(OnceCell::new())),
         };
 
-        let (handle, addr) = start(rpc_inner).await?;
+        let (handle, addr) = start(rpc_inner.clone()).await?;
         self.handle = Some(handle);
 
         info!(target: "helios::rpc", "rpc server started at {}", addr);
 
+        if let Some(ws_address) = self.ws_address {
+            let (ws_handle, ws_addr) = start_ws(rpc_inner, ws_address).await?;
+            self.ws_handle = Some(ws_handle);
+
+            info!(target: "helios::rpc", "ws rpc server started at {}", ws_addr);
+        }
+
         Ok(addr)
     }
 }
@@ -72,12 +105,25 @@ trait EthRpc {
     async fn call(&self, opts: CallOpts, block: BlockTag) -> Result<String, Error>;
     #[method(name = "estimateGas")]
     async fn estimate_gas(&self, opts: CallOpts) -> Result<String, Error>;
+    #[method(name = "simulateV1")]
+    async fn simulate_v1(
+        &self,
+        payload: SimulationPayload,
+        block: BlockTag,
+    ) -> Result<Vec<SimulatedBlock>, Error>;
     #[method(name = "chainId")]
     async fn chain_id(&self) -> Result<String, Error>;
     #[method(name = "gasPrice")]
     async fn gas_price(&self) -> Result<String, Error>;
     #[method(name = "maxPriorityFeePerGas")]
     async fn max_priority_fee_per_gas(&self) -> Result<String, Error>;
+    #[method(name = "feeHistory")]
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        last_block: BlockTag,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> Result<FeeHistory, Error>;
     #[method(name = "blockNumber")]
     async fn block_number(&self) -> Result<String, Error>;
     #[method(name = "getBlockByNumber")]
@@ -90,11 +136,24 @@ trait EthRpc {
     async fn get_block_by_hash(&self, hash: H256, full_tx: bool) -> Result<Option<Block>, Error>;
     #[method(name = "sendRawTransaction")]
     async fn send_raw_transaction(&self, bytes: &str) -> Result<String, Error>;
+    #[method(name = "sendTransaction")]
+    async fn send_transaction(&self, opts: TransactionOpts) -> Result<H256, Error>;
+    #[method(name = "signTransaction")]
+    async fn sign_transaction(&self, opts: TransactionOpts) -> Result<Bytes, Error>;
+    #[method(name = "sign")]
+    async fn sign(&self, address: Address, message: Bytes) -> Result<Bytes, Error>;
+    #[method(name = "accounts")]
+    async fn accounts(&self) -> Result<Vec<Address>, Error>;
     #[method(name = "getTransactionReceipt")]
     async fn get_transaction_receipt(
         &self,
         hash: H256,
     ) -> Result<Option<TransactionReceipt>, Error>;
+    #[method(name = "getBlockReceipts")]
+    async fn get_block_receipts(
+        &self,
+        block: BlockTag,
+    ) -> Result<Option<Vec<TransactionReceipt>>, Error>;
     #[method(name = "getTransactionByHash")]
     async fn get_transaction_by_hash(&self, hash: H256) -> Result<Option<Transaction>, Error>;
     #[method(name = "getTransactionByBlockHashAndIndex")]
@@ -106,14 +165,14 @@ trait EthRpc {
     #[method(name = "getLogs")]
     async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>, Error>;
     #[method(name = "getFilterChanges")]
-    async fn get_filter_changes(&self, filter_id: U256) -> Result<Vec<Log>, Error>;
+    async fn get_filter_changes(&self, filter_id: U256) -> Result<FilterChanges, Error>;
     #[method(name = "uninstallFilter")]
     async fn uninstall_filter(&self, filter_id: U256) -> Result<bool, Error>;
-    #[method(name = "getNewFilter")]
+    #[method(name = "newFilter")]
     async fn get_new_filter(&self, filter: Filter) -> Result<U256, Error>;
-    #[method(name = "getNewBlockFilter")]
+    #[method(name = "newBlockFilter")]
     async fn get_new_block_filter(&self) -> Result<U256, Error>;
-    #[method(name = "getNewPendingTransactionFilter")]
+    #[method(name = "newPendingTransactionFilter")]
     async fn get_new_pending_transaction_filter(&self) -> Result<U256, Error>;
     #[method(name = "getStorageAt")]
     async fn get_storage_at(
@@ -122,12 +181,68 @@ trait EthRpc {
         slot: H256,
         block: BlockTag,
     ) -> Result<String, Error>;
+    #[method(name = "getProof")]
+    async fn get_proof(
+        &self,
+        address: &str,
+        slots: Vec<H256>,
+        block: BlockTag,
+    ) -> Result<EIP1186ProofResponse, Error>;
     #[method(name = "coinbase")]
     async fn coinbase(&self) -> Result<Address, Error>;
     #[method(name = "syncing")]
     async fn syncing(&self) -> Result<SyncingStatus, Error>;
 }
 
+#[rpc(server, namespace = "eth")]
+trait EthPubSub {
+    #[subscription(
+        name = "subscribe" => "subscription",
+        unsubscribe = "unsubscribe",
+        item = SubscriptionItem
+    )]
+    fn subscribe(&self, kind: SubscriptionKind, filter: Option<Filter>);
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum SubscriptionKind {
+    NewHeads,
+    Logs,
+    Finalized,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SubscriptionItem {
+    Block(Box<Block>),
+    Log(Log),
+}
+
+#[rpc(server, namespace = "debug")]
+trait DebugRpc {
+    #[method(name = "getRawHeader")]
+    async fn get_raw_header(&self, block: BlockTag) -> Result<Option<Bytes>, Error>;
+}
+
+#[rpc(server, namespace = "evm")]
+trait EvmRpc {
+    #[method(name = "snapshot")]
+    async fn snapshot(&self) -> Result<U256, Error>;
+    #[method(name = "revert")]
+    async fn revert(&self, id: U256) -> Result<bool, Error>;
+    #[method(name = "mine")]
+    async fn mine(&self) -> Result<String, Error>;
+}
+
+#[rpc(server, namespace = "anvil")]
+trait AnvilRpc {
+    #[method(name = "impersonateAccount")]
+    async fn impersonate_account(&self, address: Address) -> Result<(), Error>;
+    #[method(name = "stopImpersonatingAccount")]
+    async fn stop_impersonating_account(&self, address: Address) -> Result<(), Error>;
+}
+
 #[rpc(client, server, namespace = "net")]
 trait NetRpc {
     #[method(name = "version")]
@@ -138,20 +253,66 @@ trait NetRpc {
 struct RpcInner {
     node: Arc<Node>,
     address: SocketAddr,
+    fork: Option<Arc<OnceCell<Fork<MultiRpc<AnyRpc>>>>>,
+}
+
+impl RpcInner {
+    /// Returns the local fork in fork mode. The fork is pinned to the latest verified block
+    /// when it is first used, and the accounts of the signer are impersonated.
+    async fn fork(&self) -> Result<Option<&Fork<MultiRpc<AnyRpc>>>, Error> {
+        let fork = match &self.fork {
+            Some(fork) => fork,
+            None => return Ok(None),
+        };
+
+        let fork = fork
+            .get_or_try_init(|| async {
+                let execution = self.node.execution.clone();
+                let fork = Fork::new(execution, self.node.chain_id(), BlockTag::Latest).await?;
+                for account in self.node.get_accounts() {
+                    fork.impersonate(account).await;
+                }
+
+                Ok::<_, eyre::Report>(fork)
+            })
+            .await;
+
+        convert_err(fork).map(Some)
+    }
+
+    /// Returns the local fork if it serves `block`.
+    async fn fork_at(&self, block: BlockTag) -> Result<Option<&Fork<MultiRpc<AnyRpc>>>, Error> {
+        match self.fork().await? {
+            Some(fork) if fork.contains(block).await => Ok(Some(fork)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn require_fork(&self) -> Result<&Fork<MultiRpc<AnyRpc>>, Error> {
+        self.fork()
+            .await?
+            .ok_or(Error::Custom("fork mode is disabled".to_string()))
+    }
 }
 
 #[async_trait]
 impl EthRpcServer for RpcInner {
     async fn get_balance(&self, address: &str, block: BlockTag) -> Result<String, Error> {
         let address = convert_err(Address::from_str(address))?;
-        let balance = convert_err(self.node.get_balance(&address, block).await)?;
+        let balance = match self.fork_at(block).await? {
+            Some(fork) => convert_err(fork.get_balance(&address, block).await)?,
+            None => convert_err(self.node.get_balance(&address, block).await)?,
+        };
 
         Ok(format_hex(&balance))
     }
 
     async fn get_transaction_count(&self, address: &str, block: BlockTag) -> Result<String, Error> {
         let address = convert_err(Address::from_str(address))?;
-        let nonce = convert_err(self.node.get_nonce(&address, block).await)?;
+        let nonce = match self.fork_at(block).await? {
+            Some(fork) => convert_err(fork.get_nonce(&address, block).await)?,
+            None => convert_err(self.node.get_nonce(&address, block).await)?,
+        };
 
         Ok(format!("0x{nonce:x}"))
     }
@@ -173,31 +334,57 @@ impl EthRpcServer for RpcInner {
 
     async fn get_code(&self, address: &str, block: BlockTag) -> Result<String, Error> {
         let address = convert_err(Address::from_str(address))?;
-        let code = convert_err(self.node.get_code(&address, block).await)?;
+        let code = match self.fork_at(block).await? {
+            Some(fork) => convert_err(fork.get_code(&address, block).await)?,
+            None => convert_err(self.node.get_code(&address, block).await)?,
+        };
 
         Ok(format!("0x{:}", hex::encode(code)))
     }
 
     async fn call(&self, opts: CallOpts, block: BlockTag) -> Result<String, Error> {
-        let res = self
-            .node
-            .call(&opts, block)
-            .await
-            .map_err(NodeError::to_json_rpsee_error)?;
+        let res = match self.fork_at(block).await? {
+            Some(fork) => fork
+                .call(&opts, block)
+                .await
+                .map_err(|err| NodeError::ExecutionEvmError(err).to_json_rpsee_error())?,
+            None => self
+                .node
+                .call(&opts, block)
+                .await
+                .map_err(NodeError::to_json_rpsee_error)?,
+        };
 
         Ok(format!("0x{}", hex::encode(res)))
     }
 
     async fn estimate_gas(&self, opts: CallOpts) -> Result<String, Error> {
-        let gas = self
-            .node
-            .estimate_gas(&opts)
-            .await
-            .map_err(NodeError::to_json_rpsee_error)?;
+        let gas = match self.fork().await? {
+            Some(fork) => fork
+                .estimate_gas(&opts)
+                .await
+                .map_err(|err| NodeError::ExecutionEvmError(err).to_json_rpsee_error())?,
+            None => self
+                .node
+                .estimate_gas(&opts)
+                .await
+                .map_err(NodeError::to_json_rpsee_error)?,
+        };
 
         Ok(u64_to_hex_string(gas))
     }
 
+    async fn simulate_v1(
+        &self,
+        payload: SimulationPayload,
+        block: BlockTag,
+    ) -> Result<Vec<SimulatedBlock>, Error> {
+        self.node
+            .simulate(&payload.block_state_calls, block)
+            .await
+            .map_err(NodeError::to_json_rpsee_error)
+    }
+
     async fn chain_id(&self) -> Result<String, Error> {
         let id = self.node.chain_id();
         Ok(u64_to_hex_string(id))
@@ -209,11 +396,31 @@ impl EthRpcServer for RpcInner {
     }
 
     async fn max_priority_fee_per_gas(&self) -> Result<String, Error> {
-        let tip = convert_err(self.node.get_priority_fee())?;
+        let tip = convert_err(self.node.get_priority_fee().await)?;
         Ok(format_hex(&tip))
     }
 
+    async fn fee_history(
+        &self,
+        block_count: U64,
+        last_block: BlockTag,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> Result<FeeHistory, Error> {
+        let reward_percentiles = reward_percentiles.unwrap_or_default();
+        let fee_history = convert_err(
+            self.node
+                .get_fee_history(block_count.as_u64(), last_block, &reward_percentiles)
+                .await,
+        )?;
+
+        Ok(fee_history)
+    }
+
     async fn block_number(&self) -> Result<String, Error> {
+        if let Some(fork) = self.fork().await? {
+            return Ok(u64_to_hex_string(fork.block_number().await));
+        }
+
         let num = convert_err(self.node.get_block_number().await)?;
         Ok(u64_to_hex_string(num.as_u64()))
     }
@@ -223,31 +430,88 @@ impl EthRpcServer for RpcInner {
         block: BlockTag,
         full_tx: bool,
     ) -> Result<Option<Block>, Error> {
+        if let Some(fork) = self.fork_at(block).await? {
+            return convert_err(fork.get_block(block, full_tx).await);
+        }
+
         let block = convert_err(self.node.get_block_by_number(block, full_tx).await)?;
         Ok(block)
     }
 
     async fn get_block_by_hash(&self, hash: H256, full_tx: bool) -> Result<Option<Block>, Error> {
+        if let Some(fork) = self.fork_at(BlockTag::Hash(hash)).await? {
+            return convert_err(fork.get_block_by_hash(hash, full_tx).await);
+        }
+
         let block = convert_err(self.node.get_block_by_hash(&hash, full_tx).await)?;
         Ok(block)
     }
 
     async fn send_raw_transaction(&self, bytes: &str) -> Result<String, Error> {
         let bytes = convert_err(hex_str_to_bytes(bytes))?;
-        let tx_hash = convert_err(self.node.send_raw_transaction(&bytes).await)?;
+        let tx_hash = match self.fork().await? {
+            Some(fork) => convert_err(fork.send_raw_transaction(&bytes).await)?,
+            None => convert_err(self.node.send_raw_transaction(&bytes).await)?,
+        };
+
         Ok(hex::encode(tx_hash))
     }
 
+    async fn send_transaction(&self, mut opts: TransactionOpts) -> Result<H256, Error> {
+        if let Some(fork) = self.fork().await? {
+            opts.from = opts.from.or(self.node.get_accounts().first().copied());
+            return convert_err(fork.send_transaction(&opts).await);
+        }
+
+        let tx_hash = convert_err(self.node.send_transaction(&opts).await)?;
+        Ok(tx_hash)
+    }
+
+    async fn sign_transaction(&self, opts: TransactionOpts) -> Result<Bytes, Error> {
+        let raw_tx = convert_err(self.node.sign_transaction(&opts).await)?;
+        Ok(raw_tx)
+    }
+
+    async fn sign(&self, address: Address, message: Bytes) -> Result<Bytes, Error> {
+        let signature = convert_err(self.node.sign(&address, &message))?;
+        Ok(signature.to_vec().into())
+    }
+
+    async fn accounts(&self) -> Result<Vec<Address>, Error> {
+        Ok(self.node.get_accounts())
+    }
+
     async fn get_transaction_receipt(
         &self,
         hash: H256,
     ) -> Result<Option<TransactionReceipt>, Error> {
+        if let Some(fork) = self.fork().await? {
+            if let Some(receipt) = fork.get_transaction_receipt(&hash).await {
+                return Ok(Some(receipt));
+            }
+        }
+
         let receipt = convert_err(self.node.get_transaction_receipt(&hash).await)?;
         Ok(receipt)
     }
 
+    async fn get_block_receipts(
+        &self,
+        block: BlockTag,
+    ) -> Result<Option<Vec<TransactionReceipt>>, Error> {
+        let receipts = convert_err(self.node.get_block_receipts(block).await)?;
+        Ok(receipts)
+    }
+
     async fn get_transaction_by_hash(&self, hash: H256) -> Result<Option<Transaction>, Error> {
-        Ok(self.node.get_transaction_by_hash(&hash).await)
+        if let Some(fork) = self.fork().await? {
+            if let Some(tx) = fork.get_transaction(&hash).await {
+                return Ok(Some(tx));
+            }
+        }
+
+        let tx = convert_err(self.node.get_transaction_by_hash(&hash).await)?;
+        Ok(tx)
     }
 
     async fn get_transaction_by_block_hash_and_index(
@@ -273,7 +537,7 @@ impl EthRpcServer for RpcInner {
         convert_err(self.node.get_logs(&filter).await)
     }
 
-    async fn get_filter_changes(&self, filter_id: U256) -> Result<Vec<Log>, Error> {
+    async fn get_filter_changes(&self, filter_id: U256) -> Result<FilterChanges, Error> {
         convert_err(self.node.get_filter_changes(&filter_id).await)
     }
 
@@ -300,10 +564,126 @@ impl EthRpcServer for RpcInner {
         block: BlockTag,
     ) -> Result<String, Error> {
         let address = convert_err(Address::from_str(address))?;
-        let storage = convert_err(self.node.get_storage_at(&address, slot, block).await)?;
+        let storage = match self.fork_at(block).await? {
+            Some(fork) => convert_err(fork.get_storage_at(&address, slot, block).await)?,
+            None => convert_err(self.node.get_storage_at(&address, slot, block).await)?,
+        };
 
         Ok(format_hex(&storage))
     }
+
+    async fn get_proof(
+        &self,
+        address: &str,
+        slots: Vec<H256>,
+        block: BlockTag,
+    ) -> Result<EIP1186ProofResponse, Error> {
+        let address = convert_err(Address::from_str(address))?;
+        let proof = convert_err(self.node.get_proof(&address, &slots, block).await)?;
+
+        Ok(proof)
+    }
+}
+
+impl EthPubSubServer for RpcInner {
+    fn subscribe(
+        &self,
+        pending: PendingSubscription,
+        kind: SubscriptionKind,
+        filter: Option<Filter>,
+    ) {
+        let node = self.node.clone();
+        let mut blocks = match kind {
+            SubscriptionKind::NewHeads | SubscriptionKind::Logs => node.subscribe_new_heads(),
+            SubscriptionKind::Finalized => node.subscribe_finalized_blocks(),
+        };
+
+        let mut sink = match pending.accept() {
+            Some(sink) => sink,
+            None => return,
+        };
+
+        let filter = filter.unwrap_or_default();
+
+        tokio::spawn(async move {
+            loop {
+                let mut block = match blocks.recv().await {
+                    Ok(block) => block,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(target: "helios::rpc", "subscriber skipped {} blocks", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let items = if kind == SubscriptionKind::Logs {
+                    // logs are read from the verified receipts of the block, so nothing
+                    // unproven is ever pushed to subscribers
+                    match node.get_block_logs(&block, &filter).await {
+                        Ok(logs) => logs.into_iter().map(SubscriptionItem::Log).collect(),
+                        Err(err) => {
+                            warn!(
+                                target: "helios::rpc",
+                                "could not verify logs of block {}: {}",
+                                block.number,
+                                err
+                            );
+
+                            continue;
+                        }
+                    }
+                } else {
+                    block.transactions = Transactions::Hashes(block.transactions.hashes());
+                    vec![SubscriptionItem::Block(Box::new(block))]
+                };
+
+                // stop once the subscriber has unsubscribed or disconnected
+                if !items.iter().all(|item| matches!(sink.send(item), Ok(true))) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl DebugRpcServer for RpcInner {
+    async fn get_raw_header(&self, block: BlockTag) -> Result<Option<Bytes>, Error> {
+        let header = convert_err(self.node.get_raw_header(block).await)?;
+        Ok(header.map(Bytes::from))
+    }
+}
+
+#[async_trait]
+impl EvmRpcServer for RpcInner {
+    async fn snapshot(&self) -> Result<U256, Error> {
+        Ok(self.require_fork().await?.snapshot().await)
+    }
+
+    async fn revert(&self, id: U256) -> Result<bool, Error> {
+        Ok(self.require_fork().await?.revert(id).await)
+    }
+
+    async fn mine(&self) -> Result<String, Error> {
+        self.require_fork().await?.mine().await;
+        Ok("0x0".to_string())
+    }
+}
+
+#[async_trait]
+impl AnvilRpcServer for RpcInner {
+    async fn impersonate_account(&self, address: Address) -> Result<(), Error> {
+        self.require_fork().await?.impersonate(address).await;
+        Ok(())
+    }
+
+    async fn stop_impersonating_account(&self, address: Address) -> Result<(), Error> {
+        self.require_fork()
+            .await?
+            .stop_impersonating(&address)
+            .await;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -319,16 +699,54 @@ async fn start(rpc: RpcInner) -> Result<(HttpServerHandle, SocketAddr)> {
 
     let mut methods = Methods::new();
     let eth_methods: Methods = EthRpcServer::into_rpc(rpc.clone()).into();
-    let net_methods: Methods = NetRpcServer::into_rpc(rpc).into();
+    let debug_methods: Methods = DebugRpcServer::into_rpc(rpc.clone()).into();
+    let net_methods: Methods = NetRpcServer::into_rpc(rpc.clone()).into();
 
     methods.merge(eth_methods)?;
+    methods.merge(debug_methods)?;
     methods.merge(net_methods)?;
+    merge_fork_methods(&mut methods, rpc)?;
 
     let handle = server.start(methods)?;
 
     Ok((handle, addr))
 }
 
+async fn start_ws(rpc: RpcInner, address: SocketAddr) -> Result<(WsServerHandle, SocketAddr)> {
+    let server = WsServerBuilder::default().build(address).await?;
+    let addr = server.local_addr()?;
+
+    let mut methods = Methods::new();
+    let eth_methods: Methods = EthRpcServer::into_rpc(rpc.clone()).into();
+    let pubsub_methods: Methods = EthPubSubServer::into_rpc(rpc.clone()).into();
+    let debug_methods: Methods = DebugRpcServer::into_rpc(rpc.clone()).into();
+    let net_methods: Methods = NetRpcServer::into_rpc(rpc.clone()).into();
+
+    methods.merge(eth_methods)?;
+    methods.merge(pubsub_methods)?;
+    methods.merge(debug_methods)?;
+    methods.merge(net_methods)?;
+    merge_fork_methods(&mut methods, rpc)?;
+
+    let handle = server.start(methods)?;
+
+    Ok((handle, addr))
+}
+
+fn merge_fork_methods(methods: &mut Methods, rpc: RpcInner) -> Result<()> {
+    if rpc.fork.is_none() {
+        return Ok(());
+    }
+
+    let evm_methods: Methods = EvmRpcServer::into_rpc(rpc.clone()).into();
+    let anvil_methods: Methods = AnvilRpcServer::into_rpc(rpc).into();
+
+    methods.merge(evm_methods)?;
+    methods.merge(anvil_methods)?;
+
+    Ok(())
+}
+
 fn convert_err<T, E: Display>(res: Result<T, E>) -> Result<T, Error> {
     res.map_err(|err| Error::Custom(err.to_string()))
 }
@@ -0,0 +1,118 @@
+use ethers::signers::{LocalWallet, Signer as _};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, Signature};
+use ethers::utils::hash_message;
+use eyre::{eyre, Result};
+use tokio::sync::Mutex;
+
+use config::SignerConfig;
+
+/// Signs messages and transactions with a local key, and hands out the nonces of the
+/// transactions it sends.
+pub struct Signer {
+    wallet: LocalWallet,
+    next_nonce: Mutex<Option<u64>>,
+}
+
+impl Signer {
+    pub fn new(config: &SignerConfig, chain_id: u64) -> Result<Self> {
+        let wallet = match config {
+            SignerConfig::PrivateKey(key) => key.parse::<LocalWallet>()?,
+            #[cfg(not(target_arch = "wasm32"))]
+            SignerConfig::Keystore { path, password } => {
+                LocalWallet::decrypt_keystore(path, password)?
+            }
+            #[cfg(target_arch = "wasm32")]
+            SignerConfig::Keystore { .. } => {
+                return Err(eyre!("keystores are not supported in the browser"))
+            }
+        };
+
+        Ok(Self {
+            wallet: wallet.with_chain_id(chain_id),
+            next_nonce: Mutex::new(None),
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Signs a message with the `\x19Ethereum Signed Message` prefix, as `eth_sign` does.
+    pub fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.wallet.sign_hash(hash_message(message))?)
+    }
+
+    /// Signs a transaction and returns its raw encoding, ready to be broadcast.
+    pub fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Bytes> {
+        if matches!(tx.from(), Some(from) if *from != self.address()) {
+            return Err(eyre!("transaction is not sent by the signer"));
+        }
+
+        let signature = self.wallet.sign_transaction_sync(tx)?;
+        Ok(tx.rlp_signed(&signature))
+    }
+
+    /// Returns the nonce of the next transaction without reserving it. Transactions sent
+    /// through the signer are counted even before they are included in a verified block.
+    pub async fn peek_nonce(&self, verified_nonce: u64) -> u64 {
+        let next_nonce = self.next_nonce.lock().await;
+        next_nonce.map_or(verified_nonce, |nonce| nonce.max(verified_nonce))
+    }
+
+    /// Reserves the nonce of the next transaction. Concurrent callers always receive distinct
+    /// nonces, even while the verified nonce lags behind the transactions already sent.
+    pub async fn reserve_nonce(&self, verified_nonce: u64) -> u64 {
+        let mut next_nonce = self.next_nonce.lock().await;
+        let nonce = next_nonce.map_or(verified_nonce, |nonce| nonce.max(verified_nonce));
+        *next_nonce = Some(nonce + 1);
+        nonce
+    }
+
+    /// Returns a reserved nonce whose transaction could not be sent, so the next transaction
+    /// reuses it. Nonces reserved after it are left untouched.
+    pub async fn release_nonce(&self, nonce: u64) {
+        let mut next_nonce = self.next_nonce.lock().await;
+        if *next_nonce == Some(nonce + 1) {
+            *next_nonce = Some(nonce);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn signer() -> Signer {
+        Signer::new(&SignerConfig::PrivateKey(KEY.to_string()), 1).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_nonces() {
+        let signer = signer();
+
+        assert_eq!(signer.reserve_nonce(5).await, 5);
+        assert_eq!(signer.reserve_nonce(5).await, 6);
+        assert_eq!(signer.peek_nonce(5).await, 7);
+
+        // the verified nonce catches up with transactions sent elsewhere
+        assert_eq!(signer.reserve_nonce(9).await, 9);
+
+        signer.release_nonce(9).await;
+        assert_eq!(signer.reserve_nonce(5).await, 9);
+
+        // nonces reserved after a released one are not reused
+        signer.release_nonce(6).await;
+        assert_eq!(signer.reserve_nonce(5).await, 10);
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let signer = signer();
+        let signature = signer.sign_message(b"hello").unwrap();
+
+        assert_eq!(signature.recover(&b"hello"[..]).unwrap(), signer.address());
+    }
+}
@@ -1,7 +1,12 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use ethers::types::{Address, Bytes, Transaction, H256, U256, U64};
-use serde::{de::Error, ser::SerializeSeq, Deserialize, Serialize};
+use ethers::types::{Address, Bytes, Transaction, Withdrawal, H256, H64, U256, U64};
+use ethers::utils::{keccak256, rlp::RlpStream};
+use serde::{
+    de::{value::MapAccessDeserializer, Error, MapAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Serialize,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
@@ -27,6 +32,141 @@ pub struct Block {
     pub transactions: Transactions,
     pub transactions_root: H256,
     pub uncles: Vec<H256>,
+    /// The withdrawals of a post-Shanghai block. Amounts are in gwei.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
+}
+
+impl Block {
+    /// Returns the RLP encoding of the block header. Fields introduced by later forks, such
+    /// as the Shanghai withdrawals root and the Cancun blob gas fields, are only encoded when
+    /// they are set. Blocks converted from beacon chain payloads never set the Cancun fields,
+    /// since only Bellatrix and Capella payloads are decoded. Fails if the nonce is not a
+    /// valid 8 byte hex string.
+    pub fn header_rlp(&self) -> eyre::Result<Vec<u8>> {
+        let nonce = H64::from_str(&self.nonce).map_err(|_| {
+            eyre::eyre!("invalid nonce for block {}: {:?}", self.number, self.nonce)
+        })?;
+
+        let mut stream = RlpStream::new();
+        stream.begin_unbounded_list();
+        stream.append(&self.parent_hash);
+        stream.append(&self.sha3_uncles);
+        stream.append(&self.miner);
+        stream.append(&self.state_root);
+        stream.append(&self.transactions_root);
+        stream.append(&self.receipts_root);
+        stream.append(&self.logs_bloom.as_ref());
+        stream.append(&self.difficulty);
+        stream.append(&self.number);
+        stream.append(&self.gas_limit);
+        stream.append(&self.gas_used);
+        stream.append(&self.timestamp);
+        stream.append(&self.extra_data.as_ref());
+        stream.append(&self.mix_hash);
+        stream.append(&nonce);
+        stream.append(&self.base_fee_per_gas);
+
+        if let Some(withdrawals_root) = &self.withdrawals_root {
+            stream.append(withdrawals_root);
+        }
+
+        if let Some(blob_gas_used) = &self.blob_gas_used {
+            stream.append(blob_gas_used);
+        }
+
+        if let Some(excess_blob_gas) = &self.excess_blob_gas {
+            stream.append(excess_blob_gas);
+        }
+
+        if let Some(parent_beacon_block_root) = &self.parent_beacon_block_root {
+            stream.append(parent_beacon_block_root);
+        }
+
+        stream.finalize_unbounded_list();
+        Ok(stream.out().to_vec())
+    }
+
+    /// Recomputes the block hash from the header fields.
+    pub fn header_hash(&self) -> eyre::Result<H256> {
+        Ok(H256::from(keccak256(self.header_rlp()?)))
+    }
+}
+
+impl From<ethers::types::Block<H256>> for Block {
+    fn from(mut block: ethers::types::Block<H256>) -> Self {
+        let transactions = Transactions::Hashes(std::mem::take(&mut block.transactions));
+        Self::from_rpc_block(block, transactions)
+    }
+}
+
+impl From<ethers::types::Block<Transaction>> for Block {
+    fn from(mut block: ethers::types::Block<Transaction>) -> Self {
+        let transactions = Transactions::Full(std::mem::take(&mut block.transactions));
+        Self::from_rpc_block(block, transactions)
+    }
+}
+
+impl Block {
+    /// Converts a block served by an execution rpc. The result is unverified until its header
+    /// hash is checked against a verified block hash.
+    fn from_rpc_block<T>(block: ethers::types::Block<T>, transactions: Transactions) -> Self {
+        // fields introduced after the fork ethers knows about are kept in `other`
+        let blob_gas_used = block
+            .other
+            .get_deserialized("blobGasUsed")
+            .and_then(|v| v.ok());
+        let excess_blob_gas = block
+            .other
+            .get_deserialized("excessBlobGas")
+            .and_then(|v| v.ok());
+        let parent_beacon_block_root = block
+            .other
+            .get_deserialized("parentBeaconBlockRoot")
+            .and_then(|v| v.ok());
+
+        Block {
+            number: block.number.unwrap_or_default(),
+            base_fee_per_gas: block.base_fee_per_gas.unwrap_or_default(),
+            difficulty: block.difficulty,
+            extra_data: block.extra_data,
+            gas_limit: block.gas_limit.as_u64().into(),
+            gas_used: block.gas_used.as_u64().into(),
+            hash: block.hash.unwrap_or_default(),
+            logs_bloom: block
+                .logs_bloom
+                .unwrap_or_default()
+                .as_bytes()
+                .to_vec()
+                .into(),
+            miner: block.author.unwrap_or_default(),
+            mix_hash: block.mix_hash.unwrap_or_default(),
+            nonce: format!("{:?}", block.nonce.unwrap_or_default()),
+            parent_hash: block.parent_hash,
+            receipts_root: block.receipts_root,
+            sha3_uncles: block.uncles_hash,
+            size: block.size.unwrap_or_default().as_u64().into(),
+            state_root: block.state_root,
+            timestamp: block.timestamp.as_u64().into(),
+            total_difficulty: 0.into(),
+            transactions,
+            transactions_root: block.transactions_root,
+            uncles: block.uncles,
+            withdrawals: block.withdrawals,
+            withdrawals_root: block.withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -76,11 +216,18 @@ impl Serialize for Transactions {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A block parameter, either as a tag, a number, or an [EIP-1898] block hash.
+///
+/// [EIP-1898]: https://eips.ethereum.org/EIPS/eip-1898
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockTag {
     Latest,
     Finalized,
+    Safe,
+    Earliest,
+    Pending,
     Number(u64),
+    Hash(H256),
 }
 
 impl Display for BlockTag {
@@ -88,32 +235,68 @@ impl Display for BlockTag {
         let formatted = match self {
             Self::Latest => "latest".to_string(),
             Self::Finalized => "finalized".to_string(),
+            Self::Safe => "safe".to_string(),
+            Self::Earliest => "earliest".to_string(),
+            Self::Pending => "pending".to_string(),
             Self::Number(num) => num.to_string(),
+            Self::Hash(hash) => format!("{hash:?}"),
         };
 
         write!(f, "{formatted}")
     }
 }
 
+/// The [EIP-1898] object form of a block parameter.
+///
+/// [EIP-1898]: https://eips.ethereum.org/EIPS/eip-1898
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct BlockObject {
+    block_hash: Option<H256>,
+    block_number: Option<U64>,
+    #[serde(default)]
+    require_canonical: bool,
+}
+
 impl<'de> Deserialize<'de> for BlockTag {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let block: String = serde::Deserialize::deserialize(deserializer)?;
-        let parse_error = D::Error::custom("could not parse block tag");
+        deserializer.deserialize_any(BlockTagVisitor)
+    }
+}
+
+struct BlockTagVisitor;
 
-        let block_tag = match block.as_str() {
+impl<'de> Visitor<'de> for BlockTagVisitor {
+    type Value = BlockTag;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a block number, a block tag, or an object with a blockHash or blockNumber")
+    }
+
+    fn visit_u64<E: Error>(self, number: u64) -> Result<BlockTag, E> {
+        Ok(BlockTag::Number(number))
+    }
+
+    fn visit_str<E: Error>(self, block: &str) -> Result<BlockTag, E> {
+        let parse_error = || E::custom(format!("invalid block tag or number: {block}"));
+
+        let block_tag = match block {
             "latest" => BlockTag::Latest,
             "finalized" => BlockTag::Finalized,
+            "safe" => BlockTag::Safe,
+            "earliest" => BlockTag::Earliest,
+            "pending" => BlockTag::Pending,
             _ => match block.strip_prefix("0x") {
                 Some(hex_block) => {
-                    let num = u64::from_str_radix(hex_block, 16).map_err(|_| parse_error)?;
+                    let num = u64::from_str_radix(hex_block, 16).map_err(|_| parse_error())?;
 
                     BlockTag::Number(num)
                 }
                 None => {
-                    let num = block.parse().map_err(|_| parse_error)?;
+                    let num = block.parse().map_err(|_| parse_error())?;
 
                     BlockTag::Number(num)
                 }
@@ -122,4 +305,28 @@ impl<'de> Deserialize<'de> for BlockTag {
 
         Ok(block_tag)
     }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<BlockTag, A::Error> {
+        let block = BlockObject::deserialize(MapAccessDeserializer::new(map))?;
+
+        match (block.block_hash, block.block_number) {
+            // helios only keeps the blocks of the chain it follows, so it cannot tell a reorged
+            // block from an unknown one as requireCanonical requires, and rejects the flag
+            // rather than ignoring it
+            (Some(_), None) if block.require_canonical => Err(A::Error::custom(
+                "requireCanonical is not supported, omit it or set it to false",
+            )),
+            (Some(hash), None) => Ok(BlockTag::Hash(hash)),
+            (None, Some(_)) if block.require_canonical => Err(A::Error::custom(
+                "requireCanonical can only be used with blockHash",
+            )),
+            (None, Some(number)) => Ok(BlockTag::Number(number.as_u64())),
+            (Some(_), Some(_)) => Err(A::Error::custom(
+                "block parameter cannot have both blockHash and blockNumber",
+            )),
+            (None, None) => Err(A::Error::custom(
+                "block parameter must have a blockHash or a blockNumber",
+            )),
+        }
+    }
 }
@@ -1,4 +1,11 @@
 use ethers::prelude::Address;
+use ethers::types::{
+    transaction::eip2930::AccessList, Bytes, Signature, Transaction, H256, U256, U64,
+};
+use ethers::utils::{
+    keccak256,
+    rlp::{Decodable, DecoderError, Rlp, RlpStream},
+};
 use eyre::Result;
 
 pub fn hex_str_to_bytes(s: &str) -> Result<Vec<u8>> {
@@ -13,3 +20,141 @@ pub fn address_to_hex_string(address: &Address) -> String {
 pub fn u64_to_hex_string(val: u64) -> String {
     format!("0x{val:x}")
 }
+
+/// EIP-4844 blob transaction type.
+const BLOB_TX_TYPE: u8 = 0x03;
+
+/// Decodes an EIP-2718 encoded transaction and recovers its sender. Legacy, EIP-2930 and
+/// EIP-1559 transactions are decoded by ethers, blob transactions are decoded here with their
+/// blob fields stored in `other`.
+pub fn decode_transaction(raw: &[u8]) -> Result<Transaction> {
+    let tx_type = *raw.first().ok_or(DecoderError::RlpIsTooShort)?;
+
+    let mut tx = match tx_type {
+        BLOB_TX_TYPE => decode_blob_transaction(raw)?,
+        0x01 | 0x02 => Transaction::decode(&Rlp::new(raw))?,
+        // legacy transactions are bare rlp lists
+        ty if ty >= 0xc0 => Transaction::decode(&Rlp::new(raw))?,
+        ty => eyre::bail!("unsupported transaction type: {ty:#x}"),
+    };
+
+    tx.from = recover_sender(&tx)?;
+
+    Ok(tx)
+}
+
+/// Recovers the sender of a signed transaction.
+pub fn recover_sender(tx: &Transaction) -> Result<Address> {
+    if tx.transaction_type != Some(BLOB_TX_TYPE.into()) {
+        return Ok(tx.recover_from()?);
+    }
+
+    let signature = Signature {
+        r: tx.r,
+        s: tx.s,
+        v: tx.v.as_u64(),
+    };
+
+    Ok(signature.recover(blob_transaction_sighash(tx))?)
+}
+
+fn decode_blob_transaction(raw: &[u8]) -> Result<Transaction, DecoderError> {
+    let rlp = Rlp::new(&raw[1..]);
+    if rlp.item_count()? != 14 {
+        return Err(DecoderError::RlpIncorrectListLen);
+    }
+
+    let mut tx = Transaction {
+        hash: H256(keccak256(raw)),
+        transaction_type: Some(BLOB_TX_TYPE.into()),
+        chain_id: Some(rlp.val_at(0)?),
+        nonce: rlp.val_at(1)?,
+        max_priority_fee_per_gas: Some(rlp.val_at(2)?),
+        max_fee_per_gas: Some(rlp.val_at(3)?),
+        gas: rlp.val_at(4)?,
+        // blob transactions cannot create contracts
+        to: Some(rlp.val_at(5)?),
+        value: rlp.val_at(6)?,
+        input: Bytes::from(rlp.val_at::<Vec<u8>>(7)?),
+        access_list: Some(rlp.val_at(8)?),
+        v: U64::from(rlp.val_at::<bool>(11)? as u8),
+        r: rlp.val_at(12)?,
+        s: rlp.val_at(13)?,
+        ..Default::default()
+    };
+
+    let max_fee_per_blob_gas: U256 = rlp.val_at(9)?;
+    let blob_versioned_hashes: Vec<H256> = rlp.list_at(10)?;
+
+    tx.other.insert(
+        "maxFeePerBlobGas".to_string(),
+        serde_json::json!(max_fee_per_blob_gas),
+    );
+    tx.other.insert(
+        "blobVersionedHashes".to_string(),
+        serde_json::json!(blob_versioned_hashes),
+    );
+
+    Ok(tx)
+}
+
+/// Returns the max fee per blob gas and the blob versioned hashes of a transaction decoded by
+/// `decode_transaction`. Transactions without blobs return zero and no hashes.
+pub fn blob_transaction_fields(tx: &Transaction) -> (U256, Vec<H256>) {
+    let max_fee_per_blob_gas = tx
+        .other
+        .get_deserialized("maxFeePerBlobGas")
+        .and_then(|v| v.ok())
+        .unwrap_or_default();
+
+    let blob_versioned_hashes = tx
+        .other
+        .get_deserialized("blobVersionedHashes")
+        .and_then(|v| v.ok())
+        .unwrap_or_default();
+
+    (max_fee_per_blob_gas, blob_versioned_hashes)
+}
+
+/// Appends the unsigned fields of a blob transaction to an open rlp list.
+fn append_blob_transaction_fields(stream: &mut RlpStream, tx: &Transaction) {
+    let (max_fee_per_blob_gas, blob_versioned_hashes) = blob_transaction_fields(tx);
+
+    stream.append(&tx.chain_id.unwrap_or_default());
+    stream.append(&tx.nonce);
+    stream.append(&tx.max_priority_fee_per_gas.unwrap_or_default());
+    stream.append(&tx.max_fee_per_gas.unwrap_or_default());
+    stream.append(&tx.gas);
+    stream.append(&tx.to.unwrap_or_default());
+    stream.append(&tx.value);
+    stream.append(&tx.input.as_ref());
+    stream.append(&tx.access_list.clone().unwrap_or_else(|| AccessList(vec![])));
+    stream.append(&max_fee_per_blob_gas);
+    stream.append_list(&blob_versioned_hashes);
+}
+
+fn blob_transaction_sighash(tx: &Transaction) -> H256 {
+    let mut stream = RlpStream::new_list(11);
+    append_blob_transaction_fields(&mut stream, tx);
+
+    let mut encoded = vec![BLOB_TX_TYPE];
+    encoded.extend_from_slice(&stream.out());
+    H256(keccak256(encoded))
+}
+
+/// Encodes a decoded transaction back into its EIP-2718 form.
+pub fn encode_transaction(tx: &Transaction) -> Vec<u8> {
+    if tx.transaction_type != Some(BLOB_TX_TYPE.into()) {
+        return tx.rlp().to_vec();
+    }
+
+    let mut stream = RlpStream::new_list(14);
+    append_blob_transaction_fields(&mut stream, tx);
+    stream.append(&(tx.v.as_u64() == 1));
+    stream.append(&tx.r);
+    stream.append(&tx.s);
+
+    let mut encoded = vec![BLOB_TX_TYPE];
+    encoded.extend_from_slice(&stream.out());
+    encoded
+}
@@ -12,6 +12,7 @@ pub struct CliConfig {
     pub checkpoint: Option<Vec<u8>>,
     pub rpc_bind_ip: Option<IpAddr>,
     pub rpc_port: Option<u16>,
+    pub ws_port: Option<u16>,
     pub data_dir: PathBuf,
     pub fallback: Option<String>,
     pub load_external_fallback: bool,
@@ -42,6 +43,10 @@ impl CliConfig {
             user_dict.insert("rpc_port", Value::from(port));
         }
 
+        if let Some(port) = self.ws_port {
+            user_dict.insert("ws_port", Value::from(port));
+        }
+
         user_dict.insert("data_dir", Value::from(self.data_dir.to_str().unwrap()));
 
         if let Some(fallback) = &self.fallback {
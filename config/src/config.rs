@@ -1,6 +1,6 @@
 use crate::base::BaseConfig;
 use crate::cli::CliConfig;
-use crate::types::{ChainConfig, Forks};
+use crate::types::{ChainConfig, Forks, PrefetchStrategy, RpcStrategy, SignerConfig};
 use crate::utils::{bytes_deserialize, bytes_opt_deserialize};
 use crate::Network;
 use figment::{
@@ -8,6 +8,7 @@ use figment::{
     Figment,
 };
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr};
 use std::str::FromStr;
 use std::{path::PathBuf, process::exit};
@@ -18,6 +19,8 @@ pub struct Config {
     pub execution_rpc: String,
     pub rpc_bind_ip: Option<IpAddr>,
     pub rpc_port: Option<u16>,
+    #[serde(default)]
+    pub ws_port: Option<u16>,
     #[serde(deserialize_with = "bytes_deserialize")]
     pub default_checkpoint: Vec<u8>,
     #[serde(default)]
@@ -30,6 +33,24 @@ pub struct Config {
     pub fallback: Option<String>,
     pub load_external_fallback: bool,
     pub strict_checkpoint_age: bool,
+    #[serde(default)]
+    pub prefetch_strategy: PrefetchStrategy,
+    #[serde(default)]
+    pub execution_rpc_batch_size: Option<usize>,
+    #[serde(default)]
+    pub max_logs: Option<usize>,
+    #[serde(default)]
+    pub logs_completeness: bool,
+    #[serde(default)]
+    pub execution_rpc_strategies: HashMap<String, RpcStrategy>,
+    #[serde(default)]
+    pub signer: Option<SignerConfig>,
+    #[serde(default)]
+    pub rebroadcast_transactions: bool,
+    #[serde(default)]
+    pub strict_transactions: bool,
+    #[serde(default)]
+    pub fork: bool,
 }
 
 impl Config {
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
 use crate::utils::{bytes_deserialize, bytes_serialize};
@@ -30,3 +33,59 @@ pub struct Fork {
     )]
     pub fork_version: Vec<u8>,
 }
+
+/// How state is prefetched before executing a call in the evm.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefetchStrategy {
+    /// Prefetch the accounts and slots returned by `eth_createAccessList`. If the execution rpc
+    /// rejects the request, state is discovered during execution instead.
+    #[default]
+    AccessList,
+    /// Only prefetch the sender, recipient and coinbase, and discover all other state during
+    /// execution. Use this for execution rpcs that do not support `eth_createAccessList`.
+    Discover,
+}
+
+/// How a request is dispatched when several execution rpcs are configured.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcStrategy {
+    /// Send the request to the healthiest provider, moving on to the next one if it fails.
+    #[default]
+    Failover,
+    /// Send the request to every healthy provider and use the first successful response. The
+    /// other requests are abandoned once one succeeds.
+    Race,
+    /// Send the request to every provider, wait for all of them, and use the first successful
+    /// response.
+    Broadcast,
+    /// Spread requests across the healthy providers in turn, failing over on errors.
+    RoundRobin,
+}
+
+/// A local key used to sign transactions sent through `eth_sendTransaction`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// A hex encoded private key.
+    PrivateKey(String),
+    /// An encrypted JSON keystore and the password that decrypts it. Keystores are not
+    /// supported in the browser.
+    Keystore { path: PathBuf, password: String },
+}
+
+impl fmt::Debug for SignerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerConfig::PrivateKey(_) => {
+                f.debug_tuple("PrivateKey").field(&"<redacted>").finish()
+            }
+            SignerConfig::Keystore { path, .. } => f
+                .debug_struct("Keystore")
+                .field("path", path)
+                .field("password", &"<redacted>")
+                .finish(),
+        }
+    }
+}
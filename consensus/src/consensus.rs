@@ -34,6 +34,7 @@ use super::utils::*;
 pub struct ConsensusClient<R: ConsensusRpc, DB: Database> {
     pub block_recv: Option<Receiver<Block>>,
     pub finalized_block_recv: Option<watch::Receiver<Option<Block>>>,
+    pub safe_block_recv: Option<watch::Receiver<Option<Block>>>,
     pub checkpoint_recv: watch::Receiver<Option<Vec<u8>>>,
     genesis_time: u64,
     db: DB,
@@ -47,6 +48,7 @@ pub struct Inner<R: ConsensusRpc> {
     last_checkpoint: Option<Vec<u8>>,
     block_send: Sender<Block>,
     finalized_block_send: watch::Sender<Option<Block>>,
+    safe_block_send: watch::Sender<Option<Block>>,
     checkpoint_send: watch::Sender<Option<Vec<u8>>>,
     pub config: Arc<Config>,
 }
@@ -65,6 +67,7 @@ impl<R: ConsensusRpc, DB: Database> ConsensusClient<R, DB> {
     pub fn new(rpc: &str, config: Arc<Config>) -> Result<ConsensusClient<R, DB>> {
         let (block_send, block_recv) = channel(256);
         let (finalized_block_send, finalized_block_recv) = watch::channel(None);
+        let (safe_block_send, safe_block_recv) = watch::channel(None);
         let (checkpoint_send, checkpoint_recv) = watch::channel(None);
 
         let rpc = rpc.to_string();
@@ -80,6 +83,7 @@ impl<R: ConsensusRpc, DB: Database> ConsensusClient<R, DB> {
                 &rpc,
                 block_send,
                 finalized_block_send,
+                safe_block_send,
                 checkpoint_send,
                 config.clone(),
             );
@@ -126,6 +130,7 @@ impl<R: ConsensusRpc, DB: Database> ConsensusClient<R, DB> {
         Ok(ConsensusClient {
             block_recv: Some(block_recv),
             finalized_block_recv: Some(finalized_block_recv),
+            safe_block_recv: Some(safe_block_recv),
             checkpoint_recv,
             genesis_time,
             db,
@@ -171,6 +176,7 @@ impl<R: ConsensusRpc> Inner<R> {
         rpc: &str,
         block_send: Sender<Block>,
         finalized_block_send: watch::Sender<Option<Block>>,
+        safe_block_send: watch::Sender<Option<Block>>,
         checkpoint_send: watch::Sender<Option<Vec<u8>>>,
         config: Arc<Config>,
     ) -> Inner<R> {
@@ -182,6 +188,7 @@ impl<R: ConsensusRpc> Inner<R> {
             last_checkpoint: None,
             block_send,
             finalized_block_send,
+            safe_block_send,
             checkpoint_send,
             config,
         }
@@ -224,6 +231,34 @@ impl<R: ConsensusRpc> Inner<R> {
         }
     }
 
+    /// Returns the execution payload of the current justified checkpoint. The consensus rpc is
+    /// trusted to report which checkpoint is justified, so the payload is only checked against
+    /// the reported root and the slots of the verified headers. Consumers must still check that
+    /// the block is on the verified chain before using it.
+    pub async fn get_justified_payload(&self) -> Result<ExecutionPayload> {
+        let checkpoint = self.rpc.get_justified_checkpoint().await?;
+        let mut block = self.rpc.get_block_by_root(&checkpoint.root).await?;
+        let block_hash = block.hash_tree_root()?;
+        let checkpoint_hash = bytes32_to_node(&checkpoint.root)?;
+
+        if block_hash != checkpoint_hash {
+            return Err(ConsensusError::InvalidHeaderHash(
+                block_hash.to_string(),
+                checkpoint_hash.to_string(),
+            )
+            .into());
+        }
+
+        let slot = block.slot.as_u64();
+        let finalized_slot = self.store.finalized_header.slot.as_u64();
+        let latest_slot = self.store.optimistic_header.slot.as_u64();
+        if slot < finalized_slot || slot > latest_slot {
+            return Err(ConsensusError::InvalidJustifiedBlock(slot).into());
+        }
+
+        Ok(block.body.execution_payload().clone())
+    }
+
     pub async fn get_payloads(
         &self,
         start_slot: u64,
@@ -333,11 +368,22 @@ impl<R: ConsensusRpc> Inner<R> {
         let finalized_slot = self.store.finalized_header.slot.as_u64();
         let finalized_payload = self.get_execution_payload(&Some(finalized_slot)).await?;
 
-        self.block_send.send(payload.into()).await?;
+        self.block_send.send(payload.try_into()?).await?;
         self.finalized_block_send
-            .send(Some(finalized_payload.into()))?;
+            .send(Some(finalized_payload.try_into()?))?;
         self.checkpoint_send.send(self.last_checkpoint.clone())?;
 
+        // the safe block falls back to the finalized block, so failing to fetch the justified
+        // block does not hold back the other blocks
+        let justified_block = self.get_justified_payload().await.and_then(Block::try_from);
+
+        match justified_block {
+            Ok(block) => self.safe_block_send.send(Some(block))?,
+            Err(err) => {
+                debug!(target: "helios::consensus", "could not get justified block: {}", err)
+            }
+        }
+
         Ok(())
     }
 
@@ -776,8 +822,12 @@ fn is_current_committee_proof_valid(
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
     use std::sync::Arc;
 
+    use common::types::{Block, Transactions};
+    use ethers::types::{Address, H256};
+
     use crate::{
         consensus::calc_sync_period,
         constants::MAX_REQUEST_LIGHT_CLIENT_UPDATES,
@@ -808,12 +858,14 @@ mod tests {
 
         let (block_send, _) = channel(256);
         let (finalized_block_send, _) = watch::channel(None);
+        let (safe_block_send, _) = watch::channel(None);
         let (channel_send, _) = watch::channel(None);
 
         let mut client = Inner::new(
             "testdata/",
             block_send,
             finalized_block_send,
+            safe_block_send,
             channel_send,
             Arc::new(config),
         );
@@ -965,4 +1017,69 @@ mod tests {
     async fn test_verify_checkpoint_age_invalid() {
         get_client(true, false).await;
     }
+
+    #[tokio::test]
+    async fn test_payload_withdrawals() {
+        let rpc = MockRpc::new("testdata/");
+        let block = rpc.get_block(7109344).await.unwrap();
+        let block: Block = block.body.execution_payload().clone().try_into().unwrap();
+
+        let withdrawals = block.withdrawals.unwrap();
+        assert_eq!(withdrawals.len(), 16);
+        assert_eq!(withdrawals[0].index.as_u64(), 14209232);
+        assert_eq!(withdrawals[0].amount.as_u64(), 14661056);
+
+        let withdrawals_root =
+            H256::from_str("acc26cc3198c4597e789df8fcbf1ae563ed724e1ce02ff4dd84a45716cbdbda9")
+                .unwrap();
+
+        assert_eq!(block.withdrawals_root, Some(withdrawals_root));
+    }
+
+    #[tokio::test]
+    async fn test_payload_block_hash() {
+        let rpc = MockRpc::new("testdata/");
+        let block = rpc.get_block(7109344).await.unwrap();
+        let mut payload = block.body.execution_payload().clone();
+
+        let block: Block = payload.clone().try_into().unwrap();
+        let transactions_root =
+            H256::from_str("568a358071161f6aa4cafafc4c4c56ef757f8d0493ffd933ab91b3eac63cbe36")
+                .unwrap();
+
+        assert_eq!(block.transactions_root, transactions_root);
+        assert_eq!(block.header_hash().unwrap(), block.hash);
+
+        *payload.gas_used_mut() = (block.gas_used.as_u64() + 1).into();
+        let res: Result<Block, _> = payload.try_into();
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_payload_transactions() {
+        let rpc = MockRpc::new("testdata/");
+        let block = rpc.get_block(7109344).await.unwrap();
+        let mut payload = block.body.execution_payload().clone();
+
+        let block: Block = payload.clone().try_into().unwrap();
+        let txs = match block.transactions {
+            Transactions::Full(txs) => txs,
+            Transactions::Hashes(_) => panic!("expected full transactions"),
+        };
+
+        assert_eq!(txs.len(), 167);
+        for (index, tx) in txs.iter().enumerate() {
+            assert_eq!(tx.block_hash, Some(block.hash));
+            assert_eq!(tx.block_number, Some(block.number));
+            assert_eq!(tx.transaction_index, Some(index.into()));
+            assert_ne!(tx.from, Address::zero());
+        }
+
+        let sender = Address::from_str("0x1869d73051eb5f7d87b93401ee3b918bc357e125").unwrap();
+        assert_eq!(txs[0].from, sender);
+
+        payload.transactions_mut()[0] = vec![0x04, 0xc0].try_into().unwrap();
+        let res: Result<Block, _> = payload.try_into();
+        assert!(res.is_err());
+    }
 }
@@ -20,8 +20,16 @@ pub enum ConsensusError {
     InvalidSignature,
     #[error("invalid header hash found: {0}, expected: {1}")]
     InvalidHeaderHash(String, String),
+    #[error("invalid execution block hash found: {0}, expected: {1}")]
+    InvalidExecutionBlockHash(String, String),
+    #[error("invalid transaction at index {0}: {1}")]
+    InvalidTransaction(usize, String),
+    #[error("invalid transactions root found: {0}, expected: {1}")]
+    InvalidTransactionsRoot(String, String),
     #[error("payload not found for slot: {0}")]
     PayloadNotFound(u64),
+    #[error("justified block at slot {0} is outside the verified chain")]
+    InvalidJustifiedBlock(u64),
     #[error("checkpoint is too old")]
     CheckpointTooOld,
     #[error("consensus rpc is for the incorrect network")]
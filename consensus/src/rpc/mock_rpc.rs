@@ -1,7 +1,9 @@
 use std::{fs::read_to_string, path::PathBuf};
 
 use super::ConsensusRpc;
-use crate::types::{BeaconBlock, Bootstrap, FinalityUpdate, OptimisticUpdate, Update};
+use crate::types::{
+    BeaconBlock, Bootstrap, Bytes32, Checkpoint, FinalityUpdate, OptimisticUpdate, Update,
+};
 use async_trait::async_trait;
 use eyre::Result;
 pub struct MockRpc {
@@ -48,6 +50,14 @@ impl ConsensusRpc for MockRpc {
         Ok(block.data.message)
     }
 
+    async fn get_block_by_root(&self, _root: &Bytes32) -> Result<BeaconBlock> {
+        eyre::bail!("not implemented")
+    }
+
+    async fn get_justified_checkpoint(&self) -> Result<Checkpoint> {
+        eyre::bail!("not implemented")
+    }
+
     async fn chain_id(&self) -> Result<u64> {
         eyre::bail!("not implemented")
     }
@@ -4,7 +4,9 @@ pub mod nimbus_rpc;
 use async_trait::async_trait;
 use eyre::Result;
 
-use crate::types::{BeaconBlock, Bootstrap, FinalityUpdate, OptimisticUpdate, Update};
+use crate::types::{
+    BeaconBlock, Bootstrap, Bytes32, Checkpoint, FinalityUpdate, OptimisticUpdate, Update,
+};
 
 #[async_trait]
 pub trait ConsensusRpc: Send + Sync {
@@ -14,5 +16,9 @@ pub trait ConsensusRpc: Send + Sync {
     async fn get_finality_update(&self) -> Result<FinalityUpdate>;
     async fn get_optimistic_update(&self) -> Result<OptimisticUpdate>;
     async fn get_block(&self, slot: u64) -> Result<BeaconBlock>;
+    async fn get_block_by_root(&self, root: &Bytes32) -> Result<BeaconBlock>;
+    /// Returns the current justified checkpoint of the head state. Light client updates do not
+    /// prove it, so the response is unverified.
+    async fn get_justified_checkpoint(&self) -> Result<Checkpoint>;
     async fn chain_id(&self) -> Result<u64>;
 }
@@ -25,6 +25,15 @@ async fn get<R: DeserializeOwned>(req: &str) -> Result<R, reqwest::Error> {
     .await
 }
 
+/// Sends a request without retrying it, for optional data that should not hold back syncing.
+async fn get_once<R: DeserializeOwned>(req: &str) -> Result<R, reqwest::Error> {
+    reqwest::get(req)
+        .await?
+        .error_for_status()?
+        .json::<R>()
+        .await
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 impl ConsensusRpc for NimbusRpc {
@@ -83,6 +92,33 @@ impl ConsensusRpc for NimbusRpc {
         Ok(res.data.message)
     }
 
+    async fn get_block_by_root(&self, root: &Bytes32) -> Result<BeaconBlock> {
+        let req = format!(
+            "{}/eth/v2/beacon/blocks/0x{}",
+            self.rpc,
+            hex::encode(root.as_slice())
+        );
+
+        let res: BeaconBlockResponse = get_once(&req)
+            .await
+            .map_err(|e| RpcError::new("blocks", e))?;
+
+        Ok(res.data.message)
+    }
+
+    async fn get_justified_checkpoint(&self) -> Result<Checkpoint> {
+        let req = format!(
+            "{}/eth/v1/beacon/states/head/finality_checkpoints",
+            self.rpc
+        );
+
+        let res: FinalityCheckpointsResponse = get_once(&req)
+            .await
+            .map_err(|e| RpcError::new("finality_checkpoints", e))?;
+
+        Ok(res.data.current_justified)
+    }
+
     async fn chain_id(&self) -> Result<u64> {
         let req = format!("{}/eth/v1/config/spec", self.rpc);
         let res: SpecResponse = get(&req).await.map_err(|e| RpcError::new("spec", e))?;
@@ -101,6 +137,16 @@ struct BeaconBlockData {
     message: BeaconBlock,
 }
 
+#[derive(serde::Deserialize, Debug)]
+struct FinalityCheckpointsResponse {
+    data: FinalityCheckpoints,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct FinalityCheckpoints {
+    current_justified: Checkpoint,
+}
+
 type UpdateResponse = Vec<UpdateData>;
 
 #[derive(serde::Deserialize, Debug)]
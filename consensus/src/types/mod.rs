@@ -166,9 +166,9 @@ struct AttestationData {
 }
 
 #[derive(serde::Deserialize, Debug, Default, SimpleSerialize, Clone)]
-struct Checkpoint {
-    epoch: U64,
-    root: Bytes32,
+pub struct Checkpoint {
+    pub epoch: U64,
+    pub root: Bytes32,
 }
 
 #[derive(serde::Deserialize, Debug, Default, SimpleSerialize, Clone)]
@@ -2,13 +2,16 @@ use std::str::FromStr;
 
 use ethers::{
     types::{Address, Transaction, H256},
-    utils::rlp::{Decodable, Rlp},
+    utils::rlp,
 };
 use serde::de::Error;
 use ssz_rs::prelude::*;
+use triehash_ethereum::ordered_trie_root;
 
-use super::{ExecutionPayload, Header};
+use super::{ExecutionPayload, Header, Withdrawal};
+use crate::errors::ConsensusError;
 use common::types::{Block, Transactions};
+use common::utils::{decode_transaction, encode_transaction};
 
 pub fn u256_deserialize<'de, D>(deserializer: D) -> Result<U256, D::Error>
 where
@@ -91,30 +94,81 @@ macro_rules! superstruct_ssz {
 /// this has to go after macro definition
 pub(crate) use superstruct_ssz;
 
-impl From<ExecutionPayload> for Block {
-    fn from(value: ExecutionPayload) -> Block {
+impl TryFrom<ExecutionPayload> for Block {
+    type Error = eyre::Report;
+
+    /// Converts a verified execution payload into a block, checking that the header built from
+    /// the payload hashes to the payload's block hash.
+    fn try_from(value: ExecutionPayload) -> eyre::Result<Block> {
         let empty_nonce = "0x0000000000000000".to_string();
         let empty_uncle_hash = "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347";
 
+        let hash = H256::from_slice(value.block_hash());
+        let number = value.block_number().as_u64();
+        let base_fee_per_gas =
+            ethers::types::U256::from_little_endian(&value.base_fee_per_gas().to_bytes_le());
+
         let txs = value
             .transactions()
             .iter()
-            .map(|tx| {
-                let rlp = Rlp::new(tx.as_slice());
-                Transaction::decode(&rlp).unwrap()
+            .enumerate()
+            .map(|(index, raw)| {
+                let mut tx = decode_transaction(raw.as_slice())
+                    .map_err(|err| ConsensusError::InvalidTransaction(index, err.to_string()))?;
+
+                tx.block_hash = Some(hash);
+                tx.block_number = Some(number.into());
+                tx.transaction_index = Some(index.into());
+
+                // typed fee market transactions report the effective gas price
+                if let (Some(max_fee), Some(max_priority_fee)) =
+                    (tx.max_fee_per_gas, tx.max_priority_fee_per_gas)
+                {
+                    let priority_fee =
+                        max_priority_fee.min(max_fee.saturating_sub(base_fee_per_gas));
+                    tx.gas_price = Some(base_fee_per_gas + priority_fee);
+                }
+
+                Ok(tx)
             })
-            .collect::<Vec<Transaction>>();
+            .collect::<Result<Vec<Transaction>, ConsensusError>>()?;
+
+        let transactions_root =
+            ordered_trie_root(value.transactions().iter().map(|tx| tx.to_vec()));
+        let transactions_root = H256::from_slice(&transactions_root.to_fixed_bytes());
+
+        // the decoded transactions must re-encode to exactly the committed transactions
+        let decoded_root = ordered_trie_root(txs.iter().map(encode_transaction));
+        let decoded_root = H256::from_slice(&decoded_root.to_fixed_bytes());
+        if decoded_root != transactions_root {
+            return Err(ConsensusError::InvalidTransactionsRoot(
+                format!("{decoded_root:?}"),
+                format!("{transactions_root:?}"),
+            )
+            .into());
+        }
 
-        Block {
-            number: value.block_number().as_u64().into(),
-            base_fee_per_gas: ethers::types::U256::from_little_endian(
-                &value.base_fee_per_gas().to_bytes_le(),
-            ),
+        // withdrawals only exist from capella onwards
+        let withdrawals = value.withdrawals().ok().map(|withdrawals| {
+            withdrawals
+                .iter()
+                .map(ethers::types::Withdrawal::from)
+                .collect::<Vec<_>>()
+        });
+
+        let withdrawals_root = withdrawals.as_ref().map(|withdrawals| {
+            let root = ordered_trie_root(withdrawals.iter().map(|w| rlp::encode(w).to_vec()));
+            H256::from_slice(&root.to_fixed_bytes())
+        });
+
+        let block = Block {
+            number: number.into(),
+            base_fee_per_gas,
             difficulty: ethers::types::U256::from(0),
             extra_data: value.extra_data().to_vec().into(),
             gas_limit: value.gas_limit().as_u64().into(),
             gas_used: value.gas_used().as_u64().into(),
-            hash: H256::from_slice(value.block_hash()),
+            hash,
             logs_bloom: value.logs_bloom().to_vec().into(),
             miner: Address::from_slice(value.fee_recipient()),
             parent_hash: H256::from_slice(value.parent_hash()),
@@ -127,8 +181,37 @@ impl From<ExecutionPayload> for Block {
             nonce: empty_nonce,
             sha3_uncles: H256::from_str(empty_uncle_hash).unwrap(),
             size: 0.into(),
-            transactions_root: H256::default(),
+            transactions_root,
             uncles: vec![],
+            withdrawals,
+            withdrawals_root,
+            // there is no Deneb payload variant, so the Cancun fields are never set and Cancun
+            // blocks fail the block hash check below
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+        };
+
+        let header_hash = block.header_hash()?;
+        if header_hash != block.hash {
+            return Err(ConsensusError::InvalidExecutionBlockHash(
+                format!("{header_hash:?}"),
+                format!("{:?}", block.hash),
+            )
+            .into());
+        }
+
+        Ok(block)
+    }
+}
+
+impl From<&Withdrawal> for ethers::types::Withdrawal {
+    fn from(value: &Withdrawal) -> Self {
+        ethers::types::Withdrawal {
+            index: value.index.as_u64().into(),
+            validator_index: value.validator_index.as_u64().into(),
+            address: Address::from_slice(&value.address),
+            amount: value.amount.as_u64().into(),
         }
     }
 }
@@ -0,0 +1,369 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+};
+
+use ethers::types::{Address, TransactionReceipt, H256};
+use tokio::sync::RwLock;
+
+use crate::types::Account;
+
+/// A bounded cache of verified accounts, storage slots and code. Accounts and slots are keyed
+/// by the state root they were proven against, so entries can be shared across calls for the
+/// same block. Code is keyed by its hash and is shared across blocks. The account bound applies
+/// across every cached state root, and the oldest accounts are evicted first.
+#[derive(Clone)]
+pub struct StateCache {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl StateCache {
+    pub fn new(max_accounts: usize, max_code: usize) -> Self {
+        let inner = Inner {
+            max_accounts,
+            max_code,
+            ..Default::default()
+        };
+
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    /// Returns the cached account at the given state root. The returned account never includes
+    /// code, which should be loaded with `get_code`.
+    pub async fn get_account(&self, state_root: H256, address: &Address) -> Option<Account> {
+        self.inner
+            .read()
+            .await
+            .roots
+            .get(&state_root)
+            .and_then(|entry| entry.accounts.get(address))
+            .cloned()
+    }
+
+    pub async fn get_code(&self, code_hash: &H256) -> Option<Vec<u8>> {
+        self.inner.read().await.code.get(code_hash).cloned()
+    }
+
+    pub async fn contains_root(&self, state_root: H256) -> bool {
+        self.inner.read().await.roots.contains_key(&state_root)
+    }
+
+    /// Inserts a verified account, merging its slots with any already cached for this root.
+    pub async fn insert_account(
+        &self,
+        block_number: u64,
+        state_root: H256,
+        address: &Address,
+        account: &Account,
+    ) {
+        let mut guard = self.inner.write().await;
+        let inner = &mut *guard;
+
+        let entry = inner.roots.entry(state_root).or_insert_with(|| RootEntry {
+            block_number,
+            ..Default::default()
+        });
+
+        match entry.accounts.get_mut(address) {
+            Some(cached) => cached.slots.extend(account.slots.clone()),
+            None => {
+                let cached = Account {
+                    code: Vec::new(),
+                    ..account.clone()
+                };
+
+                entry.accounts.insert(*address, cached);
+                inner.account_order.push_back((state_root, *address));
+            }
+        }
+
+        while inner.account_order.len() > inner.max_accounts {
+            let (root, address) = match inner.account_order.pop_front() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+
+            let emptied = match inner.roots.get_mut(&root) {
+                Some(entry) => {
+                    entry.accounts.remove(&address);
+                    entry.accounts.is_empty()
+                }
+                None => false,
+            };
+
+            if emptied {
+                inner.roots.remove(&root);
+            }
+        }
+    }
+
+    pub async fn insert_code(&self, code_hash: H256, code: Vec<u8>) {
+        let mut inner = self.inner.write().await;
+        if inner.code.insert(code_hash, code).is_none() {
+            inner.code_order.push_back(code_hash);
+        }
+
+        while inner.code.len() > inner.max_code {
+            if let Some(oldest) = inner.code_order.pop_front() {
+                inner.code.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops every state root older than `oldest_block`, except for `keep` which is used to
+    /// retain the finalized block after it leaves the block window.
+    pub async fn prune(&self, oldest_block: u64, keep: Option<H256>) {
+        let mut guard = self.inner.write().await;
+        let inner = &mut *guard;
+
+        inner
+            .roots
+            .retain(|root, entry| entry.block_number >= oldest_block || Some(*root) == keep);
+
+        let roots = &inner.roots;
+        inner
+            .account_order
+            .retain(|(root, _)| roots.contains_key(root));
+    }
+}
+
+/// A bounded cache of the receipts of recent blocks, keyed by block hash. Only receipts that
+/// have been verified against the receipts root of their block are inserted.
+#[derive(Clone)]
+pub struct ReceiptCache {
+    inner: Arc<RwLock<ReceiptInner>>,
+}
+
+impl ReceiptCache {
+    pub fn new(max_blocks: usize) -> Self {
+        let inner = ReceiptInner {
+            max_blocks,
+            ..Default::default()
+        };
+
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    pub async fn get(&self, block_hash: &H256) -> Option<Vec<TransactionReceipt>> {
+        self.inner.read().await.blocks.get(block_hash).cloned()
+    }
+
+    pub async fn insert(&self, block_hash: H256, receipts: Vec<TransactionReceipt>) {
+        let mut inner = self.inner.write().await;
+        if inner.blocks.insert(block_hash, receipts).is_none() {
+            inner.order.push_back(block_hash);
+        }
+
+        while inner.blocks.len() > inner.max_blocks {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.blocks.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// A bounded cache of canonical block hashes older than the block window, keyed by block
+/// number. Only finalized hashes and hashes proven by walking parent hashes back from them are
+/// inserted, so entries never need to be invalidated. The highest blocks are evicted first,
+/// since they are the cheapest to prove again from the finalized block, and blocks that
+/// have fallen too far behind to be looked up are removed with `remove_below`.
+#[derive(Clone)]
+pub struct BlockHashCache {
+    inner: Arc<RwLock<BlockHashInner>>,
+}
+
+impl BlockHashCache {
+    pub fn new(max_blocks: usize) -> Self {
+        let inner = BlockHashInner {
+            max_blocks,
+            ..Default::default()
+        };
+
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    pub async fn get(&self, number: u64) -> Option<H256> {
+        self.inner.read().await.hashes.get(&number).copied()
+    }
+
+    /// Returns the lowest cached block above `number` and below `before`.
+    pub async fn lowest_above(&self, number: u64, before: u64) -> Option<(u64, H256)> {
+        if number + 1 >= before {
+            return None;
+        }
+
+        self.inner
+            .read()
+            .await
+            .hashes
+            .range(number + 1..before)
+            .next()
+            .map(|(number, hash)| (*number, *hash))
+    }
+
+    pub async fn insert(&self, number: u64, hash: H256) {
+        let mut inner = self.inner.write().await;
+        inner.hashes.insert(number, hash);
+
+        while inner.hashes.len() > inner.max_blocks {
+            inner.hashes.pop_last();
+        }
+    }
+
+    /// Removes every cached block below `number`.
+    pub async fn remove_below(&self, number: u64) {
+        let mut inner = self.inner.write().await;
+        inner.hashes = inner.hashes.split_off(&number);
+    }
+}
+
+#[derive(Default)]
+struct BlockHashInner {
+    hashes: BTreeMap<u64, H256>,
+    max_blocks: usize,
+}
+
+#[derive(Default)]
+struct ReceiptInner {
+    blocks: HashMap<H256, Vec<TransactionReceipt>>,
+    order: VecDeque<H256>,
+    max_blocks: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+    roots: HashMap<H256, RootEntry>,
+    // every cached account in insertion order, across all state roots
+    account_order: VecDeque<(H256, Address)>,
+    code: HashMap<H256, Vec<u8>>,
+    code_order: VecDeque<H256>,
+    max_accounts: usize,
+    max_code: usize,
+}
+
+#[derive(Default)]
+struct RootEntry {
+    block_number: u64,
+    accounts: HashMap<Address, Account>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_insert_merges_slots() {
+        let cache = StateCache::new(16, 16);
+        let root = H256::random();
+        let address = Address::random();
+
+        let mut account = Account::default();
+        account.slots.insert(H256::from_low_u64_be(1), 1.into());
+        cache.insert_account(1, root, &address, &account).await;
+
+        let mut account = Account::default();
+        account.slots.insert(H256::from_low_u64_be(2), 2.into());
+        cache.insert_account(1, root, &address, &account).await;
+
+        let cached = cache.get_account(root, &address).await.unwrap();
+        assert_eq!(cached.slots.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_accounts() {
+        let cache = StateCache::new(2, 16);
+        let root = H256::random();
+        let addresses = [Address::random(), Address::random(), Address::random()];
+
+        for address in &addresses {
+            cache
+                .insert_account(1, root, address, &Account::default())
+                .await;
+        }
+
+        assert!(cache.get_account(root, &addresses[0]).await.is_none());
+        assert!(cache.get_account(root, &addresses[2]).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_bounded_accounts_across_roots() {
+        let cache = StateCache::new(2, 16);
+        let roots = [H256::random(), H256::random(), H256::random()];
+        let address = Address::random();
+
+        for (number, root) in roots.iter().enumerate() {
+            cache
+                .insert_account(number as u64, *root, &address, &Account::default())
+                .await;
+        }
+
+        assert!(!cache.contains_root(roots[0]).await);
+        assert!(cache.get_account(roots[1], &address).await.is_some());
+        assert!(cache.get_account(roots[2], &address).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prune() {
+        let cache = StateCache::new(16, 16);
+        let old_root = H256::random();
+        let finalized_root = H256::random();
+        let new_root = H256::random();
+        let address = Address::random();
+
+        cache
+            .insert_account(1, old_root, &address, &Account::default())
+            .await;
+        cache
+            .insert_account(2, finalized_root, &address, &Account::default())
+            .await;
+        cache
+            .insert_account(10, new_root, &address, &Account::default())
+            .await;
+
+        cache.prune(5, Some(finalized_root)).await;
+
+        assert!(!cache.contains_root(old_root).await);
+        assert!(cache.contains_root(finalized_root).await);
+        assert!(cache.contains_root(new_root).await);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_receipts() {
+        let cache = ReceiptCache::new(2);
+        let hashes = [H256::random(), H256::random(), H256::random()];
+
+        for hash in hashes {
+            cache
+                .insert(hash, vec![TransactionReceipt::default()])
+                .await;
+        }
+
+        assert!(cache.get(&hashes[0]).await.is_none());
+        assert_eq!(cache.get(&hashes[2]).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_hashes() {
+        let cache = BlockHashCache::new(2);
+        let hashes = [H256::random(), H256::random(), H256::random()];
+
+        for (number, hash) in hashes.iter().enumerate() {
+            cache.insert(10 + number as u64, *hash).await;
+        }
+
+        assert!(cache.get(12).await.is_none());
+        assert_eq!(cache.lowest_above(5, 20).await, Some((10, hashes[0])));
+        assert_eq!(cache.lowest_above(10, 12).await, Some((11, hashes[1])));
+        assert_eq!(cache.lowest_above(11, 12).await, None);
+
+        cache.remove_below(11).await;
+        assert!(cache.get(10).await.is_none());
+        assert_eq!(cache.get(11).await, Some(hashes[1]));
+    }
+}
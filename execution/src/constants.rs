@@ -1 +1,83 @@
-pub const PARALLEL_QUERY_BATCH_SIZE: usize = 20;
+// Maximum number of requests sent to the execution rpc in a single json-rpc batch.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+// Retries of rate limited requests to an http execution rpc, and the backoff before the first
+// retry. The backoff grows linearly with each retry unless the provider asks for another one.
+pub const RATE_LIMIT_MAX_RETRIES: u32 = 100;
+pub const RATE_LIMIT_INITIAL_BACKOFF_MS: u64 = 50;
+
+// Maximum number of times a call is executed while discovering the state it touches.
+pub const MAX_EVM_PASSES: usize = 32;
+
+// Gas limit of executions that may still be missing state, so calls with a large gas limit do
+// not loop over placeholder state for long. The call is executed once more at its own gas limit
+// after all the state within this limit was found.
+pub const EVM_DISCOVERY_GAS_LIMIT: u64 = 30_000_000;
+
+// Bounds for the verified state cache shared across calls.
+pub const STATE_CACHE_MAX_ACCOUNTS: usize = 10_000;
+pub const STATE_CACHE_MAX_CODE: usize = 1_000;
+
+// Default maximum number of logs proven for a single request, to avoid blocking the client
+// for too long.
+pub const MAX_SUPPORTED_LOGS_NUMBER: usize = 10_000;
+
+// Health scoring for execution rpcs backed by several providers.
+pub const PROVIDER_MAX_HEALTH: i64 = 100;
+pub const PROVIDER_HEALTHY_THRESHOLD: i64 = 50;
+pub const PROVIDER_ERROR_PENALTY: i64 = 20;
+pub const PROVIDER_INVALID_PENALTY: i64 = 100;
+pub const PROVIDER_MAX_TRACKED_RESPONSES: usize = 10_000;
+
+// Demoted providers regain one health point per interval, so they are eventually tried again.
+pub const PROVIDER_RECOVERY_INTERVAL_MS: u64 = 1_000;
+
+// Filters that are not polled for this long are removed, matching geth.
+pub const FILTER_TIMEOUT_SECS: u64 = 300;
+
+// Number of blocks buffered for each block subscriber before it starts skipping blocks.
+pub const BLOCK_SUBSCRIPTION_CAPACITY: usize = 64;
+
+// Deepest block below the finalized block whose hash is proven by walking the header chain,
+// about four weeks of 12 second slots.
+pub const MAX_HISTORICAL_BLOCK_DEPTH: u64 = 201_600;
+
+// Number of proven historical block hashes that are cached, and the interval between the
+// checkpoints cached while walking the header chain. Later walks through a range that was
+// already walked start from the closest checkpoint above the block.
+pub const BLOCK_HASH_CACHE_MAX_BLOCKS: usize = 8_192;
+pub const BLOCK_HASH_CHECKPOINT_INTERVAL: u64 = 256;
+
+// Maximum number of blocks covered by a single fee history request, matching geth.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+// Gas price oracle parameters, matching geth's defaults. The oracle samples the lowest tips
+// of recent blocks and suggests a percentile of them.
+pub const GAS_PRICE_ORACLE_BLOCKS: u64 = 20;
+pub const GAS_PRICE_ORACLE_SAMPLES: usize = 3;
+pub const GAS_PRICE_ORACLE_PERCENTILE: usize = 60;
+pub const GAS_PRICE_ORACLE_IGNORE_PRICE: u64 = 2;
+pub const GAS_PRICE_ORACLE_MAX_PRIORITY_FEE: u64 = 500_000_000_000;
+
+// Priority fee suggested when recent blocks have no transactions to sample.
+pub const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000;
+
+// Gas consumed by each blob of an EIP-4844 transaction.
+pub const GAS_PER_BLOB: u64 = 131_072;
+
+// EIP-1559 parameters used to derive the base fee of the next block.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+// Submitted transactions are rebroadcast this often while pending, and considered dropped if
+// they are not included within the timeout. Settled transactions are forgotten after the
+// retention period.
+pub const TRACKED_TX_REBROADCAST_BLOCKS: u64 = 5;
+pub const TRACKED_TX_DROP_BLOCKS: u64 = 256;
+pub const TRACKED_TX_RETENTION_BLOCKS: u64 = 64;
+
+// Seconds between the timestamps of consecutive blocks mined by a local fork.
+pub const FORK_BLOCK_TIME: u64 = 12;
+
+// Seconds between the timestamps of consecutive simulated blocks, unless overridden.
+pub const SIMULATION_BLOCK_TIME: u64 = 12;
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+use common::types::{Block, BlockTag};
+use ethers::types::{transaction::eip2930::AccessListItem, H256};
+use eyre::{eyre, Report, Result};
+use revm::{
+    db::DatabaseRef,
+    primitives::{AccountInfo, BlockEnv, Bytecode, B160, B256, KECCAK_EMPTY, U256},
+    Database,
+};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::trace;
+
+use crate::{rpc::ExecutionRpc, types::Account, ExecutionClient};
+
+/// A revm database serving the state of a single verified block.
+///
+/// The block is resolved when the database is created, so the database keeps serving the same
+/// state as new blocks arrive. Accounts, storage slots, code and block hashes are fetched from
+/// the execution rpc the first time they are read, checked against the verified block, and
+/// cached. Clones share the cache.
+///
+/// revm databases are synchronous, so every fetch blocks the calling thread until it completes.
+/// The database must therefore be used from a multi-threaded tokio runtime, or from a thread
+/// outside of any runtime. A current-thread runtime can't be blocked, so fetches made from one
+/// fail instead. State that is known ahead of execution can be loaded in batches with
+/// [`VerifiedDB::prefetch`] instead.
+///
+/// Proofs are checked against the state root of the pinned block, so state can be served for as
+/// long as the execution rpc serves proofs at that block, even after it leaves the block window
+/// of the execution client.
+#[derive(Clone)]
+pub struct VerifiedDB<R: ExecutionRpc> {
+    execution: Arc<ExecutionClient<R>>,
+    block: Arc<Block>,
+    cache: Arc<RwLock<DbCache>>,
+    handle: Handle,
+}
+
+#[derive(Default)]
+struct DbCache {
+    accounts: HashMap<B160, AccountInfo>,
+    storage: HashMap<B160, HashMap<U256, U256>>,
+    code: HashMap<B256, Bytecode>,
+    block_hashes: HashMap<u64, B256>,
+}
+
+impl<R: ExecutionRpc> VerifiedDB<R> {
+    /// Creates a database pinned to the verified block at `tag`.
+    pub async fn new(execution: Arc<ExecutionClient<R>>, tag: BlockTag) -> Result<Self> {
+        let block = execution.get_block(tag, false).await?;
+
+        Ok(Self {
+            execution,
+            block: Arc::new(block),
+            cache: Arc::new(RwLock::new(DbCache::default())),
+            handle: Handle::try_current()?,
+        })
+    }
+
+    /// Returns the block this database is pinned to. Its transactions are not included.
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// Returns the revm block environment of the pinned block.
+    pub fn block_env(&self) -> BlockEnv {
+        BlockEnv {
+            number: U256::from(self.block.number.as_u64()),
+            coinbase: self.block.miner.into(),
+            timestamp: U256::from(self.block.timestamp.as_u64()),
+            difficulty: self.block.difficulty.into(),
+            prevrandao: Some(self.block.mix_hash.into()),
+            basefee: self.block.base_fee_per_gas.into(),
+            gas_limit: U256::from(self.block.gas_limit.as_u64()),
+        }
+    }
+
+    /// Fetches and verifies several accounts along with the listed storage slots as a single
+    /// batch, so reading them later does not block. Accounts that are already cached are
+    /// fetched again only for their missing slots.
+    pub async fn prefetch(&self, items: &[AccessListItem]) -> Result<()> {
+        let accounts = self
+            .execution
+            .get_block_accounts(items, &self.block)
+            .await?;
+
+        let mut cache = self.cache.write().unwrap();
+        for (item, account) in items.iter().zip(accounts) {
+            cache.insert_account(item.address.into(), account);
+        }
+
+        Ok(())
+    }
+
+    fn fetch(&self, address: B160, slots: Vec<H256>) -> Result<()> {
+        trace!(
+            target: "helios::evm",
+            "fetch verified state for address={:?}, slots={:?}",
+            address,
+            slots
+        );
+
+        let item = AccessListItem {
+            address: address.into(),
+            storage_keys: slots,
+        };
+
+        self.block_on(self.prefetch(std::slice::from_ref(&item)))?
+    }
+
+    fn block_on<F: Future>(&self, fut: F) -> Result<F::Output> {
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                Ok(tokio::task::block_in_place(|| handle.block_on(fut)))
+            }
+            Ok(_) => Err(eyre!(
+                "cannot fetch state from a current-thread runtime, use a multi-threaded runtime"
+            )),
+            Err(_) => Ok(self.handle.block_on(fut)),
+        }
+    }
+}
+
+impl DbCache {
+    fn insert_account(&mut self, address: B160, account: Account) {
+        let code_hash: B256 = account.code_hash.into();
+        if code_hash != KECCAK_EMPTY {
+            let code = Bytecode::new_raw(account.code.into());
+            self.code.entry(code_hash).or_insert(code);
+        }
+
+        let code = self.code.get(&code_hash).cloned();
+        self.accounts.entry(address).or_insert(AccountInfo {
+            balance: account.balance.into(),
+            nonce: account.nonce,
+            code_hash,
+            code,
+        });
+
+        let storage = self.storage.entry(address).or_default();
+        for (slot, value) in account.slots {
+            storage.insert(B256::from(slot).into(), value.into());
+        }
+    }
+}
+
+impl<R: ExecutionRpc> DatabaseRef for VerifiedDB<R> {
+    type Error = Report;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Report> {
+        if let Some(info) = self.cache.read().unwrap().accounts.get(&address) {
+            return Ok(Some(info.clone()));
+        }
+
+        self.fetch(address, Vec::new())?;
+
+        let cache = self.cache.read().unwrap();
+        Ok(cache.accounts.get(&address).cloned())
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Report> {
+        if code_hash == KECCAK_EMPTY {
+            return Ok(Bytecode::new());
+        }
+
+        // code is always fetched along with the account it belongs to
+        self.cache
+            .read()
+            .unwrap()
+            .code
+            .get(&code_hash)
+            .cloned()
+            .ok_or_else(|| eyre!("code {:?} was not fetched with its account", code_hash))
+    }
+
+    fn storage(&self, address: B160, slot: U256) -> Result<U256, Report> {
+        let cached = self
+            .cache
+            .read()
+            .unwrap()
+            .storage
+            .get(&address)
+            .and_then(|storage| storage.get(&slot).copied());
+
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let key = H256::from_slice(&slot.to_be_bytes::<32>());
+        self.fetch(address, vec![key])?;
+
+        let cache = self.cache.read().unwrap();
+        let storage = cache.storage.get(&address);
+        Ok(storage
+            .and_then(|storage| storage.get(&slot).copied())
+            .unwrap_or_default())
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Report> {
+        let number_ethers: ethers::types::U256 = number.into();
+        let number = number_ethers.as_u64();
+
+        if let Some(hash) = self.cache.read().unwrap().block_hashes.get(&number) {
+            return Ok(*hash);
+        }
+
+        trace!(target: "helios::evm", "fetch block hash for block={:?}", number);
+        let block = self.block_on(self.execution.get_block(BlockTag::Number(number), false))??;
+
+        let hash = block.hash.into();
+        self.cache
+            .write()
+            .unwrap()
+            .block_hashes
+            .insert(number, hash);
+
+        Ok(hash)
+    }
+}
+
+impl<R: ExecutionRpc> Database for VerifiedDB<R> {
+    type Error = Report;
+
+    fn basic(&mut self, address: B160) -> Result<Option<AccountInfo>, Report> {
+        DatabaseRef::basic(self, address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Report> {
+        DatabaseRef::code_by_hash(self, code_hash)
+    }
+
+    fn storage(&mut self, address: B160, slot: U256) -> Result<U256, Report> {
+        DatabaseRef::storage(self, address, slot)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Report> {
+        DatabaseRef::block_hash(self, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tokio::sync::{mpsc::channel, watch};
+
+    use crate::{rpc::mock_rpc::MockRpc, state::State};
+
+    use super::*;
+
+    async fn get_client(state_root: H256) -> Arc<ExecutionClient<MockRpc>> {
+        let (_, block_recv) = channel(256);
+        let (_, finalized_recv) = watch::channel(None);
+        let (_, safe_recv) = watch::channel(None);
+        let state = State::new(block_recv, finalized_recv, safe_recv, 64);
+
+        let block = Block {
+            state_root,
+            ..Default::default()
+        };
+
+        state.push_block(block).await;
+        Arc::new(ExecutionClient::new("testdata/", state).unwrap())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verified_db() {
+        let state_root =
+            H256::from_str("0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d")
+                .unwrap();
+        let execution = get_client(state_root).await;
+        let mut db = VerifiedDB::new(execution, BlockTag::Latest).await.unwrap();
+
+        let address = B160::from_str("0x14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+        let account = Database::basic(&mut db, address).unwrap().unwrap();
+
+        assert_eq!(account.balance, U256::from(0x48c27395000u64));
+
+        // clones share the cache of verified state
+        let cached = DatabaseRef::basic(&db.clone(), address).unwrap().unwrap();
+        assert_eq!(cached, account);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verified_db_bad_proof() {
+        let execution = get_client(H256::zero()).await;
+        let db = VerifiedDB::new(execution, BlockTag::Latest).await.unwrap();
+
+        let address = B160::from_str("0x14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+        assert!(DatabaseRef::basic(&db, address).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verified_db_current_thread() {
+        let state_root =
+            H256::from_str("0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d")
+                .unwrap();
+        let execution = get_client(state_root).await;
+        let db = VerifiedDB::new(execution, BlockTag::Latest).await.unwrap();
+
+        // fetching would block the only thread of the runtime
+        let address = B160::from_str("0x14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+        assert!(DatabaseRef::basic(&db, address).is_err());
+        assert!(DatabaseRef::block_hash(&db, U256::from(1)).is_err());
+
+        // prefetched state is still served
+        let item = AccessListItem {
+            address: address.into(),
+            storage_keys: Vec::new(),
+        };
+
+        db.prefetch(&[item]).await.unwrap();
+        assert!(DatabaseRef::basic(&db, address).unwrap().is_some());
+    }
+}
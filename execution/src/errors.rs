@@ -1,4 +1,5 @@
 use bytes::Bytes;
+use common::types::BlockTag;
 use ethers::{
     abi::AbiDecode,
     types::{Address, H256, U256},
@@ -6,22 +7,38 @@ use ethers::{
 use eyre::Report;
 use thiserror::Error;
 
+use crate::proof::ProofError;
+
 #[derive(Debug, Error)]
 pub enum ExecutionError {
-    #[error("invalid account proof for address: {0}")]
-    InvalidAccountProof(Address),
-    #[error("invalid storage proof for address: {0}, slot: {1}")]
-    InvalidStorageProof(Address, H256),
+    #[error("invalid account proof for address: {0}: {1}")]
+    InvalidAccountProof(Address, ProofError),
+    #[error("invalid storage proof for address: {0}, slot: {1}: {2}")]
+    InvalidStorageProof(Address, H256, ProofError),
+    #[error("proof for address: {0} does not match the requested account and slots")]
+    ProofResponseMismatch(Address),
     #[error("code hash mismatch for address: {0}, found: {1}, expected: {2}")]
     CodeHashMismatch(Address, String, String),
     #[error("receipt root mismatch for tx: {0}")]
     ReceiptRootMismatch(String),
+    #[error("receipt for tx: {0} is missing its {1}")]
+    IncompleteReceipt(String, &'static str),
+    #[error("receipts root mismatch for block: {0}")]
+    BlockReceiptsRootMismatch(u64),
+    #[error("transactions root mismatch for block: {0}")]
+    BlockTransactionsRootMismatch(u64),
+    #[error("invalid block header for block: {0}")]
+    InvalidBlockHeader(u64),
+    #[error("block {0} is more than {1} blocks behind the finalized block")]
+    HistoricalBlockTooOld(u64, u64),
     #[error("missing transaction for tx: {0}")]
     MissingTransaction(String),
     #[error("could not prove receipt for tx: {0}")]
     NoReceiptForTransaction(String),
     #[error("missing log for transaction: {0}, index: {1}")]
     MissingLog(String, U256),
+    #[error("execution rpc returned {1} responses to a batch of {0} requests")]
+    IncompleteBatchResponse(usize, usize),
     #[error("too many logs to prove: {0}, current limit is: {1}")]
     TooManyLogsToProve(usize, usize),
     #[error("execution rpc is for the incorect network")]
@@ -30,12 +47,28 @@ pub enum ExecutionError {
     InvalidBaseGaseFee(U256, U256, u64),
     #[error("Invalid gas used ratio of helios {0} vs rpc endpoint {1} at block {2}")]
     InvalidGasUsedRatio(f64, f64, u64),
+    #[error("invalid reward percentiles: {0:?}")]
+    InvalidRewardPercentiles(Vec<f64>),
+    #[error("transaction is not tracked: {0}")]
+    TransactionNotTracked(String),
+    #[error("transaction was dropped: {0}")]
+    TransactionDropped(String),
+    #[error("transaction {0} was replaced by {1}")]
+    TransactionReplaced(String, String),
     #[error("Block {0} not found")]
     BlockNotFoundError(u64),
     #[error("Helios Execution Payload is empty")]
     EmptyExecutionPayload(),
     #[error("User query for block {0} but helios oldest block is {1}")]
     InvalidBlockRange(u64, u64),
+    #[error("filter not found: {0}")]
+    FilterNotFound(U256),
+    #[error("state of block {0} is not available in the fork")]
+    ForkStateUnavailable(BlockTag),
+    #[error("account is not impersonated: {0:?}")]
+    AccountNotImpersonated(Address),
+    #[error("invalid transaction: {0}")]
+    InvalidForkTransaction(String),
 }
 
 /// Errors that can occur during evm.rs calls
@@ -47,6 +80,9 @@ pub enum EvmError {
     #[error("evm error: {0:?}")]
     Generic(String),
 
+    #[error("state was still missing after {0} executions")]
+    TooManyPasses(usize),
+
     #[error("rpc error: {0:?}")]
     RpcError(Report),
 }
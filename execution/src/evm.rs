@@ -1,4 +1,8 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
 
 use bytes::Bytes;
 use common::types::BlockTag;
@@ -7,14 +11,18 @@ use eyre::{Report, Result};
 use futures::future::join_all;
 use revm::{
     primitives::{
-        AccountInfo, Bytecode, Env, ExecutionResult, ResultAndState, TransactTo, B160, B256, U256,
+        AccountInfo, BlockEnv, Bytecode, Env, ExecutionResult, ResultAndState, TransactTo, B160,
+        B256, U256,
     },
     Database, EVM,
 };
-use tracing::trace;
+use tracing::{debug, trace};
 
 use crate::{
-    constants::PARALLEL_QUERY_BATCH_SIZE, errors::EvmError, rpc::ExecutionRpc, types::CallOpts,
+    constants::{EVM_DISCOVERY_GAS_LIMIT, MAX_EVM_PASSES, SIMULATION_BLOCK_TIME},
+    errors::{EvmError, ExecutionError},
+    rpc::ExecutionRpc,
+    types::{BlockOverrides, CallOpts, SimulatedBlock, SimulatedCall, SimulationBlock},
 };
 
 use super::ExecutionClient;
@@ -54,6 +62,66 @@ impl<R: ExecutionRpc> Evm<R> {
         }
     }
 
+    /// Runs an ordered bundle of calls on top of the state at this evm's block. State changes
+    /// made by each call are visible to the calls following it, including those in later
+    /// simulated blocks.
+    pub async fn simulate(
+        &mut self,
+        blocks: &[SimulationBlock],
+    ) -> Result<Vec<SimulatedBlock>, EvmError> {
+        let calls = blocks
+            .iter()
+            .flat_map(|block| block.calls.iter().cloned())
+            .collect::<Vec<_>>();
+
+        self.evm
+            .db
+            .as_mut()
+            .unwrap()
+            .state
+            .prefetch_state(&calls)
+            .await
+            .map_err(|err| EvmError::Generic(err.to_string()))?;
+
+        let mut block_env = self.get_block_env(self.tag).await;
+        let mut simulated_blocks = Vec::with_capacity(blocks.len());
+
+        for (i, block) in blocks.iter().enumerate() {
+            if i > 0 {
+                block_env.number += U256::from(1);
+                block_env.timestamp += U256::from(SIMULATION_BLOCK_TIME);
+            }
+
+            if let Some(overrides) = &block.block_overrides {
+                apply_block_overrides(&mut block_env, overrides);
+            }
+
+            let number: ethers::types::U256 = block_env.number.into();
+            let timestamp: ethers::types::U256 = block_env.timestamp.into();
+
+            let mut gas_used = 0;
+            let mut calls = Vec::with_capacity(block.calls.len());
+            for opts in &block.calls {
+                let env = self.build_env(opts, block_env.clone());
+                let ResultAndState { result, state } = self.transact(env).await?;
+                self.evm.db.as_mut().unwrap().state.commit(state);
+
+                let call = simulated_call(result, number.as_u64());
+                gas_used += call.gas_used.as_u64();
+                calls.push(call);
+            }
+
+            simulated_blocks.push(SimulatedBlock {
+                number: number.as_u64().into(),
+                timestamp: timestamp.as_u64().into(),
+                gas_used: gas_used.into(),
+                calls,
+            });
+        }
+
+        Ok(simulated_blocks)
+    }
+
     async fn call_inner(&mut self, opts: &CallOpts) -> Result<ResultAndState, EvmError> {
         let env = self.get_env(opts, self.tag).await;
         self.evm
@@ -61,28 +129,51 @@ impl<R: ExecutionRpc> Evm<R> {
             .as_mut()
             .unwrap()
             .state
-            .prefetch_state(opts)
+            .prefetch_state(std::slice::from_ref(opts))
             .await
             .map_err(|err| EvmError::Generic(err.to_string()))?;
 
-        let tx_res = loop {
-            self.evm.env = env.clone();
-            let res = self.evm.transact();
-            let mut db = self.evm.db.take().unwrap();
+        self.transact(env).await
+    }
 
-            if res.is_err() && db.state.needs_update() {
-                db.state.update_state().await.unwrap();
-                self.evm = EVM::<ProofDB<R>>::new();
-                self.evm.database(db);
+    /// Executes the transaction described by `env`. State that was not prefetched is served as
+    /// empty during execution and recorded, so a single pass gathers every missing account,
+    /// slot and block hash. These are then fetched as one batch and the transaction is
+    /// re-executed until no state is missing, for at most `MAX_EVM_PASSES` passes.
+    ///
+    /// Passes that may still run on placeholder state are capped at `EVM_DISCOVERY_GAS_LIMIT`,
+    /// and the result is taken from a pass at the caller's gas limit.
+    async fn transact(&mut self, env: Env) -> Result<ResultAndState, EvmError> {
+        let mut pass_env = env.clone();
+        pass_env.tx.gas_limit = env.tx.gas_limit.min(EVM_DISCOVERY_GAS_LIMIT);
+
+        for _ in 0..MAX_EVM_PASSES {
+            self.evm.env = pass_env.clone();
+            let res = self.evm.transact();
+            let state = &mut self.evm.db.as_mut().unwrap().state;
+
+            if state.needs_update() {
+                state
+                    .update_state()
+                    .await
+                    .map_err(|err| EvmError::Generic(err.to_string()))?;
+            } else if pass_env.tx.gas_limit < env.tx.gas_limit {
+                // every piece of state within the capped gas was found
+                pass_env = env.clone();
             } else {
-                break res;
+                return res.map_err(|_| EvmError::Generic("evm error".to_string()));
             }
-        };
+        }
 
-        tx_res.map_err(|_| EvmError::Generic("evm error".to_string()))
+        Err(EvmError::TooManyPasses(MAX_EVM_PASSES))
     }
 
     async fn get_env(&self, opts: &CallOpts, tag: BlockTag) -> Env {
+        let block = self.get_block_env(tag).await;
+        self.build_env(opts, block)
+    }
+
+    fn build_env(&self, opts: &CallOpts, block: BlockEnv) -> Env {
         let mut env = Env::default();
 
         env.tx.transact_to = TransactTo::Call(opts.to.unwrap_or_default().into());
@@ -99,6 +190,13 @@ impl<R: ExecutionRpc> Evm<R> {
             .map(|g| B256::from(g).into())
             .unwrap_or_default();
 
+        env.block = block;
+        env.cfg.chain_id = U256::from(self.chain_id);
+
+        env
+    }
+
+    async fn get_block_env(&self, tag: BlockTag) -> BlockEnv {
         let block = self
             .evm
             .db
@@ -109,14 +207,94 @@ impl<R: ExecutionRpc> Evm<R> {
             .await
             .unwrap();
 
-        env.block.number = U256::from(block.number.as_u64());
-        env.block.coinbase = block.miner.into();
-        env.block.timestamp = U256::from(block.timestamp.as_u64());
-        env.block.difficulty = block.difficulty.into();
+        BlockEnv {
+            number: U256::from(block.number.as_u64()),
+            coinbase: block.miner.into(),
+            timestamp: U256::from(block.timestamp.as_u64()),
+            difficulty: block.difficulty.into(),
+            ..Default::default()
+        }
+    }
+}
 
-        env.cfg.chain_id = U256::from(self.chain_id);
+fn apply_block_overrides(env: &mut BlockEnv, overrides: &BlockOverrides) {
+    if let Some(number) = overrides.number {
+        env.number = U256::from(number.as_u64());
+    }
 
-        env
+    if let Some(time) = overrides.time {
+        env.timestamp = U256::from(time.as_u64());
+    }
+
+    if let Some(gas_limit) = overrides.gas_limit {
+        env.gas_limit = U256::from(gas_limit.as_u64());
+    }
+
+    if let Some(fee_recipient) = overrides.fee_recipient {
+        env.coinbase = fee_recipient.into();
+    }
+
+    if let Some(prev_randao) = overrides.prev_randao {
+        env.prevrandao = Some(prev_randao.into());
+    }
+
+    if let Some(base_fee) = overrides.base_fee_per_gas {
+        env.basefee = base_fee.into();
+    }
+}
+
+fn simulated_call(result: ExecutionResult, block_number: u64) -> SimulatedCall {
+    match result {
+        ExecutionResult::Success {
+            gas_used,
+            logs,
+            output,
+            ..
+        } => {
+            let logs = logs
+                .into_iter()
+                .map(|log| ethers::types::Log {
+                    address: ethers::types::Address::from_slice(log.address.as_bytes()),
+                    topics: log
+                        .topics
+                        .iter()
+                        .map(|topic| ethers::types::H256::from_slice(topic.as_bytes()))
+                        .collect(),
+                    data: log.data.to_vec().into(),
+                    block_number: Some(block_number.into()),
+                    ..Default::default()
+                })
+                .collect();
+
+            SimulatedCall {
+                status: 1.into(),
+                return_data: output.into_data().to_vec().into(),
+                gas_used: gas_used.into(),
+                logs,
+                error: None,
+            }
+        }
+        ExecutionResult::Revert { gas_used, output } => {
+            let mut msg = "execution reverted".to_string();
+            if let Some(reason) = EvmError::decode_revert_reason(&output) {
+                msg = format!("{msg}: {reason}");
+            }
+
+            SimulatedCall {
+                status: 0.into(),
+                return_data: output.to_vec().into(),
+                gas_used: gas_used.into(),
+                logs: Vec::new(),
+                error: Some(msg),
+            }
+        }
+        ExecutionResult::Halt { reason, gas_used } => SimulatedCall {
+            status: 0.into(),
+            return_data: Default::default(),
+            gas_used: gas_used.into(),
+            logs: Vec::new(),
+            error: Some(format!("{reason:?}")),
+        },
     }
 }
 
@@ -132,6 +310,7 @@ impl<R: ExecutionRpc> ProofDB<R> {
     }
 }
 
+#[derive(PartialEq)]
 enum StateAccess {
     Basic(B160),
     BlockHash(u64),
@@ -142,8 +321,10 @@ struct EvmState<R: ExecutionRpc> {
     basic: HashMap<B160, AccountInfo>,
     block_hash: HashMap<u64, B256>,
     storage: HashMap<B160, HashMap<U256, U256>>,
+    // accounts self-destructed by an earlier call, whose storage no longer matches the chain
+    destroyed: HashSet<B160>,
     block: BlockTag,
-    access: Option<StateAccess>,
+    accesses: Vec<StateAccess>,
     execution: Arc<ExecutionClient<R>>,
 }
 
@@ -155,57 +336,87 @@ impl<R: ExecutionRpc> EvmState<R> {
             basic: HashMap::new(),
             storage: HashMap::new(),
             block_hash: HashMap::new(),
-            access: None,
+            destroyed: HashSet::new(),
+            accesses: Vec::new(),
         }
     }
 
+    /// Fetches every missing account, slot and block hash recorded during the last execution.
+    /// Storage slots are grouped by account so each account is proven once.
     pub async fn update_state(&mut self) -> Result<()> {
-        if let Some(access) = &self.access.take() {
-            match access {
-                StateAccess::Basic(address) => {
-                    let account = self
-                        .execution
-                        .get_account(&(*address).into(), None, self.block)
-                        .await?;
-                    let bytecode = Bytecode::new_raw(account.code.into());
-                    let account = AccountInfo::new(account.balance.into(), account.nonce, bytecode);
-                    self.basic.insert(*address, account);
-                }
+        let mut accounts: Vec<(B160, Vec<ethers::types::H256>)> = Vec::new();
+        let mut block_numbers = Vec::new();
+
+        for access in std::mem::take(&mut self.accesses) {
+            let (address, slot) = match access {
+                StateAccess::Basic(address) => (address, None),
                 StateAccess::Storage(address, slot) => {
-                    let slot_ethers = ethers::types::H256::from_slice(&slot.to_be_bytes::<32>());
-                    let slots = [slot_ethers];
-                    let account = self
-                        .execution
-                        .get_account(&(*address).into(), Some(&slots), self.block)
-                        .await?;
-
-                    let storage = self.storage.entry(*address).or_default();
-                    let value = *account.slots.get(&slot_ethers).unwrap();
-                    storage.insert(*slot, value.into());
+                    let slot = ethers::types::H256::from_slice(&slot.to_be_bytes::<32>());
+                    (address, Some(slot))
                 }
                 StateAccess::BlockHash(number) => {
-                    let block = self
-                        .execution
-                        .get_block(BlockTag::Number(*number), false)
-                        .await?;
-                    self.block_hash.insert(*number, block.hash.into());
+                    block_numbers.push(number);
+                    continue;
                 }
+            };
+
+            let index = match accounts.iter().position(|(a, _)| *a == address) {
+                Some(index) => index,
+                None => {
+                    accounts.push((address, Vec::new()));
+                    accounts.len() - 1
+                }
+            };
+
+            accounts[index].1.extend(slot);
+        }
+
+        let requests = accounts
+            .iter()
+            .map(|(address, slots)| AccessListItem {
+                address: (*address).into(),
+                storage_keys: slots.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let fetched = self.execution.get_accounts(&requests, self.block).await?;
+
+        for ((address, _), account) in accounts.into_iter().zip(fetched) {
+            // never overwrite state committed by earlier calls in a simulation
+            self.basic.entry(address).or_insert_with(|| {
+                let bytecode = Bytecode::new_raw(account.code.clone().into());
+                AccountInfo::new(account.balance.into(), account.nonce, bytecode)
+            });
+
+            let storage = self.storage.entry(address).or_default();
+            for (slot, value) in account.slots {
+                storage
+                    .entry(B256::from(slot).into())
+                    .or_insert(value.into());
             }
         }
 
+        for number in block_numbers {
+            let block = self
+                .execution
+                .get_block(BlockTag::Number(number), false)
+                .await?;
+            self.block_hash.insert(number, block.hash.into());
+        }
+
         Ok(())
     }
 
     pub fn needs_update(&self) -> bool {
-        self.access.is_some()
+        !self.accesses.is_empty()
     }
 
     pub fn get_basic(&mut self, address: B160) -> Result<AccountInfo> {
         if let Some(account) = self.basic.get(&address) {
             Ok(account.clone())
         } else {
-            self.access = Some(StateAccess::Basic(address));
-            eyre::bail!("state missing");
+            self.record_access(StateAccess::Basic(address));
+            Ok(AccountInfo::default())
         }
     }
 
@@ -213,9 +424,11 @@ impl<R: ExecutionRpc> EvmState<R> {
         let storage = self.storage.entry(address).or_default();
         if let Some(slot) = storage.get(&slot) {
             Ok(*slot)
+        } else if self.destroyed.contains(&address) {
+            Ok(U256::ZERO)
         } else {
-            self.access = Some(StateAccess::Storage(address, slot));
-            eyre::bail!("state missing");
+            self.record_access(StateAccess::Storage(address, slot));
+            Ok(U256::ZERO)
         }
     }
 
@@ -223,72 +436,120 @@ impl<R: ExecutionRpc> EvmState<R> {
         if let Some(hash) = self.block_hash.get(&block) {
             Ok(*hash)
         } else {
-            self.access = Some(StateAccess::BlockHash(block));
-            eyre::bail!("state missing");
+            self.record_access(StateAccess::BlockHash(block));
+            Ok(B256::zero())
         }
     }
 
-    pub async fn prefetch_state(&mut self, opts: &CallOpts) -> Result<()> {
-        let mut list = self
-            .execution
-            .rpc
-            .create_access_list(opts, self.block)
-            .await
-            .map_err(EvmError::RpcError)?
-            .0;
-
-        let from_access_entry = AccessListItem {
-            address: opts.from.unwrap_or_default(),
-            storage_keys: Vec::default(),
-        };
+    fn record_access(&mut self, access: StateAccess) {
+        if !self.accesses.contains(&access) {
+            self.accesses.push(access);
+        }
+    }
 
-        let to_access_entry = AccessListItem {
-            address: opts.to.unwrap_or_default(),
-            storage_keys: Vec::default(),
-        };
+    pub fn commit(&mut self, changes: revm::primitives::State) {
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
 
-        let coinbase = self.execution.get_block(self.block, false).await?.miner;
-        let producer_access_entry = AccessListItem {
-            address: coinbase,
-            storage_keys: Vec::default(),
-        };
+            if account.is_selfdestructed() {
+                self.basic.insert(address, AccountInfo::default());
+                self.storage.insert(address, HashMap::new());
+                self.destroyed.insert(address);
+                continue;
+            }
 
-        let list_addresses = list.iter().map(|elem| elem.address).collect::<Vec<_>>();
+            let storage = self.storage.entry(address).or_default();
+            for (slot, value) in account.storage {
+                storage.insert(slot, value.present_value);
+            }
 
-        if !list_addresses.contains(&from_access_entry.address) {
-            list.push(from_access_entry)
+            self.basic.insert(address, account.info);
         }
+    }
 
-        if !list_addresses.contains(&to_access_entry.address) {
-            list.push(to_access_entry)
-        }
+    /// Returns the accounts and slots touched by `calls`: the union of the access lists the
+    /// execution rpc builds for each call, along with every sender and recipient and the
+    /// coinbase. Access lists are built against the state of the block, so those of later calls
+    /// in a bundle may miss state written by earlier ones, which is then discovered during
+    /// execution.
+    async fn access_list(&self, calls: &[CallOpts]) -> Result<Vec<AccessListItem>> {
+        let access_list_futs = calls
+            .iter()
+            .map(|opts| self.execution.create_access_list(opts, self.block));
+
+        let mut entries: Vec<AccessListItem> = Vec::new();
+        for (opts, access_list) in calls.iter().zip(join_all(access_list_futs).await) {
+            // an access list only saves round trips, so a provider that cannot build one is
+            // not fatal and the state is discovered during execution instead
+            let access_list = access_list.unwrap_or_else(|err| {
+                debug!(
+                    target: "helios::evm",
+                    error = %err,
+                    "could not create access list, discovering state during execution"
+                );
+                None
+            });
 
-        if !list_addresses.contains(&producer_access_entry.address) {
-            list.push(producer_access_entry)
-        }
+            entries.extend(access_list.unwrap_or_default().0);
 
-        let mut account_map = HashMap::new();
-        for chunk in list.chunks(PARALLEL_QUERY_BATCH_SIZE) {
-            let account_chunk_futs = chunk.iter().map(|account| {
-                let account_fut = self.execution.get_account(
-                    &account.address,
-                    Some(account.storage_keys.as_slice()),
-                    self.block,
-                );
-                async move { (account.address, account_fut.await) }
+            entries.push(AccessListItem {
+                address: opts.from.unwrap_or_default(),
+                storage_keys: Vec::default(),
             });
 
-            let account_chunk = join_all(account_chunk_futs).await;
+            entries.push(AccessListItem {
+                address: opts.to.unwrap_or_default(),
+                storage_keys: Vec::default(),
+            });
+        }
 
-            account_chunk
-                .into_iter()
-                .filter(|i| i.1.is_ok())
-                .for_each(|(key, value)| {
-                    account_map.insert(key, value.ok().unwrap());
-                });
+        let coinbase = self.execution.get_block(self.block, false).await?.miner;
+        entries.push(AccessListItem {
+            address: coinbase,
+            storage_keys: Vec::default(),
+        });
+
+        // merge the access lists of every call so each account is proven only once
+        let mut list: Vec<AccessListItem> = Vec::new();
+        for entry in entries {
+            match list.iter_mut().find(|item| item.address == entry.address) {
+                Some(item) => {
+                    for key in entry.storage_keys {
+                        if !item.storage_keys.contains(&key) {
+                            item.storage_keys.push(key);
+                        }
+                    }
+                }
+                None => list.push(entry),
+            }
         }
 
-        for (address, account) in account_map {
+        Ok(list)
+    }
+
+    /// Prefetches the state touched by `calls` as a single batch.
+    pub async fn prefetch_state(&mut self, calls: &[CallOpts]) -> Result<()> {
+        let list = self.access_list(calls).await?;
+
+        // accounts that fail to prefetch are fetched again on demand during execution, unless
+        // the provider served state that failed verification
+        let accounts = match self.execution.get_accounts(&list, self.block).await {
+            Ok(accounts) => accounts,
+            Err(err) if is_verification_error(&err) => return Err(err),
+            Err(err) => {
+                debug!(
+                    target: "helios::evm",
+                    error = %err,
+                    "could not prefetch state, discovering it during execution"
+                );
+                Vec::new()
+            }
+        };
+
+        for (item, account) in list.iter().zip(accounts) {
+            let address = item.address;
             let info = AccountInfo::new(
                 account.balance.into(),
                 account.nonce,
@@ -342,6 +603,18 @@ impl<R: ExecutionRpc> Database for ProofDB<R> {
     }
 }
 
+fn is_verification_error(err: &Report) -> bool {
+    matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(
+            ExecutionError::InvalidAccountProof(..)
+                | ExecutionError::InvalidStorageProof(..)
+                | ExecutionError::ProofResponseMismatch(..)
+                | ExecutionError::CodeHashMismatch(..)
+        )
+    )
+}
+
 fn is_precompile(address: &B160) -> bool {
     address.le(&B160::from_str("0x0000000000000000000000000000000000000009").unwrap())
         && address.gt(&B160::zero())
@@ -349,6 +622,8 @@ fn is_precompile(address: &B160) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use ethers::types::{transaction::eip2930::AccessList, Address, H256};
+    use revm::primitives::{Account, StorageSlot};
     use tokio::sync::{mpsc::channel, watch};
 
     use crate::{rpc::mock_rpc::MockRpc, state::State};
@@ -358,7 +633,8 @@ mod tests {
     fn get_client() -> ExecutionClient<MockRpc> {
         let (_, block_recv) = channel(256);
         let (_, finalized_recv) = watch::channel(None);
-        let state = State::new(block_recv, finalized_recv, 64);
+        let (_, safe_recv) = watch::channel(None);
+        let state = State::new(block_recv, finalized_recv, safe_recv, 64);
         ExecutionClient::new("testdata/", state).unwrap()
     }
 
@@ -380,4 +656,154 @@ mod tests {
 
         assert_eq!(account, info);
     }
+
+    #[test]
+    fn test_apply_block_overrides() {
+        let mut env = BlockEnv {
+            number: U256::from(100),
+            timestamp: U256::from(1000),
+            ..Default::default()
+        };
+
+        let overrides = BlockOverrides {
+            time: Some(2000.into()),
+            base_fee_per_gas: Some(7.into()),
+            ..Default::default()
+        };
+
+        apply_block_overrides(&mut env, &overrides);
+
+        assert_eq!(env.number, U256::from(100));
+        assert_eq!(env.timestamp, U256::from(2000));
+        assert_eq!(env.basefee, U256::from(7));
+    }
+
+    #[tokio::test]
+    async fn test_proof_db_records_missing_state() {
+        let execution = get_client();
+        let mut proof_db = ProofDB::new(BlockTag::Latest, Arc::new(execution));
+
+        let address = B160::from_str("0x388C818CA8B9251b393131C08a736A67ccB19297").unwrap();
+
+        // Missing state is served as empty and recorded for the next batch fetch
+        let account = proof_db.basic(address).unwrap().unwrap();
+        let value = proof_db.storage(address, U256::from(1)).unwrap();
+        proof_db.storage(address, U256::from(1)).unwrap();
+
+        assert_eq!(account, AccountInfo::default());
+        assert_eq!(value, U256::ZERO);
+        assert!(proof_db.state.needs_update());
+        assert_eq!(proof_db.state.accesses.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_commit_carries_state_across_calls() {
+        let execution = get_client();
+        let mut proof_db = ProofDB::new(BlockTag::Latest, Arc::new(execution));
+
+        let address = B160::from_str("0x388C818CA8B9251b393131C08a736A67ccB19297").unwrap();
+        let info = AccountInfo::new(U256::from(500), 10, Bytecode::new_raw(Bytes::default()));
+
+        let mut slot = StorageSlot::new(U256::ZERO);
+        slot.present_value = U256::from(7);
+
+        let mut account = Account::from(info.clone());
+        account.storage.insert(U256::from(1), slot);
+        account.mark_touch();
+
+        proof_db
+            .state
+            .commit([(address, account)].into_iter().collect());
+
+        // later calls read the committed state without fetching it
+        assert_eq!(proof_db.basic(address).unwrap().unwrap(), info);
+        assert_eq!(
+            proof_db.storage(address, U256::from(1)).unwrap(),
+            U256::from(7)
+        );
+        assert!(!proof_db.state.needs_update());
+    }
+
+    #[tokio::test]
+    async fn test_commit_self_destruct() {
+        let execution = get_client();
+        let mut proof_db = ProofDB::new(BlockTag::Latest, Arc::new(execution));
+
+        let address = B160::from_str("0x388C818CA8B9251b393131C08a736A67ccB19297").unwrap();
+        let info = AccountInfo::new(U256::from(500), 10, Bytecode::new_raw(Bytes::default()));
+        proof_db.state.basic.insert(address, info.clone());
+        proof_db
+            .state
+            .storage
+            .entry(address)
+            .or_default()
+            .insert(U256::from(1), U256::from(9));
+
+        let mut account = Account::from(info);
+        account.mark_touch();
+        account.mark_selfdestruct();
+
+        proof_db
+            .state
+            .commit([(address, account)].into_iter().collect());
+
+        // every slot of a destroyed account reads as zero, even those never fetched
+        assert_eq!(
+            proof_db.basic(address).unwrap().unwrap(),
+            AccountInfo::default()
+        );
+        assert_eq!(
+            proof_db.storage(address, U256::from(1)).unwrap(),
+            U256::ZERO
+        );
+        assert_eq!(
+            proof_db.storage(address, U256::from(2)).unwrap(),
+            U256::ZERO
+        );
+        assert!(!proof_db.state.needs_update());
+    }
+
+    #[tokio::test]
+    async fn test_access_list_of_every_call() {
+        let (_, block_recv) = channel(256);
+        let (_, finalized_recv) = watch::channel(None);
+        let (_, safe_recv) = watch::channel(None);
+        let state = State::new(block_recv, finalized_recv, safe_recv, 64);
+        state.push_block(common::types::Block::default()).await;
+
+        let execution = ExecutionClient::<MockRpc>::new("testdata/", state).unwrap();
+        let sender = Address::from_low_u64_be(0x1000);
+        let first = Address::from_low_u64_be(0x2000);
+        let second = Address::from_low_u64_be(0x3000);
+
+        // each call reads a slot of a contract the other call never touches
+        let slot = |contract: Address| AccessListItem {
+            address: contract,
+            storage_keys: vec![H256::from_low_u64_be(1)],
+        };
+
+        execution
+            .rpc
+            .set_access_list(first, AccessList(vec![slot(first)]));
+        execution
+            .rpc
+            .set_access_list(second, AccessList(vec![slot(second), slot(second)]));
+
+        let calls = [first, second].map(|to| CallOpts {
+            from: Some(sender),
+            to: Some(to),
+            gas: None,
+            gas_price: None,
+            value: None,
+            data: None,
+        });
+
+        let state = EvmState::new(Arc::new(execution), BlockTag::Latest);
+        let list = state.access_list(&calls).await.unwrap();
+
+        let addresses = list.iter().map(|item| item.address).collect::<Vec<_>>();
+        assert_eq!(addresses, vec![first, sender, second, Address::zero()]);
+        assert_eq!(list[0].storage_keys, vec![H256::from_low_u64_be(1)]);
+        assert_eq!(list[2].storage_keys, vec![H256::from_low_u64_be(1)]);
+    }
 }
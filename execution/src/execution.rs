@@ -1,41 +1,107 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use common::errors::BlockNotFoundError;
-use ethers::abi::AbiEncode;
+use config::PrefetchStrategy;
+use ethers::abi::ethereum_types::BloomInput;
 use ethers::prelude::Address;
-use ethers::types::{Filter, Log, Transaction, TransactionReceipt, H256, U256};
+use ethers::types::transaction::eip2930::{AccessList, AccessListItem};
+use ethers::types::{
+    BlockNumber, Bloom, EIP1186ProofResponse, FeeHistory, Filter, Log, Transaction,
+    TransactionReceipt, ValueOrArray, H256, U256,
+};
 use ethers::utils::keccak256;
-use ethers::utils::rlp::{encode, Encodable, RlpStream};
-use eyre::Result;
+use ethers::utils::rlp::{Encodable, RlpStream};
+use eyre::{Report, Result};
+use tokio::sync::broadcast;
 
-use futures::future::join_all;
 use revm::primitives::KECCAK_EMPTY;
+use tracing::{debug, warn};
 use triehash_ethereum::ordered_trie_root;
 
 use common::types::{Block, BlockTag, Transactions};
-use common::utils::hex_str_to_bytes;
+use common::utils::{decode_transaction, encode_transaction};
 
+use crate::cache::{BlockHashCache, ReceiptCache, StateCache};
+use crate::constants::{
+    BASE_FEE_MAX_CHANGE_DENOMINATOR, BLOCK_HASH_CACHE_MAX_BLOCKS, BLOCK_HASH_CHECKPOINT_INTERVAL,
+    DEFAULT_PRIORITY_FEE, ELASTICITY_MULTIPLIER, FILTER_TIMEOUT_SECS, GAS_PRICE_ORACLE_BLOCKS,
+    GAS_PRICE_ORACLE_IGNORE_PRICE, GAS_PRICE_ORACLE_MAX_PRIORITY_FEE, GAS_PRICE_ORACLE_PERCENTILE,
+    GAS_PRICE_ORACLE_SAMPLES, MAX_BATCH_SIZE, MAX_FEE_HISTORY_BLOCKS, MAX_HISTORICAL_BLOCK_DEPTH,
+    MAX_SUPPORTED_LOGS_NUMBER, STATE_CACHE_MAX_ACCOUNTS, STATE_CACHE_MAX_CODE,
+};
 use crate::errors::ExecutionError;
+use crate::filters::{Filters, InstalledFilter};
 use crate::state::State;
+use crate::types::{CallOpts, FilterChanges};
 
-use super::proof::{encode_account, verify_proof};
+use super::proof;
 use super::rpc::ExecutionRpc;
 use super::types::Account;
 
-// We currently limit the max number of logs to fetch,
-// to avoid blocking the client for too long.
-const MAX_SUPPORTED_LOGS_NUMBER: usize = 5;
-
 #[derive(Clone)]
 pub struct ExecutionClient<R: ExecutionRpc> {
     pub rpc: R,
     state: State,
+    cache: StateCache,
+    receipt_cache: ReceiptCache,
+    block_hashes: BlockHashCache,
+    filters: Filters,
+    prefetch_strategy: PrefetchStrategy,
+    batch_size: usize,
+    max_logs: usize,
+    logs_completeness: bool,
+    block_receipts_supported: Arc<AtomicBool>,
 }
 
 impl<R: ExecutionRpc> ExecutionClient<R> {
     pub fn new(rpc: &str, state: State) -> Result<Self> {
         let rpc: R = ExecutionRpc::new(rpc)?;
-        Ok(ExecutionClient { rpc, state })
+        let cache = StateCache::new(STATE_CACHE_MAX_ACCOUNTS, STATE_CACHE_MAX_CODE);
+        // receipts are only served from the cache for blocks in the block window
+        let receipt_cache = ReceiptCache::new(state.history_length() as usize);
+        let block_hashes = BlockHashCache::new(BLOCK_HASH_CACHE_MAX_BLOCKS);
+        let filters = Filters::new(Duration::from_secs(FILTER_TIMEOUT_SECS));
+        Ok(ExecutionClient {
+            rpc,
+            state,
+            cache,
+            receipt_cache,
+            block_hashes,
+            filters,
+            prefetch_strategy: PrefetchStrategy::default(),
+            batch_size: MAX_BATCH_SIZE,
+            max_logs: MAX_SUPPORTED_LOGS_NUMBER,
+            logs_completeness: false,
+            block_receipts_supported: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    pub fn with_prefetch_strategy(mut self, prefetch_strategy: PrefetchStrategy) -> Self {
+        self.prefetch_strategy = prefetch_strategy;
+        self
+    }
+
+    /// Sets the maximum number of requests sent to the execution rpc in a single batch.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of logs proven for a single `get_logs` or `get_filter_changes`.
+    pub fn with_max_logs(mut self, max_logs: usize) -> Self {
+        self.max_logs = max_logs;
+        self
+    }
+
+    /// When enabled, `get_logs` also proves that no logs were omitted. Every block in the range
+    /// whose verified logs bloom matches the filter has its receipts fetched and proven, and
+    /// the logs are read from those receipts instead of trusting the execution rpc.
+    pub fn with_logs_completeness(mut self, logs_completeness: bool) -> Self {
+        self.logs_completeness = logs_completeness;
+        self
     }
 
     pub async fn check_rpc(&self, chain_id: u64) -> Result<()> {
@@ -52,82 +118,304 @@ impl<R: ExecutionRpc> ExecutionClient<R> {
         slots: Option<&[H256]>,
         tag: BlockTag,
     ) -> Result<Account> {
-        let slots = slots.unwrap_or(&[]);
+        let request = AccessListItem {
+            address: *address,
+            storage_keys: slots.unwrap_or(&[]).to_vec(),
+        };
+
+        let mut accounts = self.get_accounts(&[request], tag).await?;
+        Ok(accounts.remove(0))
+    }
+
+    /// Fetches and verifies several accounts along with the requested storage slots. Proofs
+    /// and code that are not already cached are requested from the execution rpc in batches.
+    pub async fn get_accounts(
+        &self,
+        requests: &[AccessListItem],
+        tag: BlockTag,
+    ) -> Result<Vec<Account>> {
         let block = self
             .state
             .get_block(tag)
             .await
             .ok_or(BlockNotFoundError::new(tag))?;
 
-        let proof = self
-            .rpc
-            .get_proof(address, slots, block.number.as_u64())
-            .await?;
+        self.get_block_accounts(requests, &block).await
+    }
+
+    /// Fetches and verifies several accounts against the state root of a verified block. Unlike
+    /// [`ExecutionClient::get_accounts`], the block does not have to be in the block window, so
+    /// accounts can be proven for as long as the execution rpc serves proofs at that block.
+    pub async fn get_block_accounts(
+        &self,
+        requests: &[AccessListItem],
+        block: &Block,
+    ) -> Result<Vec<Account>> {
+        let block_number = block.number.as_u64();
 
-        let account_path = keccak256(address.as_bytes()).to_vec();
-        let account_encoded = encode_account(&proof);
+        let mut accounts = Vec::with_capacity(requests.len());
+        let mut to_fetch = Vec::new();
+        for (i, request) in requests.iter().enumerate() {
+            let cached = self
+                .cache
+                .get_account(block.state_root, &request.address)
+                .await;
+            let missing_slots = request
+                .storage_keys
+                .iter()
+                .filter(|slot| match &cached {
+                    Some(account) => !account.slots.contains_key(slot),
+                    None => true,
+                })
+                .cloned()
+                .collect::<Vec<_>>();
 
-        let is_valid = verify_proof(
-            &proof.account_proof,
-            block.state_root.as_bytes(),
-            &account_path,
-            &account_encoded,
-        );
+            if cached.is_none() || !missing_slots.is_empty() {
+                let item = AccessListItem {
+                    address: request.address,
+                    storage_keys: missing_slots,
+                };
 
-        if !is_valid {
-            return Err(ExecutionError::InvalidAccountProof(*address).into());
+                to_fetch.push((i, item));
+            }
+
+            accounts.push(cached);
         }
 
-        let mut slot_map = HashMap::new();
+        for chunk in to_fetch.chunks(self.batch_size) {
+            let items = chunk
+                .iter()
+                .map(|(_, item)| item.clone())
+                .collect::<Vec<_>>();
+            let verified = self.get_verified_proofs(&items, block).await?;
 
-        for storage_proof in proof.storage_proof {
-            let key = hex_str_to_bytes(&storage_proof.key.encode_hex())?;
-            let value = encode(&storage_proof.value).to_vec();
+            for ((i, item), (mut account, _)) in chunk.iter().zip(verified) {
+                self.cache_account(block, &item.address, &account).await;
 
-            let key_hash = keccak256(key);
+                if let Some(cached) = accounts[*i].take() {
+                    account.slots.extend(cached.slots);
+                }
 
-            let is_valid = verify_proof(
-                &storage_proof.proof,
-                proof.storage_hash.as_bytes(),
-                &key_hash.to_vec(),
-                &value,
-            );
+                accounts[*i] = Some(account);
+            }
+        }
+
+        let accounts = accounts.into_iter().flatten().collect::<Vec<_>>();
+
+        let mut codes = HashMap::new();
+        let mut missing_code = Vec::new();
+        for (request, account) in requests.iter().zip(&accounts) {
+            let code_hash = account.code_hash;
+            let seen = codes.contains_key(&code_hash)
+                || missing_code.iter().any(|(_, hash)| *hash == code_hash);
+
+            if code_hash == KECCAK_EMPTY.into() || seen {
+                continue;
+            }
+
+            match self.cache.get_code(&code_hash).await {
+                Some(code) => {
+                    codes.insert(code_hash, code);
+                }
+                None => missing_code.push((request.address, code_hash)),
+            }
+        }
+
+        for chunk in missing_code.chunks(self.batch_size) {
+            let addresses = chunk
+                .iter()
+                .map(|(address, _)| *address)
+                .collect::<Vec<_>>();
+            let code_hashes = chunk.iter().map(|(_, hash)| *hash).collect::<Vec<_>>();
+            let fetched = self
+                .get_verified_codes(&addresses, &code_hashes, block_number)
+                .await?;
+
+            for ((_, code_hash), code) in chunk.iter().zip(fetched) {
+                self.cache.insert_code(*code_hash, code.clone()).await;
+                codes.insert(*code_hash, code);
+            }
+        }
+
+        let accounts = requests
+            .iter()
+            .zip(accounts)
+            .map(|(request, mut account)| {
+                account
+                    .slots
+                    .retain(|slot, _| request.storage_keys.contains(slot));
+                account.code = codes.get(&account.code_hash).cloned().unwrap_or_default();
+                account
+            })
+            .collect();
+
+        Ok(accounts)
+    }
+
+    /// Returns the EIP-1186 proof of an account and storage slots at a block, after checking it
+    /// against the verified state root of that block. Accounts and slots that do not exist are
+    /// returned with their exclusion proofs.
+    pub async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        tag: BlockTag,
+    ) -> Result<EIP1186ProofResponse> {
+        let block = self
+            .state
+            .get_block(tag)
+            .await
+            .ok_or(BlockNotFoundError::new(tag))?;
+
+        let item = AccessListItem {
+            address: *address,
+            storage_keys: slots.to_vec(),
+        };
+
+        let (account, mut proof) = self.get_verified_proofs(&[item], &block).await?.remove(0);
+
+        self.cache_account(&block, address, &account).await;
 
-            if !is_valid {
+        proof.storage_hash = account.storage_hash;
+        proof.code_hash = account.code_hash;
+        Ok(proof)
+    }
+
+    /// Fetches and verifies the proofs of `items`, returning each proven account along with
+    /// its proof. Proofs for other accounts or slots than the requested ones fail verification.
+    /// If a proof fails verification, the rpc is told so it can demote the provider that served
+    /// it, and the request is retried once for every other provider backing the rpc.
+    async fn get_verified_proofs(
+        &self,
+        items: &[AccessListItem],
+        block: &Block,
+    ) -> Result<Vec<(Account, EIP1186ProofResponse)>> {
+        let block_number = block.number.as_u64();
+        let mut attempts = self.rpc.provider_count();
+
+        loop {
+            let proofs = self.rpc.get_proofs(items, block_number).await?;
+            if proofs.len() != items.len() {
                 return Err(
-                    ExecutionError::InvalidStorageProof(*address, storage_proof.key).into(),
+                    ExecutionError::IncompleteBatchResponse(items.len(), proofs.len()).into(),
                 );
             }
 
-            slot_map.insert(storage_proof.key, storage_proof.value);
+            let verified = items
+                .iter()
+                .zip(proofs)
+                .map(|(item, proof)| {
+                    verify_account_proof(item, &proof, block)
+                        .map(|account| (account, proof))
+                        .map_err(|err| (item, err))
+                })
+                .collect::<Result<Vec<_>, _>>();
+
+            match verified {
+                Ok(accounts) => return Ok(accounts),
+                Err((item, err)) => {
+                    self.rpc.report_invalid_account(&item.address, block_number);
+
+                    attempts -= 1;
+                    if attempts == 0 {
+                        return Err(err);
+                    }
+
+                    warn!(
+                        target: "helios::execution",
+                        "retrying proofs after invalid response: {}",
+                        err
+                    );
+                }
+            }
         }
+    }
 
-        let code = if proof.code_hash == KECCAK_EMPTY.into() {
-            Vec::new()
-        } else {
-            let code = self.rpc.get_code(address, block.number.as_u64()).await?;
-            let code_hash = keccak256(&code).into();
-
-            if proof.code_hash != code_hash {
-                return Err(ExecutionError::CodeHashMismatch(
-                    *address,
-                    code_hash.to_string(),
-                    proof.code_hash.to_string(),
-                )
-                .into());
+    /// Fetches the code of `addresses` and checks it against the proven code hashes, retrying
+    /// on a mismatch like [`Self::get_verified_proofs`].
+    async fn get_verified_codes(
+        &self,
+        addresses: &[Address],
+        code_hashes: &[H256],
+        block: u64,
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut attempts = self.rpc.provider_count();
+
+        loop {
+            let codes = self.rpc.get_codes(addresses, block).await?;
+            if codes.len() != addresses.len() {
+                return Err(
+                    ExecutionError::IncompleteBatchResponse(addresses.len(), codes.len()).into(),
+                );
+            }
+
+            let mismatch = addresses.iter().zip(code_hashes).zip(&codes).find_map(
+                |((address, code_hash), code)| {
+                    let fetched_hash: H256 = keccak256(code).into();
+                    (*code_hash != fetched_hash).then_some((address, fetched_hash, code_hash))
+                },
+            );
+
+            let (address, fetched_hash, code_hash) = match mismatch {
+                Some(mismatch) => mismatch,
+                None => return Ok(codes),
+            };
+
+            self.rpc.report_invalid_account(address, block);
+
+            let err = ExecutionError::CodeHashMismatch(
+                *address,
+                fetched_hash.to_string(),
+                code_hash.to_string(),
+            );
+
+            attempts -= 1;
+            if attempts == 0 {
+                return Err(err.into());
             }
 
-            code
+            warn!(
+                target: "helios::execution",
+                "retrying code after invalid response: {}",
+                err
+            );
+        }
+    }
+
+    async fn cache_account(&self, block: &Block, address: &Address, account: &Account) {
+        // prune roots of blocks that have left the state window before adding a new one
+        if !self.cache.contains_root(block.state_root).await {
+            if let Some(oldest) = self.state.oldest_block_number().await {
+                let finalized = self.state.get_state_root(BlockTag::Finalized).await;
+                self.cache.prune(oldest, finalized).await;
+            }
+        }
+
+        self.cache
+            .insert_account(block.number.as_u64(), block.state_root, address, account)
+            .await;
+    }
+
+    /// Returns the access list to prefetch for a call, or `None` if the prefetch strategy
+    /// discovers state during execution instead.
+    pub async fn create_access_list(
+        &self,
+        opts: &CallOpts,
+        block: BlockTag,
+    ) -> Result<Option<AccessList>> {
+        if self.prefetch_strategy == PrefetchStrategy::Discover {
+            return Ok(None);
+        }
+
+        // pin the access list to the block helios resolved, so tags such as `safe` refer to
+        // the same block on both sides
+        let block = match self.state.get_block(block).await {
+            Some(block) => BlockTag::Number(block.number.as_u64()),
+            None => block,
         };
 
-        Ok(Account {
-            balance: proof.balance,
-            nonce: proof.nonce.as_u64(),
-            code,
-            code_hash: proof.code_hash,
-            storage_hash: proof.storage_hash,
-            slots: slot_map,
-        })
+        let list = self.rpc.create_access_list(opts, block).await?;
+        Ok(Some(list))
     }
 
     pub async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
@@ -174,13 +462,27 @@ impl<R: ExecutionRpc> ExecutionClient<R> {
         &self,
         tx_hash: &H256,
     ) -> Result<Option<TransactionReceipt>> {
+        // transactions in the block window are located locally, so their receipts can be
+        // served from the receipt cache without asking the execution rpc
+        if let Some(block) = self.state.get_transaction_block(*tx_hash).await {
+            let receipts = self.get_verified_block_receipts(&block).await?;
+            let receipt = receipts
+                .into_iter()
+                .find(|receipt| receipt.transaction_hash == *tx_hash);
+
+            return Ok(receipt);
+        }
+
         let receipt = self.rpc.get_transaction_receipt(tx_hash).await?;
         if receipt.is_none() {
             return Ok(None);
         }
 
         let receipt = receipt.unwrap();
-        let block_number = receipt.block_number.unwrap().as_u64();
+        let block_number = match receipt.block_number {
+            Some(block_number) => block_number.as_u64(),
+            None => return Ok(None),
+        };
 
         let block = self.state.get_block(BlockTag::Number(block_number)).await;
         let block = if let Some(block) = block {
@@ -189,29 +491,374 @@ impl<R: ExecutionRpc> ExecutionClient<R> {
             return Ok(None);
         };
 
+        let receipts = self.get_verified_block_receipts(&block).await?;
+        if !receipts.contains(&receipt) {
+            self.rpc.report_invalid_receipt(tx_hash);
+            return Err(ExecutionError::ReceiptRootMismatch(tx_hash.to_string()).into());
+        }
+
+        Ok(Some(receipt))
+    }
+
+    /// Returns the verified receipts of every transaction in a block, or `None` if the block
+    /// is not in the block window.
+    pub async fn get_block_receipts(
+        &self,
+        tag: BlockTag,
+    ) -> Result<Option<Vec<TransactionReceipt>>> {
+        let block = match self.state.get_block(tag).await {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        Ok(Some(self.get_verified_block_receipts(&block).await?))
+    }
+
+    /// Returns the receipts of every transaction in `block`, verified against its receipts
+    /// root. Verified receipts are cached, so each block is only fetched and proven once.
+    async fn get_verified_block_receipts(&self, block: &Block) -> Result<Vec<TransactionReceipt>> {
+        if let Some(receipts) = self.receipt_cache.get(&block.hash).await {
+            return Ok(receipts);
+        }
+
         let tx_hashes = block.transactions.hashes();
+        let fetched = self.fetch_block_receipts(block, &tx_hashes).await?;
 
-        let receipts_fut = tx_hashes.iter().map(|hash| async move {
-            let receipt = self.rpc.get_transaction_receipt(hash).await;
-            receipt?.ok_or(eyre::eyre!("not reachable"))
-        });
+        let receipts = verify_block_receipts(block, &tx_hashes, fetched).map_err(|err| {
+            self.rpc.report_invalid_receipts(block.number.as_u64());
+            err
+        })?;
 
-        let receipts = join_all(receipts_fut).await;
-        let receipts = receipts.into_iter().collect::<Result<Vec<_>>>()?;
-        let receipts_encoded: Vec<Vec<u8>> = receipts.iter().map(encode_receipt).collect();
+        self.receipt_cache
+            .insert(block.hash, receipts.clone())
+            .await;
 
-        let expected_receipt_root = ordered_trie_root(receipts_encoded);
-        let expected_receipt_root = H256::from_slice(&expected_receipt_root.to_fixed_bytes());
+        Ok(receipts)
+    }
 
-        if expected_receipt_root != block.receipts_root || !receipts.contains(&receipt) {
-            return Err(ExecutionError::ReceiptRootMismatch(tx_hash.to_string()).into());
+    /// Fetches the receipts of `block` with a single `eth_getBlockReceipts` request, falling
+    /// back to batches of `eth_getTransactionReceipt` if the execution rpc does not support it.
+    async fn fetch_block_receipts(
+        &self,
+        block: &Block,
+        tx_hashes: &[H256],
+    ) -> Result<Vec<Option<TransactionReceipt>>> {
+        if tx_hashes.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(Some(receipt))
+        if self.block_receipts_supported.load(Ordering::Relaxed) {
+            match self.rpc.get_block_receipts(block.number.as_u64()).await {
+                Ok(receipts) => return Ok(receipts.into_iter().map(Some).collect()),
+                Err(err) => {
+                    debug!(
+                        target: "helios::execution",
+                        "eth_getBlockReceipts failed, fetching receipts individually: {}",
+                        err
+                    );
+
+                    // other errors may be transient, so the method is tried again next time
+                    if is_method_not_found(&err) {
+                        self.block_receipts_supported
+                            .store(false, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+
+        let mut receipts = Vec::with_capacity(tx_hashes.len());
+        for chunk in tx_hashes.chunks(self.batch_size) {
+            let fetched = self.rpc.get_transaction_receipts(chunk).await?;
+            if fetched.len() != chunk.len() {
+                return Err(
+                    ExecutionError::IncompleteBatchResponse(chunk.len(), fetched.len()).into(),
+                );
+            }
+
+            receipts.extend(fetched);
+        }
+
+        Ok(receipts)
+    }
+
+    /// Returns the fee history of up to `block_count` blocks ending at `last_block`, truncated
+    /// to the block window. Base fees and gas used ratios are read from verified blocks, and
+    /// rewards are the effective tips at each percentile of gas used, computed from verified
+    /// transactions weighted by the gas used in their verified receipts.
+    pub async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: BlockTag,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        let valid_percentiles = reward_percentiles
+            .iter()
+            .all(|percentile| (0.0..=100.0).contains(percentile))
+            && reward_percentiles.windows(2).all(|pair| pair[0] <= pair[1]);
+
+        if !valid_percentiles {
+            return Err(
+                ExecutionError::InvalidRewardPercentiles(reward_percentiles.to_vec()).into(),
+            );
+        }
+
+        let last_block = self
+            .state
+            .get_block(last_block)
+            .await
+            .ok_or(BlockNotFoundError::new(last_block))?;
+
+        let last_number = last_block.number.as_u64();
+        let oldest_available = self
+            .state
+            .oldest_block_number()
+            .await
+            .unwrap_or(last_number);
+
+        let block_count = block_count.min(MAX_FEE_HISTORY_BLOCKS);
+        if block_count == 0 {
+            return Ok(FeeHistory {
+                base_fee_per_gas: Vec::new(),
+                gas_used_ratio: Vec::new(),
+                oldest_block: U256::zero(),
+                reward: Vec::new(),
+            });
+        }
+
+        let oldest_number = (last_number + 1)
+            .saturating_sub(block_count)
+            .max(oldest_available);
+
+        let mut base_fee_per_gas = Vec::new();
+        let mut gas_used_ratio = Vec::new();
+        let mut reward = Vec::new();
+
+        for number in oldest_number..=last_number {
+            let block = if number == last_number {
+                last_block.clone()
+            } else {
+                self.state
+                    .get_block(BlockTag::Number(number))
+                    .await
+                    .ok_or(ExecutionError::BlockNotFoundError(number))?
+            };
+
+            base_fee_per_gas.push(block.base_fee_per_gas);
+            gas_used_ratio.push(gas_used_ratio_of(&block));
+
+            if !reward_percentiles.is_empty() {
+                let receipts = self.get_verified_block_receipts(&block).await?;
+                reward.push(block_rewards(&block, &receipts, reward_percentiles));
+            }
+        }
+
+        base_fee_per_gas.push(next_base_fee(&last_block));
+
+        Ok(FeeHistory {
+            base_fee_per_gas,
+            gas_used_ratio,
+            oldest_block: oldest_number.into(),
+            reward,
+        })
+    }
+
+    /// Suggests a priority fee from the tips paid in recent verified blocks, following geth's
+    /// gas price oracle: the lowest tips of each block are sampled, ignoring transactions sent
+    /// by the fee recipient, and a percentile of the samples is returned.
+    pub async fn get_priority_fee(&self) -> Result<U256> {
+        let latest = self
+            .state
+            .latest_block_number()
+            .await
+            .ok_or(BlockNotFoundError::new(BlockTag::Latest))?;
+
+        let oldest = self.state.oldest_block_number().await.unwrap_or(latest);
+        let first = (latest + 1)
+            .saturating_sub(GAS_PRICE_ORACLE_BLOCKS)
+            .max(oldest);
+
+        let mut tips = Vec::new();
+        for number in first..=latest {
+            if let Some(block) = self.state.get_block(BlockTag::Number(number)).await {
+                tips.extend(lowest_tips(&block));
+            }
+        }
+
+        if tips.is_empty() {
+            return Ok(DEFAULT_PRIORITY_FEE.into());
+        }
+
+        tips.sort();
+        let tip = tips[(tips.len() - 1) * GAS_PRICE_ORACLE_PERCENTILE / 100];
+
+        Ok(tip.min(U256::from(GAS_PRICE_ORACLE_MAX_PRIORITY_FEE)))
+    }
+
+    /// Suggests a legacy gas price, the base fee of the latest block plus the suggested
+    /// priority fee.
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        let base_fee = self
+            .state
+            .get_base_fee(BlockTag::Latest)
+            .await
+            .ok_or(BlockNotFoundError::new(BlockTag::Latest))?;
+
+        Ok(base_fee + self.get_priority_fee().await?)
+    }
+
+    /// Returns a verified transaction. Transactions in the block window are served locally.
+    /// Older transactions are located through the execution rpc, and their inclusion is
+    /// proven by rebuilding the transactions trie of a block verified against the finalized
+    /// block.
+    pub async fn get_transaction(&self, hash: H256) -> Result<Option<Transaction>> {
+        if let Some(tx) = self.state.get_transaction(hash).await {
+            return Ok(Some(tx));
+        }
+
+        let tx = match self.rpc.get_transaction(&hash).await? {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+
+        // pending transactions cannot be proven
+        let block_number = match tx.block_number {
+            Some(block_number) => block_number.as_u64(),
+            None => return Ok(None),
+        };
+
+        // the block window would already include the transaction if it was in one of its
+        // blocks, and later blocks have not been verified yet
+        let oldest_block = self.state.oldest_block_number().await.unwrap_or(0);
+        if block_number >= oldest_block {
+            return Ok(None);
+        }
+
+        let block = self.get_historical_block(block_number).await?;
+        let tx = match block.transactions {
+            Transactions::Full(txs) => txs.into_iter().find(|tx| tx.hash == hash),
+            Transactions::Hashes(_) => unreachable!(),
+        };
+
+        tx.map(Some)
+            .ok_or(ExecutionError::MissingTransaction(hash.to_string()).into())
+    }
+
+    /// Returns a block older than the block window with its full transactions. The header is
+    /// checked against a hash proven by `get_historical_block_hash`, and the transactions
+    /// are checked against the transactions root of the header. Transaction senders are
+    /// recovered locally rather than trusted from the execution rpc.
+    async fn get_historical_block(&self, number: u64) -> Result<Block> {
+        let hash = self.get_historical_block_hash(number).await?;
+        let mut block = self
+            .rpc
+            .get_block(number, true)
+            .await?
+            .ok_or(ExecutionError::BlockNotFoundError(number))?;
+
+        if block.number.as_u64() != number || block.header_hash().ok() != Some(hash) {
+            return Err(ExecutionError::InvalidBlockHeader(number).into());
+        }
+
+        let txs_encoded = match &block.transactions {
+            Transactions::Full(txs) => txs.iter().map(encode_transaction).collect::<Vec<_>>(),
+            Transactions::Hashes(_) => unreachable!(),
+        };
+
+        let transactions_root = ordered_trie_root(txs_encoded.iter());
+        let transactions_root = H256::from_slice(&transactions_root.to_fixed_bytes());
+        if transactions_root != block.transactions_root {
+            return Err(ExecutionError::BlockTransactionsRootMismatch(number).into());
+        }
+
+        let txs = txs_encoded
+            .iter()
+            .enumerate()
+            .map(|(index, raw)| {
+                let mut tx = decode_transaction(raw)?;
+                tx.block_hash = Some(hash);
+                tx.block_number = Some(number.into());
+                tx.transaction_index = Some(index.into());
+                Ok(tx)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        block.hash = hash;
+        block.transactions = Transactions::Full(txs);
+        Ok(block)
     }
 
-    pub async fn get_transaction(&self, hash: H256) -> Option<Transaction> {
-        self.state.get_transaction(hash).await
+    /// Proves the hash of a block older than the block window by walking parent hashes back
+    /// from the closest proven block above it, or from the finalized block. Each header on the
+    /// way is fetched in batches and checked against the hash its child commits to. Finalized
+    /// hashes, checkpoints every `BLOCK_HASH_CHECKPOINT_INTERVAL` blocks and the requested
+    /// hash are cached, so later walks can start from the closest of them.
+    pub(crate) async fn get_historical_block_hash(&self, number: u64) -> Result<H256> {
+        let finalized = self
+            .state
+            .get_block(BlockTag::Finalized)
+            .await
+            .ok_or(BlockNotFoundError::new(BlockTag::Finalized))?;
+
+        let finalized_number = finalized.number.as_u64();
+        if number >= finalized_number {
+            return Err(ExecutionError::BlockNotFoundError(number).into());
+        }
+
+        if finalized_number - number > MAX_HISTORICAL_BLOCK_DEPTH {
+            return Err(
+                ExecutionError::HistoricalBlockTooOld(number, MAX_HISTORICAL_BLOCK_DEPTH).into(),
+            );
+        }
+
+        self.block_hashes
+            .remove_below(finalized_number.saturating_sub(MAX_HISTORICAL_BLOCK_DEPTH))
+            .await;
+
+        if let Some(hash) = self.block_hashes.get(number).await {
+            return Ok(hash);
+        }
+
+        let (mut current, mut hash) = match self
+            .block_hashes
+            .lowest_above(number, finalized_number)
+            .await
+        {
+            Some(proven) => proven,
+            None => (finalized_number - 1, finalized.parent_hash),
+        };
+
+        self.block_hashes
+            .insert(finalized_number, finalized.hash)
+            .await;
+
+        while current > number {
+            let lowest = (current + 1)
+                .saturating_sub(self.batch_size as u64)
+                .max(number + 1);
+
+            let numbers = (lowest..=current).rev().collect::<Vec<_>>();
+            let headers = self.rpc.get_blocks(&numbers).await?;
+            if headers.len() != numbers.len() {
+                return Err(ExecutionError::BlockNotFoundError(lowest).into());
+            }
+
+            for (header_number, header) in numbers.into_iter().zip(headers) {
+                let header = header.ok_or(ExecutionError::BlockNotFoundError(header_number))?;
+                if header.number.as_u64() != header_number
+                    || header.header_hash().ok() != Some(hash)
+                {
+                    return Err(ExecutionError::InvalidBlockHeader(header_number).into());
+                }
+
+                current = header_number - 1;
+                hash = header.parent_hash;
+                if current % BLOCK_HASH_CHECKPOINT_INTERVAL == 0 || current == number {
+                    self.block_hashes.insert(current, hash).await;
+                }
+            }
+        }
+
+        Ok(hash)
     }
 
     pub async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
@@ -231,105 +878,591 @@ impl<R: ExecutionRpc> ExecutionClient<R> {
         };
 
         let logs = self.rpc.get_logs(&filter).await?;
-        if logs.len() > MAX_SUPPORTED_LOGS_NUMBER {
-            return Err(
-                ExecutionError::TooManyLogsToProve(logs.len(), MAX_SUPPORTED_LOGS_NUMBER).into(),
-            );
+        if logs.len() > self.max_logs {
+            return Err(ExecutionError::TooManyLogsToProve(logs.len(), self.max_logs).into());
+        }
+
+        if self.logs_completeness {
+            return self.get_complete_logs(&filter, &logs).await;
         }
 
         self.verify_logs(&logs).await?;
         Ok(logs)
     }
 
-    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<Vec<Log>> {
-        let logs = self.rpc.get_filter_changes(filter_id).await?;
-        if logs.len() > MAX_SUPPORTED_LOGS_NUMBER {
-            return Err(
-                ExecutionError::TooManyLogsToProve(logs.len(), MAX_SUPPORTED_LOGS_NUMBER).into(),
-            );
+    /// Reads every log matching `filter` from the verified receipts of the blocks whose logs
+    /// bloom matches it. The logs returned by the execution rpc are only used to detect
+    /// omissions.
+    async fn get_complete_logs(&self, filter: &Filter, returned: &[Log]) -> Result<Vec<Log>> {
+        let mut logs = Vec::new();
+        for block in self.get_filter_blocks(filter).await? {
+            let returned = returned
+                .iter()
+                .filter(|log| log.block_number == Some(block.number))
+                .count();
+
+            if !block_bloom_matches_filter(&block, filter) {
+                if returned > 0 {
+                    warn!(
+                        target: "helios::execution",
+                        "execution rpc returned logs for block {} which cannot contain them",
+                        block.number
+                    );
+
+                    self.rpc.report_invalid_logs(block.number.as_u64());
+                }
+
+                continue;
+            }
+
+            let block_logs = self.get_block_logs(&block, filter).await?;
+            if block_logs.len() > returned {
+                warn!(
+                    target: "helios::execution",
+                    "execution rpc omitted {} logs in block {}",
+                    block_logs.len() - returned,
+                    block.number
+                );
+
+                self.rpc.report_invalid_logs(block.number.as_u64());
+            }
+
+            logs.extend(block_logs);
+            if logs.len() > self.max_logs {
+                return Err(ExecutionError::TooManyLogsToProve(logs.len(), self.max_logs).into());
+            }
         }
-        self.verify_logs(&logs).await?;
+
         Ok(logs)
     }
 
-    pub async fn uninstall_filter(&self, filter_id: &U256) -> Result<bool> {
-        self.rpc.uninstall_filter(filter_id).await
+    /// Returns the logs of `block` matching `filter`, read from its verified receipts. The
+    /// receipts are only fetched if the logs bloom of the block matches the filter.
+    pub async fn get_block_logs(&self, block: &Block, filter: &Filter) -> Result<Vec<Log>> {
+        if !block_bloom_matches_filter(block, filter) {
+            return Ok(Vec::new());
+        }
+
+        let receipts = self.get_verified_block_receipts(block).await?;
+        let logs = receipts
+            .iter()
+            .enumerate()
+            .flat_map(|(tx_index, receipt)| {
+                receipt.logs.iter().map(move |log| (tx_index, receipt, log))
+            })
+            .enumerate()
+            .filter(|(_, (_, _, log))| log_matches_filter(log, filter))
+            .map(|(log_index, (tx_index, receipt, log))| Log {
+                block_hash: Some(block.hash),
+                block_number: Some(block.number),
+                transaction_hash: Some(receipt.transaction_hash),
+                transaction_index: Some(tx_index.into()),
+                log_index: Some(log_index.into()),
+                removed: Some(false),
+                ..log.clone()
+            })
+            .collect();
+
+        Ok(logs)
     }
 
-    pub async fn get_new_filter(&self, filter: &Filter) -> Result<U256> {
-        let filter = filter.clone();
+    /// Returns a receiver of every new verified optimistic block.
+    pub fn subscribe_blocks(&self) -> broadcast::Receiver<Block> {
+        self.state.subscribe_blocks()
+    }
 
-        // avoid submitting a filter for logs for a block helios hasn't seen yet
-        let filter = if filter.get_to_block().is_none() && filter.get_block_hash().is_none() {
-            let block = self.state.latest_block_number().await.unwrap();
-            let filter = filter.to_block(block);
-            if filter.get_from_block().is_none() {
-                filter.from_block(block)
-            } else {
-                filter
+    /// Returns a receiver of every new verified finalized block.
+    pub fn subscribe_finalized_blocks(&self) -> broadcast::Receiver<Block> {
+        self.state.subscribe_finalized_blocks()
+    }
+
+    /// Returns every block covered by `filter`. Fails if part of the range is outside of the
+    /// block window, since logs there cannot be proven complete.
+    async fn get_filter_blocks(&self, filter: &Filter) -> Result<Vec<Block>> {
+        if let Some(hash) = filter.get_block_hash() {
+            let block = self
+                .state
+                .get_block_by_hash(hash)
+                .await
+                .ok_or(eyre::eyre!("block not found"))?;
+
+            return Ok(vec![block]);
+        }
+
+        let from = self
+            .resolve_block_number(filter.block_option.get_from_block())
+            .await?;
+        let to = self
+            .resolve_block_number(filter.block_option.get_to_block())
+            .await?;
+
+        let mut blocks = Vec::new();
+        for number in from..=to {
+            let block = self.state.get_block(BlockTag::Number(number)).await;
+            match block {
+                Some(block) => blocks.push(block),
+                None => {
+                    let oldest = self.state.oldest_block_number().await.unwrap_or_default();
+                    return Err(ExecutionError::InvalidBlockRange(number, oldest).into());
+                }
             }
-        } else {
-            filter
+        }
+
+        Ok(blocks)
+    }
+
+    async fn resolve_block_number(&self, block: Option<&BlockNumber>) -> Result<u64> {
+        let tag = match block {
+            Some(BlockNumber::Number(number)) => return Ok(number.as_u64()),
+            Some(BlockNumber::Earliest) => return Ok(0),
+            Some(BlockNumber::Finalized) => BlockTag::Finalized,
+            Some(BlockNumber::Safe) => BlockTag::Safe,
+            _ => BlockTag::Latest,
         };
-        self.rpc.get_new_filter(&filter).await
+
+        let block = self
+            .state
+            .get_block(tag)
+            .await
+            .ok_or(BlockNotFoundError::new(tag))?;
+
+        Ok(block.number.as_u64())
+    }
+
+    pub async fn get_filter_changes(&self, filter_id: &U256) -> Result<FilterChanges> {
+        let latest = self.state.latest_block_number().await.unwrap_or_default();
+        let (filter, blocks) = self
+            .filters
+            .poll(filter_id, latest)
+            .await
+            .ok_or(ExecutionError::FilterNotFound(*filter_id))?;
+
+        // blocks that have already left the block window can no longer be reported
+        let from_block = match self.state.oldest_block_number().await {
+            Some(oldest) => (*blocks.start()).max(oldest),
+            None => *blocks.start(),
+        };
+
+        // the blocks are only marked as reported once their changes are known, so a failed
+        // poll can be retried without losing them
+        let changes = self.filter_changes(filter, from_block, latest).await?;
+        self.filters.advance(filter_id, latest).await;
+
+        Ok(changes)
+    }
+
+    async fn filter_changes(
+        &self,
+        filter: InstalledFilter,
+        from_block: u64,
+        latest: u64,
+    ) -> Result<FilterChanges> {
+        match filter {
+            InstalledFilter::Blocks => {
+                let mut hashes = Vec::new();
+                for number in from_block..=latest {
+                    if let Some(block) = self.state.get_block(BlockTag::Number(number)).await {
+                        hashes.push(block.hash);
+                    }
+                }
+
+                Ok(FilterChanges::Hashes(hashes))
+            }
+            // helios does not see the mempool, so it has no pending transactions to report
+            InstalledFilter::PendingTransactions => Ok(FilterChanges::Hashes(Vec::new())),
+            InstalledFilter::Logs(filter) => {
+                // a filter pinned to a block hash reports the logs of that block once, when the
+                // block is received
+                if let Some(hash) = filter.get_block_hash() {
+                    let block = self.state.get_block_by_hash(hash).await;
+                    let received = block.map_or(false, |block| {
+                        (from_block..=latest).contains(&block.number.as_u64())
+                    });
+
+                    if !received {
+                        return Ok(FilterChanges::Logs(Vec::new()));
+                    }
+
+                    return Ok(FilterChanges::Logs(self.get_logs(&filter).await?));
+                }
+
+                let from_block = match filter.get_from_block() {
+                    Some(number) => from_block.max(number.as_u64()),
+                    None => from_block,
+                };
+
+                let to_block = match filter.get_to_block() {
+                    Some(number) => latest.min(number.as_u64()),
+                    None => latest,
+                };
+
+                if from_block > to_block {
+                    return Ok(FilterChanges::Logs(Vec::new()));
+                }
+
+                let filter = filter.from_block(from_block).to_block(to_block);
+                Ok(FilterChanges::Logs(self.get_logs(&filter).await?))
+            }
+        }
+    }
+
+    pub async fn uninstall_filter(&self, filter_id: &U256) -> Result<bool> {
+        Ok(self.filters.uninstall(filter_id).await)
+    }
+
+    /// Installs a log filter. Like geth, only logs from blocks received after the filter is
+    /// installed are reported by `get_filter_changes`.
+    pub async fn get_new_filter(&self, filter: &Filter) -> Result<U256> {
+        let latest = self.state.latest_block_number().await.unwrap_or_default();
+        let filter = InstalledFilter::Logs(filter.clone());
+        Ok(self.filters.install(filter, latest).await)
     }
 
     pub async fn get_new_block_filter(&self) -> Result<U256> {
-        self.rpc.get_new_block_filter().await
+        let latest = self.state.latest_block_number().await.unwrap_or_default();
+        Ok(self.filters.install(InstalledFilter::Blocks, latest).await)
     }
 
     pub async fn get_new_pending_transaction_filter(&self) -> Result<U256> {
-        self.rpc.get_new_pending_transaction_filter().await
+        let latest = self.state.latest_block_number().await.unwrap_or_default();
+        let filter = InstalledFilter::PendingTransactions;
+        Ok(self.filters.install(filter, latest).await)
     }
 
     async fn verify_logs(&self, logs: &[Log]) -> Result<()> {
-        for (_pos, log) in logs.iter().enumerate() {
-            // For every log
-            // Get the hash of the tx that generated it
-            let tx_hash = log
-                .transaction_hash
-                .ok_or(eyre::eyre!("tx hash not found in log"))?;
-            // Get its proven receipt
-            let receipt = self
-                .get_transaction_receipt(&tx_hash)
-                .await?
-                .ok_or(ExecutionError::NoReceiptForTransaction(tx_hash.to_string()))?;
-
-            // Check if the receipt contains the desired log
-            // Encoding logs for comparison
-            let receipt_logs_encoded = receipt
-                .logs
+        // group logs by block so each block's receipts are fetched and proven only once
+        let mut blocks: BTreeMap<u64, Vec<&Log>> = BTreeMap::new();
+        for log in logs {
+            let block_number = log
+                .block_number
+                .ok_or(eyre::eyre!("block number not found in log"))?;
+
+            blocks.entry(block_number.as_u64()).or_default().push(log);
+        }
+
+        for (block_number, logs) in blocks {
+            let tag = BlockTag::Number(block_number);
+            let block = self
+                .state
+                .get_block(tag)
+                .await
+                .ok_or(BlockNotFoundError::new(tag))?;
+
+            let receipts = self.get_verified_block_receipts(&block).await?;
+            let receipts = receipts
                 .iter()
-                .map(|log| log.rlp_bytes())
-                .collect::<Vec<_>>();
+                .map(|receipt| (receipt.transaction_hash, receipt))
+                .collect::<HashMap<_, _>>();
 
-            let log_encoded = log.rlp_bytes();
+            for log in logs {
+                let tx_hash = log
+                    .transaction_hash
+                    .ok_or(eyre::eyre!("tx hash not found in log"))?;
 
-            if !receipt_logs_encoded.contains(&log_encoded) {
-                return Err(ExecutionError::MissingLog(
-                    tx_hash.to_string(),
-                    log.log_index.unwrap(),
-                )
-                .into());
+                let receipt = match receipts.get(&tx_hash) {
+                    Some(receipt) => receipt,
+                    None => {
+                        self.rpc.report_invalid_logs(block_number);
+                        let err = ExecutionError::NoReceiptForTransaction(tx_hash.to_string());
+                        return Err(err.into());
+                    }
+                };
+
+                // Check if the receipt contains the desired log
+                // Encoding logs for comparison
+                let log_encoded = log.rlp_bytes();
+                let in_block = log.block_hash.map_or(true, |hash| hash == block.hash);
+                let in_receipt = receipt
+                    .logs
+                    .iter()
+                    .any(|receipt_log| receipt_log.rlp_bytes() == log_encoded);
+
+                if !in_block || !in_receipt {
+                    self.rpc.report_invalid_logs(block_number);
+                    return Err(ExecutionError::MissingLog(
+                        tx_hash.to_string(),
+                        log.log_index.unwrap_or_default(),
+                    )
+                    .into());
+                }
             }
         }
+
         Ok(())
     }
 }
 
-fn encode_receipt(receipt: &TransactionReceipt) -> Vec<u8> {
+/// Returns whether `err` is the execution rpc rejecting a json-rpc method it does not support.
+fn is_method_not_found(err: &Report) -> bool {
+    let message = format!("{:#}", err).to_lowercase();
+
+    [
+        "-32601",
+        "method not found",
+        "does not exist",
+        "not supported",
+        "unsupported",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Checks that `fetched` holds a receipt for each of `tx_hashes` in order, and that together
+/// they match the receipts root of `block`.
+fn verify_block_receipts(
+    block: &Block,
+    tx_hashes: &[H256],
+    fetched: Vec<Option<TransactionReceipt>>,
+) -> Result<Vec<TransactionReceipt>> {
+    let mut receipts = Vec::with_capacity(tx_hashes.len());
+    let mut fetched = fetched.into_iter();
+    for tx_hash in tx_hashes {
+        // the receipts root only commits to the order of receipts, so the hash each
+        // receipt claims must match the transaction at its position
+        let receipt = fetched
+            .next()
+            .flatten()
+            .filter(|receipt| receipt.transaction_hash == *tx_hash)
+            .ok_or(ExecutionError::NoReceiptForTransaction(tx_hash.to_string()))?;
+
+        receipts.push(receipt);
+    }
+
+    if fetched.next().is_some() {
+        return Err(ExecutionError::BlockReceiptsRootMismatch(block.number.as_u64()).into());
+    }
+
+    let receipts_encoded = receipts
+        .iter()
+        .map(encode_receipt)
+        .collect::<Result<Vec<_>>>()?;
+
+    let expected_receipt_root = ordered_trie_root(receipts_encoded);
+    let expected_receipt_root = H256::from_slice(&expected_receipt_root.to_fixed_bytes());
+
+    if expected_receipt_root != block.receipts_root {
+        return Err(ExecutionError::BlockReceiptsRootMismatch(block.number.as_u64()).into());
+    }
+
+    Ok(receipts)
+}
+
+/// Blocks without a logs bloom are assumed to match.
+fn block_bloom_matches_filter(block: &Block, filter: &Filter) -> bool {
+    block.logs_bloom.len() != 256
+        || bloom_matches_filter(&Bloom::from_slice(&block.logs_bloom), filter)
+}
+
+fn bloom_matches_filter(bloom: &Bloom, filter: &Filter) -> bool {
+    let contains = |input: &[u8]| bloom.contains_input(BloomInput::Raw(input));
+
+    let address_matches = match &filter.address {
+        Some(ValueOrArray::Value(address)) => contains(address.as_bytes()),
+        Some(ValueOrArray::Array(addresses)) if !addresses.is_empty() => {
+            addresses.iter().any(|address| contains(address.as_bytes()))
+        }
+        _ => true,
+    };
+
+    address_matches
+        && filter.topics.iter().all(|topic| match topic {
+            Some(ValueOrArray::Value(Some(topic))) => contains(topic.as_bytes()),
+            Some(ValueOrArray::Array(topics)) if !topics.is_empty() => topics
+                .iter()
+                .any(|topic| topic.map_or(true, |topic| contains(topic.as_bytes()))),
+            _ => true,
+        })
+}
+
+fn log_matches_filter(log: &Log, filter: &Filter) -> bool {
+    let address_matches = match &filter.address {
+        Some(ValueOrArray::Value(address)) => log.address == *address,
+        Some(ValueOrArray::Array(addresses)) if !addresses.is_empty() => {
+            addresses.contains(&log.address)
+        }
+        _ => true,
+    };
+
+    address_matches
+        && filter
+            .topics
+            .iter()
+            .enumerate()
+            .all(|(i, topic)| match topic {
+                Some(ValueOrArray::Value(Some(topic))) => log.topics.get(i) == Some(topic),
+                Some(ValueOrArray::Array(topics)) if !topics.is_empty() => {
+                    topics.iter().any(|topic| match topic {
+                        Some(topic) => log.topics.get(i) == Some(topic),
+                        None => true,
+                    })
+                }
+                _ => true,
+            })
+}
+
+fn gas_used_ratio_of(block: &Block) -> f64 {
+    if block.gas_limit.is_zero() {
+        return 0.0;
+    }
+
+    block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64
+}
+
+/// Computes the base fee of the block following `block` as specified by EIP-1559.
+pub(crate) fn next_base_fee(block: &Block) -> U256 {
+    let base_fee = block.base_fee_per_gas;
+    let gas_used = U256::from(block.gas_used.as_u64());
+    let gas_target = U256::from(block.gas_limit.as_u64() / ELASTICITY_MULTIPLIER);
+
+    if gas_target.is_zero() || gas_used == gas_target {
+        return base_fee;
+    }
+
+    let denominator = gas_target * BASE_FEE_MAX_CHANGE_DENOMINATOR;
+    if gas_used > gas_target {
+        let delta = base_fee * (gas_used - gas_target) / denominator;
+        base_fee + delta.max(U256::one())
+    } else {
+        let delta = base_fee * (gas_target - gas_used) / denominator;
+        base_fee.saturating_sub(delta)
+    }
+}
+
+/// Returns the tip per gas a transaction pays to the fee recipient of a block with `base_fee`.
+fn effective_tip(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => {
+            max_priority_fee.min(max_fee.saturating_sub(base_fee))
+        }
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+/// Returns the effective tip at each percentile of the gas used in a block, as reported by
+/// `eth_feeHistory`. Empty blocks report zero tips.
+fn block_rewards(block: &Block, receipts: &[TransactionReceipt], percentiles: &[f64]) -> Vec<U256> {
+    let txs = match &block.transactions {
+        Transactions::Full(txs) => txs,
+        Transactions::Hashes(_) => unreachable!(),
+    };
+
+    if txs.is_empty() {
+        return vec![U256::zero(); percentiles.len()];
+    }
+
+    let mut tips = txs
+        .iter()
+        .zip(receipts)
+        .map(|(tx, receipt)| {
+            let gas_used = receipt.gas_used.unwrap_or_default().as_u64();
+            (effective_tip(tx, block.base_fee_per_gas), gas_used)
+        })
+        .collect::<Vec<_>>();
+
+    tips.sort_by_key(|(tip, _)| *tip);
+
+    let mut index = 0;
+    let mut sum_gas_used = tips[0].1;
+
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (block.gas_used.as_u64() as f64 * percentile / 100.0) as u64;
+            while sum_gas_used < threshold && index < tips.len() - 1 {
+                index += 1;
+                sum_gas_used += tips[index].1;
+            }
+
+            tips[index].0
+        })
+        .collect()
+}
+
+/// Returns the lowest tips paid in a block, skipping transactions sent by its fee recipient
+/// and tips too small to be meaningful.
+fn lowest_tips(block: &Block) -> Vec<U256> {
+    let txs = match &block.transactions {
+        Transactions::Full(txs) => txs,
+        Transactions::Hashes(_) => unreachable!(),
+    };
+
+    let mut tips = txs
+        .iter()
+        .filter(|tx| tx.from != block.miner)
+        .map(|tx| effective_tip(tx, block.base_fee_per_gas))
+        .filter(|tip| *tip >= U256::from(GAS_PRICE_ORACLE_IGNORE_PRICE))
+        .collect::<Vec<_>>();
+
+    tips.sort();
+    tips.truncate(GAS_PRICE_ORACLE_SAMPLES);
+    tips
+}
+
+/// Encodes a receipt as it is committed to by the receipts root. Receipts from before
+/// Byzantium commit to a state root instead of a status.
+pub(crate) fn encode_receipt(receipt: &TransactionReceipt) -> Result<Vec<u8>> {
+    let incomplete =
+        |field| ExecutionError::IncompleteReceipt(receipt.transaction_hash.to_string(), field);
+
     let mut stream = RlpStream::new();
     stream.begin_list(4);
-    stream.append(&receipt.status.unwrap());
+    match (receipt.status, receipt.root) {
+        (Some(status), _) => stream.append(&status),
+        (None, Some(root)) => stream.append(&root),
+        (None, None) => return Err(incomplete("status").into()),
+    };
     stream.append(&receipt.cumulative_gas_used);
     stream.append(&receipt.logs_bloom);
     stream.append_list(&receipt.logs);
 
     let legacy_receipt_encoded = stream.out();
-    let tx_type = receipt.transaction_type.unwrap().as_u64();
+    let tx_type = receipt
+        .transaction_type
+        .ok_or(incomplete("transaction type"))?
+        .as_u64();
 
-    match tx_type {
+    Ok(match tx_type {
         0 => legacy_receipt_encoded.to_vec(),
         _ => [&tx_type.to_be_bytes()[7..8], &legacy_receipt_encoded].concat(),
+    })
+}
+
+/// Verifies a proof against the state root of `block` and checks that it answers exactly the
+/// request in `item`, with the storage proofs in the order the slots were requested.
+fn verify_account_proof(
+    item: &AccessListItem,
+    proof: &EIP1186ProofResponse,
+    block: &Block,
+) -> Result<Account> {
+    let address = &item.address;
+    proof::verify_account(address, proof, block.state_root)
+        .map_err(|err| ExecutionError::InvalidAccountProof(*address, err))?;
+
+    let slots = proof
+        .storage_proof
+        .iter()
+        .map(|storage_proof| storage_proof.key);
+    if proof.address != *address || !slots.eq(item.storage_keys.iter().copied()) {
+        return Err(ExecutionError::ProofResponseMismatch(*address).into());
     }
+
+    let (storage_hash, code_hash) = proof::account_hashes(proof);
+
+    let mut slot_map = HashMap::new();
+
+    for storage_proof in &proof.storage_proof {
+        proof::verify_storage(storage_proof, storage_hash)
+            .map_err(|err| ExecutionError::InvalidStorageProof(*address, storage_proof.key, err))?;
+
+        slot_map.insert(storage_proof.key, storage_proof.value);
+    }
+
+    Ok(Account {
+        balance: proof.balance,
+        nonce: proof.nonce.as_u64(),
+        code: Vec::new(),
+        code_hash,
+        storage_hash,
+        slots: slot_map,
+    })
 }
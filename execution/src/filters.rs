@@ -0,0 +1,128 @@
+use std::{collections::HashMap, ops::RangeInclusive, sync::Arc, time::Duration};
+
+use ethers::types::{Filter, H128, U256};
+use tokio::sync::RwLock;
+use wasm_timer::Instant;
+
+/// A filter installed with `eth_newFilter`, `eth_newBlockFilter` or
+/// `eth_newPendingTransactionFilter`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstalledFilter {
+    Logs(Filter),
+    Blocks,
+    PendingTransactions,
+}
+
+/// Filters kept locally instead of upstream. Each filter remembers the last block reported to
+/// it, so changes are computed from the verified blocks helios has received since. Like geth,
+/// filters that are not polled within the timeout are removed.
+#[derive(Clone)]
+pub struct Filters {
+    inner: Arc<RwLock<HashMap<U256, FilterEntry>>>,
+    timeout: Duration,
+}
+
+struct FilterEntry {
+    filter: InstalledFilter,
+    last_block: u64,
+    last_poll: Instant,
+}
+
+impl Filters {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(HashMap::new())),
+            timeout,
+        }
+    }
+
+    /// Installs a filter which reports changes after `last_block`, and returns its id.
+    pub async fn install(&self, filter: InstalledFilter, last_block: u64) -> U256 {
+        let mut inner = self.inner.write().await;
+        self.prune(&mut inner);
+
+        let id = loop {
+            let id = U256::from_big_endian(H128::random().as_bytes());
+            if !inner.contains_key(&id) {
+                break id;
+            }
+        };
+
+        let entry = FilterEntry {
+            filter,
+            last_block,
+            last_poll: Instant::now(),
+        };
+
+        inner.insert(id, entry);
+        id
+    }
+
+    pub async fn uninstall(&self, id: &U256) -> bool {
+        let mut inner = self.inner.write().await;
+        self.prune(&mut inner);
+        inner.remove(id).is_some()
+    }
+
+    /// Returns the filter along with the blocks up to `latest_block` that have not been
+    /// reported to it yet. They are only marked as reported by [`Filters::advance`], so blocks
+    /// whose changes could not be computed are returned again by the next poll.
+    pub async fn poll(
+        &self,
+        id: &U256,
+        latest_block: u64,
+    ) -> Option<(InstalledFilter, RangeInclusive<u64>)> {
+        let mut inner = self.inner.write().await;
+        self.prune(&mut inner);
+
+        let entry = inner.get_mut(id)?;
+        entry.last_poll = Instant::now();
+
+        Some((entry.filter.clone(), entry.last_block + 1..=latest_block))
+    }
+
+    /// Marks every block up to `last_block` as reported to the filter.
+    pub async fn advance(&self, id: &U256, last_block: u64) {
+        if let Some(entry) = self.inner.write().await.get_mut(id) {
+            entry.last_block = entry.last_block.max(last_block);
+        }
+    }
+
+    fn prune(&self, inner: &mut HashMap<U256, FilterEntry>) {
+        inner.retain(|_, entry| entry.last_poll.elapsed() < self.timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_poll_advances() {
+        let filters = Filters::new(Duration::from_secs(300));
+        let id = filters.install(InstalledFilter::Blocks, 10).await;
+
+        let (filter, blocks) = filters.poll(&id, 12).await.unwrap();
+        assert_eq!(filter, InstalledFilter::Blocks);
+        assert_eq!(blocks, 11..=12);
+
+        // blocks are reported again until the filter is advanced past them
+        let (_, blocks) = filters.poll(&id, 12).await.unwrap();
+        assert_eq!(blocks, 11..=12);
+
+        filters.advance(&id, 12).await;
+        let (_, blocks) = filters.poll(&id, 12).await.unwrap();
+        assert!(blocks.is_empty());
+
+        assert!(filters.uninstall(&id).await);
+        assert!(filters.poll(&id, 12).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expiry() {
+        let filters = Filters::new(Duration::ZERO);
+        let id = filters.install(InstalledFilter::Blocks, 10).await;
+
+        assert!(filters.poll(&id, 12).await.is_none());
+    }
+}
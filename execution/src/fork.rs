@@ -0,0 +1,918 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use common::types::{Block, BlockTag, Transactions};
+use common::utils::{decode_transaction, encode_transaction};
+use ethers::abi::ethereum_types::BloomInput;
+use ethers::types::{Address, Bloom, Log, Transaction, TransactionReceipt, H256, H64, U256, U64};
+use ethers::utils::keccak256;
+use eyre::{eyre, Report, Result};
+use revm::{
+    db::{CacheDB, DatabaseRef},
+    primitives::{
+        AccountInfo, BlockEnv, CreateScheme, EVMError, Env, ExecutionResult, Output,
+        ResultAndState, TransactTo, B160, B256,
+    },
+    Database, DatabaseCommit, EVM,
+};
+use tokio::sync::Mutex;
+use tracing::info;
+use triehash_ethereum::ordered_trie_root;
+
+use crate::constants::{DEFAULT_PRIORITY_FEE, FORK_BLOCK_TIME};
+use crate::db::VerifiedDB;
+use crate::errors::{EvmError, ExecutionError};
+use crate::execution::{encode_receipt, next_base_fee};
+use crate::rpc::ExecutionRpc;
+use crate::types::{CallOpts, TransactionOpts};
+use crate::ExecutionClient;
+
+/// A local chain forked from a verified block, for testing against real state without trusting
+/// a fork provider.
+///
+/// State is read from an in-memory overlay first, and from the verified state of the pinned
+/// block otherwise. Transactions are executed against the overlay and each one is mined right
+/// away into its own local block, whose timestamp follows its parent by 12 seconds and whose
+/// base fee follows EIP-1559. The state roots of local blocks are not computed and are left
+/// empty.
+///
+/// Only the state of the latest local block and of the pinned block are kept. The state of a
+/// fork can be saved with [`Fork::snapshot`] and restored with [`Fork::revert`], and
+/// impersonated accounts can send transactions without signing them.
+pub struct Fork<R: ExecutionRpc> {
+    execution: Arc<ExecutionClient<R>>,
+    chain_id: u64,
+    inner: Mutex<ForkInner<R>>,
+}
+
+struct ForkInner<R: ExecutionRpc> {
+    state: ForkState<R>,
+    snapshots: Vec<ForkState<R>>,
+    impersonated: HashSet<Address>,
+}
+
+#[derive(Clone)]
+struct ForkState<R: ExecutionRpc> {
+    db: CacheDB<VerifiedDB<R>>,
+    // the pinned block followed by every local block
+    blocks: Vec<Block>,
+    transactions: HashMap<H256, Transaction>,
+    receipts: HashMap<H256, TransactionReceipt>,
+}
+
+enum StateAt {
+    Head,
+    Pinned,
+}
+
+impl<R: ExecutionRpc> Fork<R> {
+    /// Forks the chain at the verified block at `tag`.
+    pub async fn new(
+        execution: Arc<ExecutionClient<R>>,
+        chain_id: u64,
+        tag: BlockTag,
+    ) -> Result<Self> {
+        let db = VerifiedDB::new(execution.clone(), tag).await?;
+        let block = db.block().clone();
+
+        info!(
+            target: "helios::execution",
+            "forked chain at block {} ({:?})",
+            block.number,
+            block.hash
+        );
+
+        let state = ForkState {
+            db: CacheDB::new(db),
+            blocks: vec![block],
+            transactions: HashMap::new(),
+            receipts: HashMap::new(),
+        };
+
+        Ok(Self {
+            execution,
+            chain_id,
+            inner: Mutex::new(ForkInner {
+                state,
+                snapshots: Vec::new(),
+                impersonated: HashSet::new(),
+            }),
+        })
+    }
+
+    /// Returns whether `tag` refers to the pinned block or a local block. Other blocks are
+    /// shared with the verified chain.
+    pub async fn contains(&self, tag: BlockTag) -> bool {
+        self.inner.lock().await.state.block_index(tag).is_some()
+    }
+
+    pub async fn block_number(&self) -> u64 {
+        self.inner.lock().await.state.head().number.as_u64()
+    }
+
+    pub async fn get_block(&self, tag: BlockTag, full_tx: bool) -> Result<Option<Block>> {
+        let inner = self.inner.lock().await;
+        let index = match inner.state.block_index(tag) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let block = &inner.state.blocks[index];
+        if index == 0 && full_tx {
+            let tag = BlockTag::Number(block.number.as_u64());
+            return Ok(Some(self.execution.get_block(tag, true).await?));
+        }
+
+        Ok(Some(with_transactions(block, full_tx)))
+    }
+
+    pub async fn get_block_by_hash(&self, hash: H256, full_tx: bool) -> Result<Option<Block>> {
+        self.get_block(BlockTag::Hash(hash), full_tx).await
+    }
+
+    pub async fn get_balance(&self, address: &Address, tag: BlockTag) -> Result<U256> {
+        let mut inner = self.inner.lock().await;
+        let info = inner.state.account(address, tag)?;
+        Ok(info.balance.into())
+    }
+
+    pub async fn get_nonce(&self, address: &Address, tag: BlockTag) -> Result<u64> {
+        let mut inner = self.inner.lock().await;
+        let info = inner.state.account(address, tag)?;
+        Ok(info.nonce)
+    }
+
+    pub async fn get_code(&self, address: &Address, tag: BlockTag) -> Result<Vec<u8>> {
+        let mut inner = self.inner.lock().await;
+        inner.state.code(address, tag)
+    }
+
+    pub async fn get_storage_at(
+        &self,
+        address: &Address,
+        slot: H256,
+        tag: BlockTag,
+    ) -> Result<U256> {
+        let mut inner = self.inner.lock().await;
+        inner.state.storage(address, slot, tag)
+    }
+
+    pub async fn call(&self, opts: &CallOpts, tag: BlockTag) -> Result<Vec<u8>, EvmError> {
+        let mut inner = self.inner.lock().await;
+        let result = inner.state.call(opts, tag, self.chain_id)?;
+
+        match result {
+            ExecutionResult::Success { output, .. } => Ok(output.into_data().to_vec()),
+            ExecutionResult::Revert { output, .. } => Err(EvmError::Revert(Some(output))),
+            ExecutionResult::Halt { .. } => Err(EvmError::Revert(None)),
+        }
+    }
+
+    /// Finds the lowest gas limit a call succeeds with on top of the latest local state.
+    pub async fn estimate_gas(&self, opts: &CallOpts) -> Result<u64, EvmError> {
+        let mut inner = self.inner.lock().await;
+        inner.state.estimate_gas(opts, self.chain_id)
+    }
+
+    /// Executes a signed transaction on top of the latest local state and mines it into a new
+    /// local block. Transactions that are invalid against the local state are rejected.
+    pub async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
+        let tx = decode_transaction(bytes)?;
+        let mut inner = self.inner.lock().await;
+        inner.state.execute(tx, self.chain_id)
+    }
+
+    /// Completes a transaction from an impersonated account from the local state, then
+    /// executes and mines it without signing it. Its hash commits to its sender, since it
+    /// has no signature.
+    pub async fn send_transaction(&self, opts: &TransactionOpts) -> Result<H256> {
+        let from = opts.from.ok_or(eyre!("missing transaction sender"))?;
+        let mut inner = self.inner.lock().await;
+
+        if !inner.impersonated.contains(&from) {
+            return Err(ExecutionError::AccountNotImpersonated(from).into());
+        }
+
+        if let Some(chain_id) = opts.chain_id {
+            if chain_id.as_u64() != self.chain_id {
+                return Err(eyre!(
+                    "invalid chain id: {}, expected: {}",
+                    chain_id,
+                    self.chain_id
+                ));
+            }
+        }
+
+        let tx = inner.state.fill_transaction(from, opts, self.chain_id)?;
+        inner.state.execute(tx, self.chain_id)
+    }
+
+    pub async fn get_transaction_receipt(&self, hash: &H256) -> Option<TransactionReceipt> {
+        self.inner.lock().await.state.receipts.get(hash).cloned()
+    }
+
+    pub async fn get_transaction(&self, hash: &H256) -> Option<Transaction> {
+        self.inner
+            .lock()
+            .await
+            .state
+            .transactions
+            .get(hash)
+            .cloned()
+    }
+
+    /// Mines an empty local block, and returns its number.
+    pub async fn mine(&self) -> u64 {
+        self.inner.lock().await.state.mine(None)
+    }
+
+    /// Saves the current local state, and returns the id to revert to it with.
+    pub async fn snapshot(&self) -> U256 {
+        let mut inner = self.inner.lock().await;
+        let snapshot = inner.state.clone();
+        inner.snapshots.push(snapshot);
+
+        U256::from(inner.snapshots.len() - 1)
+    }
+
+    /// Restores the local state saved by a snapshot. The snapshot and every snapshot taken
+    /// after it are discarded. Returns false if the snapshot does not exist.
+    pub async fn revert(&self, id: U256) -> bool {
+        let mut inner = self.inner.lock().await;
+        if id >= U256::from(inner.snapshots.len()) {
+            return false;
+        }
+
+        let id = id.as_usize();
+        inner.state = inner.snapshots[id].clone();
+        inner.snapshots.truncate(id);
+
+        true
+    }
+
+    /// Lets an account send transactions without signing them.
+    pub async fn impersonate(&self, address: Address) {
+        self.inner.lock().await.impersonated.insert(address);
+    }
+
+    pub async fn stop_impersonating(&self, address: &Address) {
+        self.inner.lock().await.impersonated.remove(address);
+    }
+}
+
+impl<R: ExecutionRpc> ForkState<R> {
+    fn head(&self) -> &Block {
+        self.blocks.last().unwrap()
+    }
+
+    fn block_index(&self, tag: BlockTag) -> Option<usize> {
+        match tag {
+            BlockTag::Latest | BlockTag::Pending => Some(self.blocks.len() - 1),
+            BlockTag::Number(number) => number
+                .checked_sub(self.blocks[0].number.as_u64())
+                .map(|index| index as usize)
+                .filter(|index| *index < self.blocks.len()),
+            BlockTag::Hash(hash) => self.blocks.iter().position(|block| block.hash == hash),
+            _ => None,
+        }
+    }
+
+    fn state_at(&self, tag: BlockTag) -> Result<StateAt> {
+        match self.block_index(tag) {
+            Some(index) if index + 1 == self.blocks.len() => Ok(StateAt::Head),
+            Some(0) => Ok(StateAt::Pinned),
+            _ => Err(ExecutionError::ForkStateUnavailable(tag).into()),
+        }
+    }
+
+    fn account(&mut self, address: &Address, tag: BlockTag) -> Result<AccountInfo> {
+        let address = B160::from(*address);
+        let info = match self.state_at(tag)? {
+            StateAt::Head => Database::basic(&mut self.db, address)?,
+            StateAt::Pinned => DatabaseRef::basic(&self.db.db, address)?,
+        };
+
+        Ok(info.unwrap_or_default())
+    }
+
+    fn code(&mut self, address: &Address, tag: BlockTag) -> Result<Vec<u8>> {
+        let info = self.account(address, tag)?;
+        let code = match info.code {
+            Some(code) => code,
+            None => match self.state_at(tag)? {
+                StateAt::Head => Database::code_by_hash(&mut self.db, info.code_hash)?,
+                StateAt::Pinned => DatabaseRef::code_by_hash(&self.db.db, info.code_hash)?,
+            },
+        };
+
+        Ok(code.original_bytes().to_vec())
+    }
+
+    fn storage(&mut self, address: &Address, slot: H256, tag: BlockTag) -> Result<U256> {
+        let address = B160::from(*address);
+        let slot = B256::from(slot).into();
+        let value = match self.state_at(tag)? {
+            StateAt::Head => Database::storage(&mut self.db, address, slot)?,
+            StateAt::Pinned => DatabaseRef::storage(&self.db.db, address, slot)?,
+        };
+
+        Ok(value.into())
+    }
+
+    fn call(
+        &mut self,
+        opts: &CallOpts,
+        tag: BlockTag,
+        chain_id: u64,
+    ) -> Result<ExecutionResult, EvmError> {
+        let state_at = self
+            .state_at(tag)
+            .map_err(|err| EvmError::Generic(err.to_string()))?;
+
+        let res = match state_at {
+            StateAt::Head => {
+                let env = call_env(opts, self.pending_block_env(), chain_id)
+                    .map_err(|err| EvmError::Generic(err.to_string()))?;
+                transact(&mut self.db, env)
+            }
+            StateAt::Pinned => {
+                let env = call_env(opts, self.db.db.block_env(), chain_id)
+                    .map_err(|err| EvmError::Generic(err.to_string()))?;
+                transact(self.db.db.clone(), env)
+            }
+        };
+
+        res.map(|res| res.result)
+            .map_err(|err| EvmError::Generic(format!("{err:?}")))
+    }
+
+    fn estimate_gas(&mut self, opts: &CallOpts, chain_id: u64) -> Result<u64, EvmError> {
+        let mut env = call_env(opts, self.pending_block_env(), chain_id)
+            .map_err(|err| EvmError::Generic(err.to_string()))?;
+        let cap = env.tx.gas_limit;
+
+        let gas_used = match transact(&mut self.db, env.clone()) {
+            Ok(ResultAndState { result, .. }) => match result {
+                ExecutionResult::Success { gas_used, .. } => gas_used,
+                ExecutionResult::Revert { output, .. } => {
+                    return Err(EvmError::Revert(Some(output)))
+                }
+                ExecutionResult::Halt { reason, .. } => {
+                    return Err(EvmError::Generic(format!("{reason:?}")))
+                }
+            },
+            Err(err) => return Err(EvmError::Generic(format!("{err:?}"))),
+        };
+
+        // gas refunds and the gas withheld from subcalls can make the call fail with a limit
+        // of exactly the gas it used, so the lowest working limit is searched for
+        let (mut low, mut high) = (gas_used.saturating_sub(1), cap);
+        while low + 1 < high {
+            let mid = low + (high - low) / 2;
+            env.tx.gas_limit = mid;
+
+            let succeeded = matches!(
+                transact(&mut self.db, env.clone()),
+                Ok(ResultAndState {
+                    result: ExecutionResult::Success { .. },
+                    ..
+                })
+            );
+
+            if succeeded {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        Ok(high)
+    }
+
+    fn fill_transaction(
+        &mut self,
+        from: Address,
+        opts: &TransactionOpts,
+        chain_id: u64,
+    ) -> Result<Transaction> {
+        let nonce = match opts.nonce {
+            Some(nonce) => nonce,
+            None => self.account(&from, BlockTag::Latest)?.nonce.into(),
+        };
+
+        let gas = match opts.gas {
+            Some(gas) => gas,
+            None => {
+                let call_opts = CallOpts {
+                    from: Some(from),
+                    to: opts.to,
+                    gas: None,
+                    gas_price: None,
+                    value: opts.value,
+                    data: opts.data.clone(),
+                };
+
+                self.estimate_gas(&call_opts, chain_id)?.into()
+            }
+        };
+
+        let mut tx = Transaction {
+            from,
+            to: opts.to,
+            nonce,
+            gas,
+            value: opts.value.unwrap_or_default(),
+            input: opts.data.clone().unwrap_or_default(),
+            chain_id: Some(chain_id.into()),
+            ..Default::default()
+        };
+
+        match opts.gas_price {
+            Some(gas_price) => {
+                tx.gas_price = Some(gas_price);
+                if let Some(access_list) = &opts.access_list {
+                    tx.transaction_type = Some(1.into());
+                    tx.access_list = Some(access_list.clone());
+                }
+            }
+            None => {
+                let default_priority_fee = U256::from(DEFAULT_PRIORITY_FEE);
+                let max_priority_fee = match (opts.max_priority_fee_per_gas, opts.max_fee_per_gas) {
+                    (Some(max_priority_fee), _) => max_priority_fee,
+                    (None, Some(max_fee)) => default_priority_fee.min(max_fee),
+                    (None, None) => default_priority_fee,
+                };
+
+                let max_fee = opts
+                    .max_fee_per_gas
+                    .unwrap_or(next_base_fee(self.head()) * 2 + max_priority_fee);
+
+                tx.transaction_type = Some(2.into());
+                tx.max_priority_fee_per_gas = Some(max_priority_fee);
+                tx.max_fee_per_gas = Some(max_fee);
+                tx.access_list = Some(opts.access_list.clone().unwrap_or_default());
+            }
+        }
+
+        tx.hash = keccak256([encode_transaction(&tx), from.as_bytes().to_vec()].concat()).into();
+        Ok(tx)
+    }
+
+    fn execute(&mut self, tx: Transaction, chain_id: u64) -> Result<H256> {
+        if tx.transaction_type == Some(3.into()) {
+            return Err(ExecutionError::InvalidForkTransaction(
+                "blob transactions are not supported".to_string(),
+            )
+            .into());
+        }
+
+        if self.transactions.contains_key(&tx.hash) {
+            return Err(ExecutionError::InvalidForkTransaction("already known".to_string()).into());
+        }
+
+        let mut env = Env::default();
+        env.cfg.chain_id = revm::primitives::U256::from(chain_id);
+        env.block = self.pending_block_env();
+
+        env.tx.caller = tx.from.into();
+        env.tx.transact_to = match tx.to {
+            Some(to) => TransactTo::Call(to.into()),
+            None => TransactTo::Create(CreateScheme::Create),
+        };
+        env.tx.value = tx.value.into();
+        env.tx.data = Bytes::from(tx.input.to_vec());
+        env.tx.gas_limit = u64_field(tx.gas, "gas limit")?;
+        env.tx.gas_price = tx
+            .max_fee_per_gas
+            .or(tx.gas_price)
+            .unwrap_or_default()
+            .into();
+        env.tx.gas_priority_fee = tx.max_priority_fee_per_gas.map(Into::into);
+        env.tx.nonce = Some(u64_field(tx.nonce, "nonce")?);
+        env.tx.chain_id = tx
+            .chain_id
+            .map(|chain_id| u64_field(chain_id, "chain id"))
+            .transpose()?;
+        env.tx.access_list = tx
+            .access_list
+            .iter()
+            .flat_map(|access_list| access_list.0.iter())
+            .map(|item| {
+                let keys = item
+                    .storage_keys
+                    .iter()
+                    .map(|key| B256::from(*key).into())
+                    .collect();
+                (item.address.into(), keys)
+            })
+            .collect();
+
+        let ResultAndState { result, state } = transact(&mut self.db, env)
+            .map_err(|err| ExecutionError::InvalidForkTransaction(format!("{err:?}")))?;
+
+        self.db.commit(state);
+
+        let hash = tx.hash;
+        let number = self.mine(Some((tx, result)));
+        info!(
+            target: "helios::execution",
+            "mined transaction {:?} in local block {}",
+            hash,
+            number
+        );
+
+        Ok(hash)
+    }
+
+    /// Mines a local block on top of the head, including an executed transaction if any.
+    fn mine(&mut self, executed: Option<(Transaction, ExecutionResult)>) -> u64 {
+        let parent = self.head();
+        let number = parent.number.as_u64() + 1;
+        let base_fee = next_base_fee(parent);
+
+        let mut block = Block {
+            number: number.into(),
+            base_fee_per_gas: base_fee,
+            gas_limit: parent.gas_limit,
+            miner: parent.miner,
+            mix_hash: parent.mix_hash,
+            parent_hash: parent.hash,
+            sha3_uncles: keccak256([0xc0]).into(),
+            timestamp: (parent.timestamp.as_u64() + FORK_BLOCK_TIME).into(),
+            nonce: format!("{:?}", H64::zero()),
+            ..Default::default()
+        };
+
+        let mut txs = Vec::new();
+        let mut receipts = Vec::new();
+        let mut logs_bloom = Bloom::default();
+
+        if let Some((mut tx, result)) = executed {
+            let (status, gas_used, logs, contract_address) = match result {
+                ExecutionResult::Success {
+                    gas_used,
+                    logs,
+                    output,
+                    ..
+                } => {
+                    let contract_address = match output {
+                        Output::Create(_, address) => address.map(Address::from),
+                        Output::Call(_) => None,
+                    };
+
+                    (1u64, gas_used, logs, contract_address)
+                }
+                ExecutionResult::Revert { gas_used, .. } => (0, gas_used, Vec::new(), None),
+                ExecutionResult::Halt { gas_used, .. } => (0, gas_used, Vec::new(), None),
+            };
+
+            let effective_gas_price = match tx.max_fee_per_gas {
+                Some(max_fee) => {
+                    max_fee.min(base_fee + tx.max_priority_fee_per_gas.unwrap_or_default())
+                }
+                None => tx.gas_price.unwrap_or_default(),
+            };
+
+            let logs = logs
+                .into_iter()
+                .enumerate()
+                .map(|(index, log)| Log {
+                    address: Address::from_slice(log.address.as_bytes()),
+                    topics: log
+                        .topics
+                        .iter()
+                        .map(|topic| H256::from_slice(topic.as_bytes()))
+                        .collect(),
+                    data: log.data.to_vec().into(),
+                    block_number: Some(number.into()),
+                    transaction_hash: Some(tx.hash),
+                    transaction_index: Some(U64::zero()),
+                    log_index: Some(index.into()),
+                    transaction_log_index: Some(index.into()),
+                    removed: Some(false),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+
+            for log in &logs {
+                logs_bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+                for topic in &log.topics {
+                    logs_bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+                }
+            }
+
+            tx.block_number = Some(number.into());
+            tx.transaction_index = Some(U64::zero());
+
+            receipts.push(TransactionReceipt {
+                transaction_hash: tx.hash,
+                transaction_index: U64::zero(),
+                block_number: Some(number.into()),
+                from: tx.from,
+                to: tx.to,
+                cumulative_gas_used: gas_used.into(),
+                gas_used: Some(gas_used.into()),
+                contract_address,
+                logs,
+                status: Some(status.into()),
+                logs_bloom,
+                transaction_type: Some(tx.transaction_type.unwrap_or_default()),
+                effective_gas_price: Some(effective_gas_price),
+                ..Default::default()
+            });
+
+            block.gas_used = gas_used.into();
+            txs.push(tx);
+        }
+
+        let transactions_root = ordered_trie_root(txs.iter().map(encode_transaction));
+        // local receipts always carry a status and a transaction type
+        let receipts_root = ordered_trie_root(
+            receipts
+                .iter()
+                .map(|receipt| encode_receipt(receipt).unwrap()),
+        );
+        block.transactions_root = H256::from_slice(&transactions_root.to_fixed_bytes());
+        block.receipts_root = H256::from_slice(&receipts_root.to_fixed_bytes());
+        block.logs_bloom = logs_bloom.as_bytes().to_vec().into();
+        // the nonce of local blocks is always well formed
+        block.hash = block.header_hash().unwrap();
+
+        for tx in txs.iter_mut() {
+            tx.block_hash = Some(block.hash);
+            self.transactions.insert(tx.hash, tx.clone());
+        }
+
+        for mut receipt in receipts {
+            receipt.block_hash = Some(block.hash);
+            for log in receipt.logs.iter_mut() {
+                log.block_hash = Some(block.hash);
+            }
+
+            self.receipts.insert(receipt.transaction_hash, receipt);
+        }
+
+        let number_revm = revm::primitives::U256::from(number);
+        self.db.block_hashes.insert(number_revm, block.hash.into());
+
+        block.transactions = Transactions::Full(txs);
+        self.blocks.push(block);
+
+        number
+    }
+
+    /// Returns the environment of the next local block.
+    fn pending_block_env(&self) -> BlockEnv {
+        let head = self.head();
+
+        BlockEnv {
+            number: revm::primitives::U256::from(head.number.as_u64() + 1),
+            coinbase: head.miner.into(),
+            timestamp: revm::primitives::U256::from(head.timestamp.as_u64() + FORK_BLOCK_TIME),
+            difficulty: revm::primitives::U256::ZERO,
+            prevrandao: Some(head.mix_hash.into()),
+            basefee: next_base_fee(head).into(),
+            gas_limit: revm::primitives::U256::from(head.gas_limit.as_u64()),
+        }
+    }
+}
+
+fn transact<DB: Database<Error = Report>>(
+    db: DB,
+    env: Env,
+) -> Result<ResultAndState, EVMError<Report>> {
+    let mut evm = EVM::new();
+    evm.database(db);
+    evm.env = env;
+    evm.transact()
+}
+
+/// Builds the environment of a call. Calls pay no base fee and default to the block gas limit.
+/// Gas limits that do not fit in the `u64` revm holds are rejected.
+fn call_env(opts: &CallOpts, mut block: BlockEnv, chain_id: u64) -> Result<Env> {
+    let mut env = Env::default();
+
+    env.tx.caller = opts.from.map(B160::from).unwrap_or_default();
+    env.tx.transact_to = match opts.to {
+        Some(to) => TransactTo::Call(to.into()),
+        None => TransactTo::Create(CreateScheme::Create),
+    };
+    env.tx.value = opts.value.unwrap_or_default().into();
+    env.tx.data = Bytes::from(opts.data.clone().unwrap_or_default().to_vec());
+    let gas_limit: U256 = block.gas_limit.into();
+    env.tx.gas_limit = u64_field(opts.gas.unwrap_or(gas_limit), "gas limit")?;
+    env.tx.gas_price = opts.gas_price.unwrap_or_default().into();
+
+    block.basefee = revm::primitives::U256::ZERO;
+    env.block = block;
+    env.cfg.chain_id = revm::primitives::U256::from(chain_id);
+
+    Ok(env)
+}
+
+/// Converts a transaction field that revm holds as a `u64`, rejecting the transaction if the
+/// value does not fit.
+fn u64_field(value: U256, name: &str) -> Result<u64> {
+    if value > U256::from(u64::MAX) {
+        return Err(
+            ExecutionError::InvalidForkTransaction(format!("{name} too high: {value}")).into(),
+        );
+    }
+
+    Ok(value.as_u64())
+}
+
+fn with_transactions(block: &Block, full_tx: bool) -> Block {
+    let mut block = block.clone();
+    if !full_tx {
+        block.transactions = Transactions::Hashes(block.transactions.hashes());
+    }
+
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tokio::sync::{mpsc::channel, watch};
+
+    use crate::{rpc::mock_rpc::MockRpc, state::State};
+
+    use super::*;
+
+    async fn get_fork() -> Fork<MockRpc> {
+        let (_, block_recv) = channel(256);
+        let (_, finalized_recv) = watch::channel(None);
+        let (_, safe_recv) = watch::channel(None);
+        let state = State::new(block_recv, finalized_recv, safe_recv, 64);
+
+        let block = Block {
+            number: 100.into(),
+            gas_limit: 30_000_000.into(),
+            state_root: H256::from_str(
+                "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        state.push_block(block).await;
+        let execution = Arc::new(ExecutionClient::new("testdata/", state).unwrap());
+        Fork::new(execution, 1, BlockTag::Latest).await.unwrap()
+    }
+
+    async fn set_balance(fork: &Fork<MockRpc>, address: Address, balance: u64) {
+        let info = AccountInfo {
+            balance: revm::primitives::U256::from(balance),
+            ..Default::default()
+        };
+
+        let mut inner = fork.inner.lock().await;
+        inner.state.db.insert_account_info(address.into(), info);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fork_reads_verified_state() {
+        let fork = get_fork().await;
+        let address = Address::from_str("14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+
+        let balance = fork.get_balance(&address, BlockTag::Latest).await.unwrap();
+        let pinned_balance = fork
+            .get_balance(&address, BlockTag::Number(100))
+            .await
+            .unwrap();
+
+        assert_eq!(balance, U256::from_str_radix("48c27395000", 16).unwrap());
+        assert_eq!(pinned_balance, balance);
+        assert_eq!(fork.block_number().await, 100);
+
+        // blocks before the fork are served by the verified chain
+        assert!(!fork.contains(BlockTag::Number(99)).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fork_send_transaction_and_revert() {
+        let fork = get_fork().await;
+        let sender = Address::from_low_u64_be(0x1000);
+        let recipient = Address::from_low_u64_be(0x2000);
+
+        set_balance(&fork, sender, 1_000_000_000).await;
+        set_balance(&fork, recipient, 0).await;
+        set_balance(&fork, Address::zero(), 0).await;
+
+        let opts = TransactionOpts {
+            from: Some(sender),
+            to: Some(recipient),
+            value: Some(1000.into()),
+            max_fee_per_gas: Some(1.into()),
+            max_priority_fee_per_gas: Some(1.into()),
+            ..Default::default()
+        };
+
+        assert!(fork.send_transaction(&opts).await.is_err());
+
+        fork.impersonate(sender).await;
+        let snapshot = fork.snapshot().await;
+
+        let call_opts = CallOpts {
+            from: Some(sender),
+            to: Some(recipient),
+            gas: None,
+            gas_price: None,
+            value: Some(1000.into()),
+            data: None,
+        };
+
+        assert_eq!(fork.estimate_gas(&call_opts).await.unwrap(), 21000);
+
+        let hash = fork.send_transaction(&opts).await.unwrap();
+        let receipt = fork.get_transaction_receipt(&hash).await.unwrap();
+
+        assert_eq!(receipt.status, Some(1.into()));
+        assert_eq!(receipt.block_number, Some(101.into()));
+        assert_eq!(fork.block_number().await, 101);
+        assert_eq!(
+            fork.get_balance(&recipient, BlockTag::Latest)
+                .await
+                .unwrap(),
+            U256::from(1000)
+        );
+        assert_eq!(fork.get_nonce(&sender, BlockTag::Latest).await.unwrap(), 1);
+
+        let block = fork
+            .get_block(BlockTag::Number(101), false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.transactions.hashes(), vec![hash]);
+
+        assert!(fork.revert(snapshot).await);
+        assert_eq!(fork.block_number().await, 100);
+        assert_eq!(
+            fork.get_balance(&recipient, BlockTag::Latest)
+                .await
+                .unwrap(),
+            U256::zero()
+        );
+        assert!(fork.get_transaction_receipt(&hash).await.is_none());
+
+        // reverting discards the snapshot
+        assert!(!fork.revert(snapshot).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fork_rejects_oversized_fields() {
+        let fork = get_fork().await;
+        let sender = Address::from_low_u64_be(0x1000);
+
+        set_balance(&fork, sender, 1_000_000_000).await;
+        fork.impersonate(sender).await;
+
+        let opts = TransactionOpts {
+            from: Some(sender),
+            to: Some(Address::from_low_u64_be(0x2000)),
+            gas: Some(21000.into()),
+            max_fee_per_gas: Some(1.into()),
+            max_priority_fee_per_gas: Some(1.into()),
+            ..Default::default()
+        };
+
+        let oversized = [
+            TransactionOpts {
+                gas: Some(U256::MAX),
+                ..opts.clone()
+            },
+            TransactionOpts {
+                nonce: Some(U256::from(u64::MAX) + 1),
+                ..opts.clone()
+            },
+        ];
+
+        for opts in oversized {
+            let err = fork.send_transaction(&opts).await.unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<ExecutionError>(),
+                Some(ExecutionError::InvalidForkTransaction(_))
+            ));
+        }
+
+        let call_opts = CallOpts {
+            from: Some(sender),
+            to: Some(Address::from_low_u64_be(0x2000)),
+            gas: Some(U256::MAX),
+            gas_price: None,
+            value: None,
+            data: None,
+        };
+
+        let err = fork.call(&call_opts, BlockTag::Latest).await.unwrap_err();
+        assert!(matches!(err, EvmError::Generic(msg) if msg.contains("gas limit too high")));
+
+        let err = fork.estimate_gas(&call_opts).await.unwrap_err();
+        assert!(matches!(err, EvmError::Generic(msg) if msg.contains("gas limit too high")));
+
+        assert_eq!(fork.block_number().await, 100);
+    }
+}
@@ -1,11 +1,17 @@
+pub mod cache;
 pub mod constants;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod db;
 pub mod errors;
 pub mod evm;
+pub mod filters;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fork;
+pub mod proof;
 pub mod rpc;
 pub mod state;
+pub mod tracker;
 pub mod types;
 
 mod execution;
 pub use crate::execution::*;
-
-mod proof;
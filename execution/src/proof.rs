@@ -1,194 +1,509 @@
-use ethers::types::{Bytes, EIP1186ProofResponse};
+use ethers::types::{Address, EIP1186ProofResponse, StorageProof, H256};
 use ethers::utils::keccak256;
-use ethers::utils::rlp::{decode_list, RlpStream};
+use ethers::utils::rlp::{encode, Rlp, RlpStream};
+use revm::primitives::KECCAK_EMPTY;
+use thiserror::Error;
 
-pub fn verify_proof(proof: &Vec<Bytes>, root: &[u8], path: &Vec<u8>, value: &Vec<u8>) -> bool {
-    let mut expected_hash = root.to_vec();
+/// Root of the empty trie, the storage root of accounts without storage.
+pub const KECCAK_NULL_RLP: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Reasons a Merkle Patricia trie proof can fail verification.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    #[error("proof node {0} does not match the hash committed to by its parent")]
+    HashMismatch(usize),
+    #[error("proof node {0} is not a valid trie node")]
+    InvalidNode(usize),
+    #[error("proof ends before the key is resolved")]
+    MissingNode,
+    #[error("proof has {0} nodes past the one resolving the key")]
+    UnusedNodes(usize),
+    #[error("proven value does not match the expected value")]
+    ValueMismatch,
+    #[error("proof excludes the key but a value was expected")]
+    UnexpectedExclusion,
+    #[error("proof includes the key but its absence was expected")]
+    UnexpectedInclusion,
+}
+
+/// Walks a proof from `root` along `key` and returns the value stored at `key`, or `None` if
+/// the proof shows that the trie does not contain it. The proof must hold exactly the hashed
+/// nodes on the path to `key`, starting with the root. Nodes shorter than 32 bytes are
+/// embedded in their parent rather than listed separately.
+pub fn get_proven_value<N: AsRef<[u8]>>(
+    proof: &[N],
+    root: H256,
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, ProofError> {
+    if proof.is_empty() && root == KECCAK_NULL_RLP {
+        return Ok(None);
+    }
+
+    let path = to_nibbles(key);
     let mut path_offset = 0;
+    let mut next = NodeRef::Hash(root);
+    let mut used = 0;
 
-    for (i, node) in proof.iter().enumerate() {
-        if expected_hash != keccak256(node).to_vec() {
-            return false;
-        }
+    let value = loop {
+        let node = match next {
+            NodeRef::Hash(hash) => {
+                let node = proof.get(used).ok_or(ProofError::MissingNode)?.as_ref();
+                if H256(keccak256(node)) != hash {
+                    return Err(ProofError::HashMismatch(used));
+                }
 
-        let node_list: Vec<Vec<u8>> = decode_list(node);
+                used += 1;
+                node
+            }
+            NodeRef::Inline(node) => node,
+        };
+
+        let index = used - 1;
+        let node = Rlp::new(node);
+        let invalid = |_| ProofError::InvalidNode(index);
 
-        if node_list.len() == 17 {
-            if i == proof.len() - 1 {
-                // exclusion proof
-                let nibble = get_nibble(path, path_offset);
-                let node = &node_list[nibble as usize];
+        match node.item_count().map_err(invalid)? {
+            17 => {
+                let nibble = match path.get(path_offset) {
+                    Some(nibble) => nibble,
+                    None => break non_empty(node.at(16).and_then(|v| v.data()).map_err(invalid)?),
+                };
 
-                if node.is_empty() && is_empty_value(value) {
-                    return true;
+                let child = node.at(*nibble as usize).map_err(invalid)?;
+                if child.is_empty() {
+                    break None;
                 }
-            } else {
-                let nibble = get_nibble(path, path_offset);
-                expected_hash = node_list[nibble as usize].clone();
 
+                next = NodeRef::from_rlp(child).ok_or(ProofError::InvalidNode(index))?;
                 path_offset += 1;
             }
-        } else if node_list.len() == 2 {
-            if i == proof.len() - 1 {
-                // exclusion proof
-                if !paths_match(&node_list[0], skip_length(&node_list[0]), path, path_offset)
-                    && is_empty_value(value)
-                {
-                    return true;
-                }
+            2 => {
+                let encoded_path = node.at(0).and_then(|p| p.data()).map_err(invalid)?;
+                let (node_path, is_leaf) =
+                    decode_path(encoded_path).ok_or(ProofError::InvalidNode(index))?;
+                let rest = &path[path_offset..];
 
-                // inclusion proof
-                if &node_list[1] == value {
-                    return paths_match(
-                        &node_list[0],
-                        skip_length(&node_list[0]),
-                        path,
-                        path_offset,
-                    );
+                if is_leaf {
+                    if rest != node_path.as_slice() {
+                        break None;
+                    }
+
+                    break Some(node.at(1).and_then(|v| v.data()).map_err(invalid)?.to_vec());
                 }
-            } else {
-                let node_path = &node_list[0];
-                let prefix_length = shared_prefix_length(path, path_offset, node_path);
-                if prefix_length < node_path.len() * 2 - skip_length(node_path) {
-                    // The proof shows a divergent path, but we're not
-                    // at the end of the proof, so something's wrong.
-                    return false;
+
+                if !rest.starts_with(&node_path) {
+                    break None;
                 }
-                path_offset += prefix_length;
-                expected_hash = node_list[1].clone();
+
+                let child = node.at(1).map_err(invalid)?;
+                next = NodeRef::from_rlp(child).ok_or(ProofError::InvalidNode(index))?;
+                path_offset += node_path.len();
             }
-        } else {
-            return false;
+            _ => return Err(ProofError::InvalidNode(index)),
         }
+    };
+
+    if used < proof.len() {
+        return Err(ProofError::UnusedNodes(proof.len() - used));
     }
 
-    false
+    Ok(value)
 }
 
-fn paths_match(p1: &Vec<u8>, s1: usize, p2: &Vec<u8>, s2: usize) -> bool {
-    let len1 = p1.len() * 2 - s1;
-    let len2 = p2.len() * 2 - s2;
+/// Verifies that `key` holds `value` in the trie with `root`, or that the trie does not
+/// contain `key` if `value` is `None`.
+pub fn verify_proof<N: AsRef<[u8]>>(
+    proof: &[N],
+    root: H256,
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> Result<(), ProofError> {
+    let proven = get_proven_value(proof, root, key)?;
 
-    if len1 != len2 {
-        return false;
+    match (proven.as_deref(), value) {
+        (Some(proven), Some(value)) if proven == value => Ok(()),
+        (Some(_), Some(_)) => Err(ProofError::ValueMismatch),
+        (Some(_), None) => Err(ProofError::UnexpectedInclusion),
+        (None, Some(_)) => Err(ProofError::UnexpectedExclusion),
+        (None, None) => Ok(()),
     }
+}
 
-    for offset in 0..len1 {
-        let n1 = get_nibble(p1, s1 + offset);
-        let n2 = get_nibble(p2, s2 + offset);
+/// Verifies the account of an EIP-1186 proof against a state root. The proof is checked at
+/// the key of the requested `address` rather than the address reported in the response.
+/// Empty accounts are not stored in the state trie, so they are proven by exclusion.
+pub fn verify_account(
+    address: &Address,
+    proof: &EIP1186ProofResponse,
+    state_root: H256,
+) -> Result<(), ProofError> {
+    let account = encode_account(proof);
+    let value = (account != empty_account()).then_some(account.as_slice());
 
-        if n1 != n2 {
-            return false;
-        }
-    }
+    verify_proof(
+        &proof.account_proof,
+        state_root,
+        &account_key(address),
+        value,
+    )
+}
 
-    true
+/// Verifies a storage slot of an EIP-1186 proof against the storage root of its account. Zero
+/// slots are not stored in the storage trie, so they are proven by exclusion.
+pub fn verify_storage(proof: &StorageProof, storage_root: H256) -> Result<(), ProofError> {
+    let value = encode(&proof.value);
+    let value = (!proof.value.is_zero()).then_some(value.as_ref());
+
+    verify_proof(&proof.proof, storage_root, &storage_key(&proof.key), value)
 }
 
-#[allow(dead_code)]
-fn get_rest_path(p: &Vec<u8>, s: usize) -> String {
-    let mut ret = String::new();
-    for i in s..p.len() * 2 {
-        let n = get_nibble(p, i);
-        ret += &format!("{n:01x}");
-    }
-    ret
+/// Returns the key of an account in the state trie.
+pub fn account_key(address: &Address) -> [u8; 32] {
+    keccak256(address.as_bytes())
+}
+
+/// Returns the key of a slot in a storage trie.
+pub fn storage_key(slot: &H256) -> [u8; 32] {
+    keccak256(slot.as_bytes())
+}
+
+/// Returns the key of the entry at `index` in a transactions, receipts or withdrawals trie.
+pub fn index_key(index: usize) -> Vec<u8> {
+    encode(&index).to_vec()
 }
 
-fn is_empty_value(value: &Vec<u8>) -> bool {
-    let mut stream = RlpStream::new();
-    stream.begin_list(4);
-    stream.append_empty_data();
-    stream.append_empty_data();
-    let empty_storage_hash = "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
-    stream.append(&hex::decode(empty_storage_hash).unwrap());
-    let empty_code_hash = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
-    stream.append(&hex::decode(empty_code_hash).unwrap());
-    let empty_account = stream.out();
+/// Returns the storage and code hashes of the account of a proof. Some providers report zero
+/// storage and code hashes for accounts that do not exist, so those are replaced by the
+/// hashes of empty storage and code.
+pub fn account_hashes(proof: &EIP1186ProofResponse) -> (H256, H256) {
+    let storage_hash = if proof.storage_hash.is_zero() {
+        KECCAK_NULL_RLP
+    } else {
+        proof.storage_hash
+    };
+
+    let code_hash = if proof.code_hash.is_zero() {
+        KECCAK_EMPTY.into()
+    } else {
+        proof.code_hash
+    };
 
-    let is_empty_slot = value.len() == 1 && value[0] == 0x80;
-    let is_empty_account = value == &empty_account;
-    is_empty_slot || is_empty_account
+    (storage_hash, code_hash)
 }
 
-fn shared_prefix_length(path: &Vec<u8>, path_offset: usize, node_path: &Vec<u8>) -> usize {
-    let skip_length = skip_length(node_path);
+/// Encodes the account of a proof as it is stored in the state trie.
+pub fn encode_account(proof: &EIP1186ProofResponse) -> Vec<u8> {
+    let (storage_hash, code_hash) = account_hashes(proof);
+
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&proof.nonce);
+    stream.append(&proof.balance);
+    stream.append(&storage_hash);
+    stream.append(&code_hash);
+    let encoded = stream.out();
+    encoded.to_vec()
+}
 
-    let len = std::cmp::min(
-        node_path.len() * 2 - skip_length,
-        path.len() * 2 - path_offset,
-    );
-    let mut prefix_len = 0;
+fn empty_account() -> Vec<u8> {
+    encode_account(&EIP1186ProofResponse::default())
+}
 
-    for i in 0..len {
-        let path_nibble = get_nibble(path, i + path_offset);
-        let node_path_nibble = get_nibble(node_path, i + skip_length);
+enum NodeRef<'a> {
+    Hash(H256),
+    Inline(&'a [u8]),
+}
 
-        if path_nibble == node_path_nibble {
-            prefix_len += 1;
-        } else {
-            break;
+impl<'a> NodeRef<'a> {
+    /// Children are referenced by hash, unless their encoding is shorter than a hash in which
+    /// case they are embedded directly.
+    fn from_rlp(rlp: Rlp<'a>) -> Option<Self> {
+        if rlp.is_list() {
+            return Some(NodeRef::Inline(rlp.as_raw()));
+        }
+
+        match rlp.data().ok()? {
+            data if data.len() == 32 => Some(NodeRef::Hash(H256::from_slice(data))),
+            _ => None,
         }
     }
+}
 
-    prefix_len
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
 }
 
-fn skip_length(node: &Vec<u8>) -> usize {
-    if node.is_empty() {
-        return 0;
+/// Decodes the hex-prefix encoded path of a leaf or extension node, returning its nibbles and
+/// whether the node is a leaf.
+fn decode_path(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let first = *encoded.first()?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return None;
     }
 
-    let nibble = get_nibble(node, 0);
-    match nibble {
-        0 => 2,
-        1 => 1,
-        2 => 2,
-        3 => 1,
-        _ => 0,
-    }
-}
+    let is_leaf = flag >= 2;
+    let is_odd = flag % 2 == 1;
 
-fn get_nibble(path: &[u8], offset: usize) -> u8 {
-    let byte = path[offset / 2];
-    if offset % 2 == 0 {
-        byte >> 4
-    } else {
-        byte & 0xF
+    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+    if is_odd {
+        nibbles.push(first & 0x0f);
+    } else if first & 0x0f != 0 {
+        return None;
     }
+
+    nibbles.extend(to_nibbles(&encoded[1..]));
+    Some((nibbles, is_leaf))
 }
 
-pub fn encode_account(proof: &EIP1186ProofResponse) -> Vec<u8> {
-    let mut stream = RlpStream::new_list(4);
-    stream.append(&proof.nonce);
-    stream.append(&proof.balance);
-    stream.append(&proof.storage_hash);
-    stream.append(&proof.code_hash);
-    let encoded = stream.out();
-    encoded.to_vec()
+fn non_empty(value: &[u8]) -> Option<Vec<u8>> {
+    (!value.is_empty()).then(|| value.to_vec())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::proof::shared_prefix_length;
-
-    #[tokio::test]
-    async fn test_shared_prefix_length() {
-        // We compare the path starting from the 6th nibble i.e. the 6 in 0x6f
-        let path: Vec<u8> = vec![0x12, 0x13, 0x14, 0x6f, 0x6c, 0x64, 0x21];
-        let path_offset = 6;
-        // Our node path matches only the first 5 nibbles of the path
-        let node_path: Vec<u8> = vec![0x6f, 0x6c, 0x63, 0x21];
-        let shared_len = shared_prefix_length(&path, path_offset, &node_path);
-        assert_eq!(shared_len, 5);
-
-        // Now we compare the path starting from the 5th nibble i.e. the 4 in 0x14
-        let path: Vec<u8> = vec![0x12, 0x13, 0x14, 0x6f, 0x6c, 0x64, 0x21];
-        let path_offset = 5;
-        // Our node path matches only the first 7 nibbles of the path
-        // Note the first nibble is 1, so we skip 1 nibble
-        let node_path: Vec<u8> = vec![0x14, 0x6f, 0x6c, 0x64, 0x11];
-        let shared_len = shared_prefix_length(&path, path_offset, &node_path);
-        assert_eq!(shared_len, 7);
+    use ethers::types::{EIP1186ProofResponse, H256};
+    use ethers::utils::keccak256;
+    use ethers::utils::rlp::RlpStream;
+
+    use super::*;
+
+    /// Entries and roots from the `trieanyorder` vectors of the Ethereum trie tests.
+    fn test_vectors() -> Vec<(Vec<(&'static [u8], &'static [u8])>, &'static str)> {
+        vec![
+            (
+                vec![(b"A", b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")],
+                "d23786fb4a010da3ce639d66d5e904a11dbc02746d1ce25029e53290cabf28ab",
+            ),
+            (
+                vec![
+                    (b"doe", b"reindeer"),
+                    (b"dog", b"puppy"),
+                    (b"dogglesworth", b"cat"),
+                ],
+                "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3",
+            ),
+            (
+                vec![
+                    (b"do", b"verb"),
+                    (b"horse", b"stallion"),
+                    (b"doge", b"coin"),
+                    (b"dog", b"puppy"),
+                ],
+                "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84",
+            ),
+            (
+                vec![(b"foo", b"bar"), (b"food", b"bass")],
+                "17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3",
+            ),
+            (
+                vec![(b"be", b"e"), (b"dog", b"puppy"), (b"bed", b"d")],
+                "3f67c7a47520f79faa29255d2d3c084a7a6df0453116ed7232ff10277a8be68b",
+            ),
+            (
+                vec![(b"test", b"test"), (b"te", b"testy")],
+                "8452568af70d8d140f58d941338542f645fcca50094b20f3c3d8c3df49337928",
+            ),
+            (
+                vec![
+                    (&[0x00, 0x45], &[0x01, 0x23, 0x45, 0x67, 0x89]),
+                    (&[0x45, 0x00], &[0x98, 0x76, 0x54, 0x32, 0x10]),
+                ],
+                "285505fcabe84badc8aa310e2aae17eddc7d120aabec8a476902c8184b3a3503",
+            ),
+        ]
+    }
+
+    /// Builds the trie holding `entries` and returns its root node along with the hashed
+    /// nodes on the path to `key`, ordered from the root.
+    fn build_proof(entries: &[(&[u8], &[u8])], key: &[u8]) -> (H256, Vec<Vec<u8>>) {
+        let mut entries = entries
+            .iter()
+            .map(|(k, v)| (to_nibbles(k), v.to_vec()))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        let mut proof = Vec::new();
+        let root = encode_node(&entries, 0, &to_nibbles(key), true, &mut proof);
+        proof.reverse();
+
+        (H256(keccak256(root)), proof)
+    }
+
+    fn encode_node(
+        entries: &[(Vec<u8>, Vec<u8>)],
+        depth: usize,
+        key: &[u8],
+        on_path: bool,
+        proof: &mut Vec<Vec<u8>>,
+    ) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+
+        if entries.len() == 1 {
+            stream.begin_list(2);
+            stream.append(&encode_path(&entries[0].0[depth..], true));
+            stream.append(&entries[0].1);
+        } else {
+            let first = &entries[0].0;
+            let last = &entries[entries.len() - 1].0;
+            let shared = first[depth..]
+                .iter()
+                .zip(&last[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if shared > 0 {
+                let prefix = &first[depth..depth + shared];
+                let on_path = on_path && key.get(depth..depth + shared) == Some(prefix);
+                let child = encode_node(entries, depth + shared, key, on_path, proof);
+
+                stream.begin_list(2);
+                stream.append(&encode_path(prefix, false));
+                append_child(&mut stream, &child);
+            } else {
+                stream.begin_list(17);
+                for nibble in 0..16 {
+                    let children = entries
+                        .iter()
+                        .filter(|(k, _)| k.get(depth) == Some(&nibble))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    if children.is_empty() {
+                        stream.append_empty_data();
+                        continue;
+                    }
+
+                    let on_path = on_path && key.get(depth) == Some(&nibble);
+                    let child = encode_node(&children, depth + 1, key, on_path, proof);
+                    append_child(&mut stream, &child);
+                }
+
+                match entries.iter().find(|(k, _)| k.len() == depth) {
+                    Some((_, value)) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+
+        let node = stream.out().to_vec();
+        if on_path && (node.len() >= 32 || depth == 0) {
+            proof.push(node.clone());
+        }
+
+        node
+    }
+
+    fn append_child(stream: &mut RlpStream, child: &[u8]) {
+        if child.len() < 32 {
+            stream.append_raw(child, 1);
+        } else {
+            stream.append(&H256(keccak256(child)));
+        }
+    }
+
+    fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = if is_leaf { 2 } else { 0 };
+        let mut encoded = if nibbles.len() % 2 == 1 {
+            vec![(flag + 1) << 4 | nibbles[0]]
+        } else {
+            vec![flag << 4]
+        };
+
+        let rest = &nibbles[nibbles.len() % 2..];
+        encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+        encoded
+    }
+
+    #[test]
+    fn test_trie_vectors() {
+        for (entries, root) in test_vectors() {
+            let root = H256::from_slice(&hex::decode(root).unwrap());
+
+            for (key, value) in &entries {
+                let (built_root, proof) = build_proof(&entries, key);
+                assert_eq!(built_root, root);
+
+                let proven = get_proven_value(&proof, root, key).unwrap();
+                assert_eq!(proven.as_deref(), Some(*value));
+                assert!(verify_proof(&proof, root, key, Some(value)).is_ok());
+                assert_eq!(
+                    verify_proof(&proof, root, key, Some(b"other")),
+                    Err(ProofError::ValueMismatch)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_exclusion() {
+        for (entries, root) in test_vectors() {
+            let root = H256::from_slice(&hex::decode(root).unwrap());
+
+            for key in [&b"dogs"[..], b"d", b"x", b"fo", b"tes", &[0x45, 0x01]] {
+                if entries.iter().any(|(k, _)| *k == key) {
+                    continue;
+                }
+
+                let (_, proof) = build_proof(&entries, key);
+                assert!(verify_proof(&proof, root, key, None).is_ok());
+                assert_eq!(
+                    verify_proof(&proof, root, key, Some(b"value")),
+                    Err(ProofError::UnexpectedExclusion)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_proofs() {
+        let entries: Vec<(&[u8], &[u8])> = vec![
+            (b"doe", b"reindeer"),
+            (b"dog", b"puppy"),
+            (b"dogglesworth", b"cat"),
+        ];
+        let (root, proof) = build_proof(&entries, b"dog");
+
+        let mut tampered = proof.clone();
+        let last = tampered.last_mut().unwrap();
+        *last.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            get_proven_value(&tampered, root, b"dog"),
+            Err(ProofError::HashMismatch(proof.len() - 1))
+        );
+
+        assert_eq!(
+            get_proven_value(&proof[..1], root, b"dog"),
+            Err(ProofError::MissingNode)
+        );
+
+        let mut extended = proof.clone();
+        extended.push(vec![0x80]);
+        assert_eq!(
+            get_proven_value(&extended, root, b"dog"),
+            Err(ProofError::UnusedNodes(1))
+        );
+
+        assert_eq!(
+            verify_proof(&proof, root, b"dog", None),
+            Err(ProofError::UnexpectedInclusion)
+        );
+    }
+
+    #[test]
+    fn test_empty_trie_exclusion() {
+        let proof: Vec<Vec<u8>> = Vec::new();
+
+        assert!(verify_proof(&proof, KECCAK_NULL_RLP, b"key", None).is_ok());
+        assert_eq!(
+            verify_proof(&proof, KECCAK_NULL_RLP, b"key", Some(b"value")),
+            Err(ProofError::UnexpectedExclusion)
+        );
+
+        // providers may report zero hashes for accounts that do not exist
+        let proof = EIP1186ProofResponse::default();
+        assert!(verify_account(&proof.address, &proof, KECCAK_NULL_RLP).is_ok());
     }
 }
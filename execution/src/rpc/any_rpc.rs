@@ -0,0 +1,170 @@
+use async_trait::async_trait;
+use common::types::{Block, BlockTag};
+use ethers::types::{
+    transaction::eip2930::{AccessList, AccessListItem},
+    Address, EIP1186ProofResponse, FeeHistory, Filter, Log, Transaction, TransactionReceipt, H256,
+};
+use eyre::Result;
+
+use crate::types::CallOpts;
+
+use super::http_rpc::HttpRpc;
+use super::socket_rpc::WsRpc;
+use super::ExecutionRpc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::socket_rpc::IpcRpc;
+
+/// An execution rpc whose transport is selected by the scheme of its url. `http://` and
+/// `https://` urls use [`HttpRpc`], `ws://` and `wss://` urls use [`WsRpc`], and `ipc://` urls
+/// or filesystem paths use [`IpcRpc`]. Anything else, including urls missing their scheme, is
+/// rejected.
+#[derive(Clone)]
+pub enum AnyRpc {
+    Http(HttpRpc),
+    Ws(WsRpc),
+    #[cfg(not(target_arch = "wasm32"))]
+    Ipc(IpcRpc),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $rpc:ident => $call:expr) => {
+        match $self {
+            AnyRpc::Http($rpc) => $call,
+            AnyRpc::Ws($rpc) => $call,
+            #[cfg(not(target_arch = "wasm32"))]
+            AnyRpc::Ipc($rpc) => $call,
+        }
+    };
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl ExecutionRpc for AnyRpc {
+    fn new(rpc: &str) -> Result<Self> {
+        let scheme = rpc.split_once("://").map(|(scheme, _)| scheme);
+
+        Ok(match scheme {
+            Some("http") | Some("https") => AnyRpc::Http(HttpRpc::new(rpc)?),
+            Some("ws") | Some("wss") => AnyRpc::Ws(WsRpc::new(rpc)?),
+            #[cfg(not(target_arch = "wasm32"))]
+            Some("ipc") => AnyRpc::Ipc(IpcRpc::new(rpc)?),
+            #[cfg(not(target_arch = "wasm32"))]
+            None if is_path(rpc) => AnyRpc::Ipc(IpcRpc::new(rpc)?),
+            _ => eyre::bail!("unsupported execution rpc url: {}", rpc),
+        })
+    }
+
+    async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        block: u64,
+    ) -> Result<EIP1186ProofResponse> {
+        dispatch!(self, rpc => rpc.get_proof(address, slots, block).await)
+    }
+
+    async fn get_proofs(
+        &self,
+        accounts: &[AccessListItem],
+        block: u64,
+    ) -> Result<Vec<EIP1186ProofResponse>> {
+        dispatch!(self, rpc => rpc.get_proofs(accounts, block).await)
+    }
+
+    async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList> {
+        dispatch!(self, rpc => rpc.create_access_list(opts, block).await)
+    }
+
+    async fn get_code(&self, address: &Address, block: u64) -> Result<Vec<u8>> {
+        dispatch!(self, rpc => rpc.get_code(address, block).await)
+    }
+
+    async fn get_codes(&self, addresses: &[Address], block: u64) -> Result<Vec<Vec<u8>>> {
+        dispatch!(self, rpc => rpc.get_codes(addresses, block).await)
+    }
+
+    async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
+        dispatch!(self, rpc => rpc.send_raw_transaction(bytes).await)
+    }
+
+    async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TransactionReceipt>> {
+        dispatch!(self, rpc => rpc.get_transaction_receipt(tx_hash).await)
+    }
+
+    async fn get_transaction_receipts(
+        &self,
+        tx_hashes: &[H256],
+    ) -> Result<Vec<Option<TransactionReceipt>>> {
+        dispatch!(self, rpc => rpc.get_transaction_receipts(tx_hashes).await)
+    }
+
+    async fn get_block_receipts(&self, block: u64) -> Result<Vec<TransactionReceipt>> {
+        dispatch!(self, rpc => rpc.get_block_receipts(block).await)
+    }
+
+    async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
+        dispatch!(self, rpc => rpc.get_transaction(tx_hash).await)
+    }
+
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>> {
+        dispatch!(self, rpc => rpc.get_block(block, full_tx).await)
+    }
+
+    async fn get_blocks(&self, blocks: &[u64]) -> Result<Vec<Option<Block>>> {
+        dispatch!(self, rpc => rpc.get_blocks(blocks).await)
+    }
+
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        dispatch!(self, rpc => rpc.get_logs(filter).await)
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        dispatch!(self, rpc => rpc.chain_id().await)
+    }
+
+    async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: u64,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        dispatch!(self, rpc => {
+            rpc.get_fee_history(block_count, last_block, reward_percentiles)
+                .await
+        })
+    }
+}
+
+/// Returns whether `rpc` is clearly a filesystem path, such as `/tmp/geth.ipc` or a windows
+/// named pipe, rather than a url missing its scheme like `localhost:8545`.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_path(rpc: &str) -> bool {
+    ["/", "./", "../", r"\\.\pipe\"]
+        .iter()
+        .any(|prefix| rpc.starts_with(prefix))
+        || rpc.ends_with(".ipc")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selects_transport_by_scheme() {
+        let rpc = AnyRpc::new("http://localhost:8545").unwrap();
+        assert!(matches!(rpc, AnyRpc::Http(_)));
+
+        let rpc = AnyRpc::new("wss://localhost:8546").unwrap();
+        assert!(matches!(rpc, AnyRpc::Ws(_)));
+
+        let rpc = AnyRpc::new("/tmp/geth.ipc").unwrap();
+        assert!(matches!(rpc, AnyRpc::Ipc(_)));
+
+        let rpc = AnyRpc::new("ipc://geth.sock").unwrap();
+        assert!(matches!(rpc, AnyRpc::Ipc(_)));
+
+        assert!(AnyRpc::new("ftp://localhost").is_err());
+        assert!(AnyRpc::new("localhost:8545").is_err());
+    }
+}
@@ -1,17 +1,25 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use async_trait::async_trait;
-use common::types::BlockTag;
+use common::types::{Block, BlockTag};
 use ethers::prelude::{Address, Http};
-use ethers::providers::{FilterKind, HttpRateLimitRetryPolicy, Middleware, Provider, RetryClient};
+use ethers::providers::{
+    HttpClientError, HttpRateLimitRetryPolicy, JsonRpcError, Middleware, Provider, RetryClient,
+    RetryPolicy,
+};
 use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::transaction::eip2930::{AccessList, AccessListItem};
 use ethers::types::{
     BlockId, BlockNumber, Bytes, EIP1186ProofResponse, Eip1559TransactionRequest, FeeHistory,
     Filter, Log, Transaction, TransactionReceipt, H256, U256,
 };
-use eyre::Result;
+use eyre::{eyre, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use wasm_timer::Delay;
 
+use crate::constants::{RATE_LIMIT_INITIAL_BACKOFF_MS, RATE_LIMIT_MAX_RETRIES};
 use crate::types::CallOpts;
 use common::errors::RpcError;
 
@@ -20,6 +28,7 @@ use super::ExecutionRpc;
 pub struct HttpRpc {
     url: String,
     provider: Provider<RetryClient<Http>>,
+    client: reqwest::Client,
 }
 
 impl Clone for HttpRpc {
@@ -33,7 +42,12 @@ impl Clone for HttpRpc {
 impl ExecutionRpc for HttpRpc {
     fn new(rpc: &str) -> Result<Self> {
         let http = Http::from_str(rpc)?;
-        let mut client = RetryClient::new(http, Box::new(HttpRateLimitRetryPolicy), 100, 50);
+        let mut client = RetryClient::new(
+            http,
+            Box::new(HttpRateLimitRetryPolicy),
+            RATE_LIMIT_MAX_RETRIES,
+            RATE_LIMIT_INITIAL_BACKOFF_MS,
+        );
         client.set_compute_units(300);
 
         let provider = Provider::new(client);
@@ -41,6 +55,7 @@ impl ExecutionRpc for HttpRpc {
         Ok(HttpRpc {
             url: rpc.to_string(),
             provider,
+            client: reqwest::Client::new(),
         })
     }
 
@@ -60,23 +75,23 @@ impl ExecutionRpc for HttpRpc {
         Ok(proof_response)
     }
 
-    async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList> {
-        let block = match block {
-            BlockTag::Latest => BlockId::Number(BlockNumber::Latest),
-            BlockTag::Finalized => BlockId::Number(BlockNumber::Finalized),
-            BlockTag::Number(number) => BlockId::Number(BlockNumber::Number(number.into())),
-        };
+    async fn get_proofs(
+        &self,
+        accounts: &[AccessListItem],
+        block: u64,
+    ) -> Result<Vec<EIP1186ProofResponse>> {
+        let params = accounts
+            .iter()
+            .map(|account| json!([account.address, account.storage_keys, BlockId::from(block)]))
+            .collect();
 
-        let mut raw_tx = Eip1559TransactionRequest::new();
-        raw_tx.to = Some(opts.to.unwrap_or_default().into());
-        raw_tx.from = opts.from;
-        raw_tx.value = opts.value;
-        raw_tx.gas = Some(opts.gas.unwrap_or(U256::from(100_000_000)));
-        raw_tx.max_fee_per_gas = Some(U256::zero());
-        raw_tx.max_priority_fee_per_gas = Some(U256::zero());
-        raw_tx.data = opts.data.as_ref().map(|data| data.to_owned());
+        self.batch_request("eth_getProof", params).await
+    }
+
+    async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList> {
+        let block = block_id(block);
+        let tx = access_list_request(opts);
 
-        let tx = TypedTransaction::Eip1559(raw_tx);
         let list = self
             .provider
             .create_access_list(&tx, Some(block))
@@ -97,6 +112,16 @@ impl ExecutionRpc for HttpRpc {
         Ok(code.to_vec())
     }
 
+    async fn get_codes(&self, addresses: &[Address], block: u64) -> Result<Vec<Vec<u8>>> {
+        let params = addresses
+            .iter()
+            .map(|address| json!([address, BlockId::from(block)]))
+            .collect();
+
+        let codes: Vec<Bytes> = self.batch_request("eth_getCode", params).await?;
+        Ok(codes.into_iter().map(|code| code.to_vec()).collect())
+    }
+
     async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
         let bytes = Bytes::from(bytes.to_owned());
         let tx = self
@@ -118,60 +143,72 @@ impl ExecutionRpc for HttpRpc {
         Ok(receipt)
     }
 
-    async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
-        Ok(self
-            .provider
-            .get_transaction(*tx_hash)
+    async fn get_transaction_receipts(
+        &self,
+        tx_hashes: &[H256],
+    ) -> Result<Vec<Option<TransactionReceipt>>> {
+        let params = tx_hashes.iter().map(|tx_hash| json!([tx_hash])).collect();
+        self.batch_request("eth_getTransactionReceipt", params)
             .await
-            .map_err(|e| RpcError::new("get_transaction", e))?)
     }
 
-    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
-        Ok(self
+    async fn get_block_receipts(&self, block: u64) -> Result<Vec<TransactionReceipt>> {
+        let receipts = self
             .provider
-            .get_logs(filter)
+            .get_block_receipts(block)
             .await
-            .map_err(|e| RpcError::new("get_logs", e))?)
-    }
+            .map_err(|e| RpcError::new("get_block_receipts", e))?;
 
-    async fn get_filter_changes(&self, filter_id: &U256) -> Result<Vec<Log>> {
-        Ok(self
-            .provider
-            .get_filter_changes(filter_id)
-            .await
-            .map_err(|e| RpcError::new("get_filter_changes", e))?)
+        Ok(receipts)
     }
 
-    async fn uninstall_filter(&self, filter_id: &U256) -> Result<bool> {
+    async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
         Ok(self
             .provider
-            .uninstall_filter(filter_id)
+            .get_transaction(*tx_hash)
             .await
-            .map_err(|e| RpcError::new("uninstall_filter", e))?)
+            .map_err(|e| RpcError::new("get_transaction", e))?)
     }
 
-    async fn get_new_filter(&self, filter: &Filter) -> Result<U256> {
-        Ok(self
-            .provider
-            .new_filter(FilterKind::Logs(filter))
-            .await
-            .map_err(|e| RpcError::new("get_new_filter", e))?)
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>> {
+        let block = if full_tx {
+            self.provider
+                .get_block_with_txs(block)
+                .await
+                .map_err(|e| RpcError::new("get_block", e))?
+                .map(Block::from)
+        } else {
+            self.provider
+                .get_block(block)
+                .await
+                .map_err(|e| RpcError::new("get_block", e))?
+                .map(Block::from)
+        };
+
+        Ok(block)
     }
 
-    async fn get_new_block_filter(&self) -> Result<U256> {
-        Ok(self
-            .provider
-            .new_filter(FilterKind::NewBlocks)
-            .await
-            .map_err(|e| RpcError::new("get_new_block_filter", e))?)
+    async fn get_blocks(&self, blocks: &[u64]) -> Result<Vec<Option<Block>>> {
+        let params = blocks
+            .iter()
+            .map(|block| json!([BlockNumber::from(*block), false]))
+            .collect();
+
+        let blocks: Vec<Option<ethers::types::Block<H256>>> =
+            self.batch_request("eth_getBlockByNumber", params).await?;
+
+        Ok(blocks
+            .into_iter()
+            .map(|block| block.map(Block::from))
+            .collect())
     }
 
-    async fn get_new_pending_transaction_filter(&self) -> Result<U256> {
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
         Ok(self
             .provider
-            .new_filter(FilterKind::PendingTransactions)
+            .get_logs(filter)
             .await
-            .map_err(|e| RpcError::new("get_new_pending_transactions", e))?)
+            .map_err(|e| RpcError::new("get_logs", e))?)
     }
 
     async fn chain_id(&self) -> Result<u64> {
@@ -197,3 +234,245 @@ impl ExecutionRpc for HttpRpc {
             .map_err(|e| RpcError::new("fee_history", e))?)
     }
 }
+
+impl HttpRpc {
+    /// Sends one json-rpc request per entry of `params` as a single batch, and returns the
+    /// results in the same order as `params`. Rate limited batches are retried with the same
+    /// policy as every other request.
+    async fn batch_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<T>> {
+        if params.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let requests = params
+            .into_iter()
+            .enumerate()
+            .map(|(id, params)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": method,
+                    "params": params,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let policy = HttpRateLimitRetryPolicy;
+        let mut retries = 0;
+        let responses = loop {
+            let err = match self.send_batch(&requests).await {
+                Ok(responses) => break responses,
+                Err(err) => err,
+            };
+
+            if retries >= RATE_LIMIT_MAX_RETRIES || !policy.should_retry(&err) {
+                return Err(RpcError::new(method, err).into());
+            }
+
+            retries += 1;
+            let backoff = policy.backoff_hint(&err).unwrap_or_else(|| {
+                Duration::from_millis(RATE_LIMIT_INITIAL_BACKOFF_MS * retries as u64)
+            });
+
+            let _ = Delay::new(backoff).await;
+        };
+
+        // responses to a batch may arrive in any order
+        let mut results = (0..requests.len()).map(|_| None).collect::<Vec<_>>();
+        for response in responses {
+            let result = response.result.unwrap_or(Value::Null);
+            let result = serde_json::from_value(result).map_err(|e| RpcError::new(method, e))?;
+
+            if let Some(entry) = results.get_mut(response.id) {
+                *entry = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.ok_or(eyre!("missing response in batch for {method}")))
+            .collect()
+    }
+
+    /// Posts a batch, failing if any request in it failed so rate limited batches can be
+    /// retried as a whole.
+    async fn send_batch(&self, requests: &[Value]) -> Result<Vec<BatchResponse>, HttpClientError> {
+        let text = self
+            .client
+            .post(&self.url)
+            .json(requests)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let responses: Vec<BatchResponse> =
+            serde_json::from_str(&text).map_err(|err| HttpClientError::SerdeJson { err, text })?;
+
+        responses
+            .into_iter()
+            .map(|response| match response.error {
+                Some(error) => Err(HttpClientError::JsonRpcError(error)),
+                None => Ok(response),
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    id: usize,
+    result: Option<Value>,
+    error: Option<JsonRpcError>,
+}
+
+pub(super) fn block_id(block: BlockTag) -> BlockId {
+    match block {
+        BlockTag::Latest => BlockId::Number(BlockNumber::Latest),
+        BlockTag::Finalized => BlockId::Number(BlockNumber::Finalized),
+        BlockTag::Safe => BlockId::Number(BlockNumber::Safe),
+        BlockTag::Earliest => BlockId::Number(BlockNumber::Earliest),
+        BlockTag::Pending => BlockId::Number(BlockNumber::Pending),
+        BlockTag::Number(number) => BlockId::Number(BlockNumber::Number(number.into())),
+        BlockTag::Hash(hash) => BlockId::Hash(hash),
+    }
+}
+
+pub(super) fn access_list_request(opts: &CallOpts) -> TypedTransaction {
+    let mut raw_tx = Eip1559TransactionRequest::new();
+    raw_tx.to = Some(opts.to.unwrap_or_default().into());
+    raw_tx.from = opts.from;
+    raw_tx.value = opts.value;
+    raw_tx.gas = Some(opts.gas.unwrap_or(U256::from(100_000_000)));
+    raw_tx.max_fee_per_gas = Some(U256::zero());
+    raw_tx.max_priority_fee_per_gas = Some(U256::zero());
+    raw_tx.data = opts.data.as_ref().map(|data| data.to_owned());
+
+    TypedTransaction::Eip1559(raw_tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    /// Serves one canned response per connection, in order, and returns the url of the server.
+    fn serve(responses: Vec<(u16, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                read_request(&mut stream);
+
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        url
+    }
+
+    fn read_request(stream: &mut TcpStream) {
+        let mut request = Vec::new();
+        let mut chunk = [0; 4096];
+
+        loop {
+            let read = stream.read(&mut chunk).unwrap();
+            request.extend_from_slice(&chunk[..read]);
+
+            let text = String::from_utf8_lossy(&request);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let body_len = text[..end]
+                    .lines()
+                    .find_map(|line| {
+                        let line = line.to_lowercase();
+                        let len = line.strip_prefix("content-length:")?;
+                        len.trim().parse::<usize>().ok()
+                    })
+                    .unwrap_or_default();
+
+                if request.len() >= end + 4 + body_len {
+                    return;
+                }
+            }
+
+            if read == 0 {
+                return;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_orders_responses() {
+        let url = serve(vec![(
+            200,
+            r#"[{"jsonrpc":"2.0","id":1,"result":"0x2"},{"jsonrpc":"2.0","id":0,"result":"0x1"}]"#,
+        )]);
+
+        let rpc = HttpRpc::new(&url).unwrap();
+        let results: Vec<U256> = rpc
+            .batch_request("eth_getBalance", vec![json!([]), json!([])])
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![U256::from(1), U256::from(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_retries_rate_limit() {
+        let url = serve(vec![
+            (429, ""),
+            (200, r#"[{"jsonrpc":"2.0","id":0,"result":"0x1"}]"#),
+        ]);
+
+        let rpc = HttpRpc::new(&url).unwrap();
+        let results: Vec<U256> = rpc
+            .batch_request("eth_getBalance", vec![json!([])])
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![U256::from(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_missing_response() {
+        let url = serve(vec![(200, r#"[{"jsonrpc":"2.0","id":0,"result":"0x1"}]"#)]);
+
+        let rpc = HttpRpc::new(&url).unwrap();
+        let results = rpc
+            .batch_request::<U256>("eth_getBalance", vec![json!([]), json!([])])
+            .await;
+
+        assert!(results.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_error() {
+        let url = serve(vec![(
+            200,
+            r#"[{"jsonrpc":"2.0","id":0,"error":{"code":-32000,"message":"missing trie node"}}]"#,
+        )]);
+
+        let rpc = HttpRpc::new(&url).unwrap();
+        let err = rpc
+            .batch_request::<U256>("eth_getBalance", vec![json!([])])
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("missing trie node"));
+    }
+}
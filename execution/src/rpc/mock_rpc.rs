@@ -1,11 +1,20 @@
-use std::{fs::read_to_string, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use async_trait::async_trait;
-use common::{types::BlockTag, utils::hex_str_to_bytes};
+use common::{
+    types::{Block, BlockTag, Transactions},
+    utils::hex_str_to_bytes,
+};
 use ethers::types::{
     transaction::eip2930::AccessList, Address, EIP1186ProofResponse, FeeHistory, Filter, Log,
-    Transaction, TransactionReceipt, H256, U256,
+    Transaction, TransactionReceipt, H256,
 };
+use ethers::utils::keccak256;
 use eyre::{eyre, Result};
 
 use crate::types::CallOpts;
@@ -15,6 +24,21 @@ use super::ExecutionRpc;
 #[derive(Clone)]
 pub struct MockRpc {
     path: PathBuf,
+    sent: Arc<Mutex<Vec<Vec<u8>>>>,
+    access_lists: Arc<Mutex<HashMap<Address, AccessList>>>,
+}
+
+impl MockRpc {
+    /// Returns every raw transaction sent through the mock, in the order they were sent.
+    pub fn sent_transactions(&self) -> Vec<Vec<u8>> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Serves `list` as the access list of calls to `to`. Calls without one fail to build an
+    /// access list.
+    pub fn set_access_list(&self, to: Address, list: AccessList) {
+        self.access_lists.lock().unwrap().insert(to, list);
+    }
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
@@ -22,7 +46,11 @@ pub struct MockRpc {
 impl ExecutionRpc for MockRpc {
     fn new(rpc: &str) -> Result<Self> {
         let path = PathBuf::from(rpc);
-        Ok(MockRpc { path })
+        Ok(MockRpc {
+            path,
+            sent: Arc::default(),
+            access_lists: Arc::default(),
+        })
     }
 
     async fn get_proof(
@@ -35,8 +63,10 @@ impl ExecutionRpc for MockRpc {
         Ok(serde_json::from_str(&proof)?)
     }
 
-    async fn create_access_list(&self, _opts: &CallOpts, _block: BlockTag) -> Result<AccessList> {
-        Err(eyre!("not implemented"))
+    async fn create_access_list(&self, opts: &CallOpts, _block: BlockTag) -> Result<AccessList> {
+        let to = opts.to.unwrap_or_default();
+        let list = self.access_lists.lock().unwrap().get(&to).cloned();
+        list.ok_or(eyre!("not implemented"))
     }
 
     async fn get_code(&self, _address: &Address, _block: u64) -> Result<Vec<u8>> {
@@ -44,8 +74,9 @@ impl ExecutionRpc for MockRpc {
         hex_str_to_bytes(&code[0..code.len() - 1])
     }
 
-    async fn send_raw_transaction(&self, _bytes: &[u8]) -> Result<H256> {
-        Err(eyre!("not implemented"))
+    async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
+        self.sent.lock().unwrap().push(bytes.to_vec());
+        Ok(H256::from(keccak256(bytes)))
     }
 
     async fn get_transaction_receipt(&self, _tx_hash: &H256) -> Result<Option<TransactionReceipt>> {
@@ -53,35 +84,34 @@ impl ExecutionRpc for MockRpc {
         Ok(serde_json::from_str(&receipt)?)
     }
 
+    async fn get_block_receipts(&self, _block: u64) -> Result<Vec<TransactionReceipt>> {
+        Err(eyre!("not implemented"))
+    }
+
     async fn get_transaction(&self, _tx_hash: &H256) -> Result<Option<Transaction>> {
         let tx = read_to_string(self.path.join("transaction.json"))?;
         Ok(serde_json::from_str(&tx)?)
     }
 
-    async fn get_logs(&self, _filter: &Filter) -> Result<Vec<Log>> {
-        let logs = read_to_string(self.path.join("logs.json"))?;
-        Ok(serde_json::from_str(&logs)?)
-    }
-
-    async fn get_filter_changes(&self, _filter_id: &U256) -> Result<Vec<Log>> {
-        let logs = read_to_string(self.path.join("logs.json"))?;
-        Ok(serde_json::from_str(&logs)?)
-    }
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>> {
+        let path = self.path.join(format!("blocks/{block}.json"));
+        if !path.exists() {
+            return Ok(None);
+        }
 
-    async fn uninstall_filter(&self, _filter_id: &U256) -> Result<bool> {
-        Err(eyre!("not implemented"))
-    }
+        let block: ethers::types::Block<Transaction> =
+            serde_json::from_str(&read_to_string(path)?)?;
+        let mut block = Block::from(block);
+        if !full_tx {
+            block.transactions = Transactions::Hashes(block.transactions.hashes());
+        }
 
-    async fn get_new_filter(&self, _filter: &Filter) -> Result<U256> {
-        Err(eyre!("not implemented"))
+        Ok(Some(block))
     }
 
-    async fn get_new_block_filter(&self) -> Result<U256> {
-        Err(eyre!("not implemented"))
-    }
-
-    async fn get_new_pending_transaction_filter(&self) -> Result<U256> {
-        Err(eyre!("not implemented"))
+    async fn get_logs(&self, _filter: &Filter) -> Result<Vec<Log>> {
+        let logs = read_to_string(self.path.join("logs.json"))?;
+        Ok(serde_json::from_str(&logs)?)
     }
 
     async fn chain_id(&self) -> Result<u64> {
@@ -1,15 +1,19 @@
 use async_trait::async_trait;
-use common::types::BlockTag;
+use common::types::{Block, BlockTag};
 use ethers::types::{
-    transaction::eip2930::AccessList, Address, EIP1186ProofResponse, FeeHistory, Filter, Log,
-    Transaction, TransactionReceipt, H256, U256,
+    transaction::eip2930::{AccessList, AccessListItem},
+    Address, EIP1186ProofResponse, FeeHistory, Filter, Log, Transaction, TransactionReceipt, H256,
 };
 use eyre::Result;
+use futures::future::join_all;
 
 use crate::types::CallOpts;
 
+pub mod any_rpc;
 pub mod http_rpc;
 pub mod mock_rpc;
+pub mod multi_rpc;
+pub mod socket_rpc;
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -18,6 +22,26 @@ pub trait ExecutionRpc: Send + Clone + Sync + 'static {
     where
         Self: Sized;
 
+    /// Returns the number of independent providers backing this rpc.
+    fn provider_count(&self) -> usize {
+        1
+    }
+
+    /// Reports that the proof or code of `address` at `block` served by this rpc failed
+    /// verification. Rpcs backed by several providers use this to demote the provider that
+    /// served it.
+    fn report_invalid_account(&self, _address: &Address, _block: u64) {}
+
+    /// Reports that the receipt of `tx_hash` served by this rpc did not match the verified
+    /// receipts of its block.
+    fn report_invalid_receipt(&self, _tx_hash: &H256) {}
+
+    /// Reports that the receipts of `block` served by this rpc did not match its receipts root.
+    fn report_invalid_receipts(&self, _block: u64) {}
+
+    /// Reports that the logs in `block` served by this rpc did not match its verified receipts.
+    fn report_invalid_logs(&self, _block: u64) {}
+
     async fn get_proof(
         &self,
         address: &Address,
@@ -25,17 +49,67 @@ pub trait ExecutionRpc: Send + Clone + Sync + 'static {
         block: u64,
     ) -> Result<EIP1186ProofResponse>;
 
+    /// Fetches the proofs of several accounts. Implementations that support json-rpc batching
+    /// should send these as a single request.
+    async fn get_proofs(
+        &self,
+        accounts: &[AccessListItem],
+        block: u64,
+    ) -> Result<Vec<EIP1186ProofResponse>> {
+        let proof_futs = accounts
+            .iter()
+            .map(|account| self.get_proof(&account.address, &account.storage_keys, block));
+
+        join_all(proof_futs).await.into_iter().collect()
+    }
+
     async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList>;
     async fn get_code(&self, address: &Address, block: u64) -> Result<Vec<u8>>;
+
+    /// Fetches the code of several accounts. Implementations that support json-rpc batching
+    /// should send these as a single request.
+    async fn get_codes(&self, addresses: &[Address], block: u64) -> Result<Vec<Vec<u8>>> {
+        let code_futs = addresses
+            .iter()
+            .map(|address| self.get_code(address, block));
+
+        join_all(code_futs).await.into_iter().collect()
+    }
+
     async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256>;
     async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TransactionReceipt>>;
+
+    /// Fetches the receipts of several transactions. Implementations that support json-rpc
+    /// batching should send these as a single request.
+    async fn get_transaction_receipts(
+        &self,
+        tx_hashes: &[H256],
+    ) -> Result<Vec<Option<TransactionReceipt>>> {
+        let receipt_futs = tx_hashes
+            .iter()
+            .map(|tx_hash| self.get_transaction_receipt(tx_hash));
+
+        join_all(receipt_futs).await.into_iter().collect()
+    }
+
+    /// Fetches every receipt of a block in a single `eth_getBlockReceipts` request. Not every
+    /// execution rpc supports this method.
+    async fn get_block_receipts(&self, block: u64) -> Result<Vec<TransactionReceipt>>;
+
     async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>>;
+
+    /// Fetches a block by number, with its full transactions if `full_tx` is set and only
+    /// their hashes otherwise. The returned block is unverified.
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>>;
+
+    /// Fetches several blocks with their transaction hashes. Implementations that support
+    /// json-rpc batching should send these as a single request.
+    async fn get_blocks(&self, blocks: &[u64]) -> Result<Vec<Option<Block>>> {
+        let block_futs = blocks.iter().map(|block| self.get_block(*block, false));
+        join_all(block_futs).await.into_iter().collect()
+    }
+
     async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>>;
-    async fn get_filter_changes(&self, filter_id: &U256) -> Result<Vec<Log>>;
-    async fn uninstall_filter(&self, filter_id: &U256) -> Result<bool>;
-    async fn get_new_filter(&self, filter: &Filter) -> Result<U256>;
-    async fn get_new_block_filter(&self) -> Result<U256>;
-    async fn get_new_pending_transaction_filter(&self) -> Result<U256>;
     async fn chain_id(&self) -> Result<u64>;
     async fn get_fee_history(
         &self,
@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use common::types::{Block, BlockTag};
+use config::RpcStrategy;
+use ethers::types::{
+    transaction::eip2930::{AccessList, AccessListItem},
+    Address, EIP1186ProofResponse, FeeHistory, Filter, Log, Transaction, TransactionReceipt, H256,
+};
+use eyre::{eyre, Result};
+use futures::future::{join_all, select_ok};
+use tracing::warn;
+use wasm_timer::Instant;
+
+use crate::constants::{
+    PROVIDER_ERROR_PENALTY, PROVIDER_HEALTHY_THRESHOLD, PROVIDER_INVALID_PENALTY,
+    PROVIDER_MAX_HEALTH, PROVIDER_MAX_TRACKED_RESPONSES, PROVIDER_RECOVERY_INTERVAL_MS,
+};
+use crate::types::CallOpts;
+
+use super::ExecutionRpc;
+
+/// An execution rpc backed by several providers. Each json-rpc method is dispatched according
+/// to its [`RpcStrategy`], preferring providers with a better health score. Providers lose
+/// health when they return errors, and are demoted when [`ExecutionClient`] reports that a
+/// response they served failed verification.
+///
+/// [`ExecutionClient`]: crate::ExecutionClient
+#[derive(Clone)]
+pub struct MultiRpc<R: ExecutionRpc> {
+    providers: Arc<Vec<Provider<R>>>,
+    strategies: HashMap<String, RpcStrategy>,
+    next: Arc<AtomicUsize>,
+    served: Arc<Mutex<HashMap<Served, usize>>>,
+    recovered: Arc<Mutex<Instant>>,
+}
+
+/// A response that may later be reported as invalid, keyed by what it is verified against.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Served {
+    Account(Address, u64),
+    Receipt(H256),
+    Receipts(u64),
+    Logs(u64),
+}
+
+struct Provider<R> {
+    url: String,
+    rpc: R,
+    health: AtomicI64,
+}
+
+impl<R: ExecutionRpc> MultiRpc<R> {
+    /// Sets the strategy used for a json-rpc method, such as `eth_getProof`.
+    pub fn with_strategy(mut self, method: &str, strategy: RpcStrategy) -> Self {
+        self.strategies.insert(method.to_string(), strategy);
+        self
+    }
+
+    /// Returns the urls of the providers along with their current health.
+    pub fn health(&self) -> Vec<(String, i64)> {
+        self.providers
+            .iter()
+            .map(|p| (p.url.clone(), p.health.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    fn strategy(&self, method: &str) -> RpcStrategy {
+        if let Some(strategy) = self.strategies.get(method) {
+            return *strategy;
+        }
+
+        match method {
+            // broadcasting to every provider helps the transaction propagate
+            "eth_sendRawTransaction" => RpcStrategy::Broadcast,
+            _ => RpcStrategy::Failover,
+        }
+    }
+
+    /// Returns the indices of the providers to try, in order of preference.
+    fn candidates(&self, strategy: RpcStrategy) -> Vec<usize> {
+        self.recover();
+
+        let mut indices = (0..self.providers.len()).collect::<Vec<_>>();
+        let health = |i: usize| self.providers[i].health.load(Ordering::Relaxed);
+
+        match strategy {
+            RpcStrategy::Broadcast => {}
+            RpcStrategy::Failover => {
+                indices.sort_by_key(|i| std::cmp::Reverse(health(*i)));
+            }
+            RpcStrategy::Race | RpcStrategy::RoundRobin => {
+                let (healthy, unhealthy): (Vec<_>, Vec<_>) = indices
+                    .into_iter()
+                    .partition(|i| health(*i) >= PROVIDER_HEALTHY_THRESHOLD);
+
+                indices = if healthy.is_empty() {
+                    unhealthy
+                } else {
+                    healthy
+                };
+
+                if strategy == RpcStrategy::RoundRobin {
+                    let start = self.next.fetch_add(1, Ordering::Relaxed) % indices.len();
+                    indices.rotate_left(start);
+                }
+            }
+        }
+
+        indices
+    }
+
+    /// Restores one health point to every provider for each recovery interval elapsed since
+    /// the last recovery, so demoted providers are eventually tried again.
+    fn recover(&self) {
+        let mut recovered = self.recovered.lock().unwrap();
+        let intervals = recovered.elapsed().as_millis() / PROVIDER_RECOVERY_INTERVAL_MS as u128;
+        if intervals == 0 {
+            return;
+        }
+
+        *recovered = Instant::now();
+        let points = intervals.min(PROVIDER_MAX_HEALTH as u128) as i64;
+
+        for provider in self.providers.iter() {
+            let _ = provider
+                .health
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |health| {
+                    Some((health + points).min(PROVIDER_MAX_HEALTH))
+                });
+        }
+    }
+
+    fn penalize(&self, index: usize, penalty: i64) {
+        let provider = &self.providers[index];
+        let health = provider.health.fetch_sub(penalty, Ordering::Relaxed) - penalty;
+
+        if health < 0 {
+            provider.health.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Sends a request according to the strategy of `method`, and returns the response along
+    /// with the index of the provider that served it.
+    async fn request<'a, T, F, Fut>(&'a self, method: &str, f: F) -> Result<(usize, T)>
+    where
+        F: Fn(&'a R) -> Fut,
+        Fut: Future<Output = Result<T>> + 'a,
+    {
+        let strategy = self.strategy(method);
+        let candidates = self.candidates(strategy);
+
+        if strategy == RpcStrategy::Broadcast {
+            let futs = candidates.iter().map(|i| f(&self.providers[*i].rpc));
+            let results = join_all(futs).await;
+
+            let mut response = None;
+            let mut last_err = None;
+            for (i, res) in candidates.into_iter().zip(results) {
+                match res {
+                    Ok(res) => {
+                        response.get_or_insert((i, res));
+                    }
+                    Err(err) => {
+                        warn!(
+                            target: "helios::execution",
+                            "execution rpc {} failed {}: {}",
+                            self.providers[i].url,
+                            method,
+                            err
+                        );
+
+                        self.penalize(i, PROVIDER_ERROR_PENALTY);
+                        last_err = Some(err);
+                    }
+                }
+            }
+
+            return response.ok_or(last_err.unwrap_or(eyre!("no execution rpc available")));
+        }
+
+        if strategy == RpcStrategy::Race {
+            let futs = candidates.into_iter().map(|i| {
+                let fut = f(&self.providers[i].rpc);
+                Box::pin(async move {
+                    fut.await.map(|res| (i, res)).map_err(|err| {
+                        self.penalize(i, PROVIDER_ERROR_PENALTY);
+                        err
+                    })
+                })
+            });
+
+            return select_ok(futs).await.map(|(res, _)| res);
+        }
+
+        let mut last_err = None;
+        for i in candidates {
+            match f(&self.providers[i].rpc).await {
+                Ok(res) => return Ok((i, res)),
+                Err(err) => {
+                    warn!(
+                        target: "helios::execution",
+                        "execution rpc {} failed {}: {}",
+                        self.providers[i].url,
+                        method,
+                        err
+                    );
+
+                    self.penalize(i, PROVIDER_ERROR_PENALTY);
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or(eyre!("no execution rpc available")))
+    }
+
+    fn record_served(&self, responses: impl Iterator<Item = Served>, i: usize) {
+        if self.providers.len() == 1 {
+            return;
+        }
+
+        let mut served = self.served.lock().unwrap();
+        if served.len() >= PROVIDER_MAX_TRACKED_RESPONSES {
+            served.clear();
+        }
+
+        for response in responses {
+            served.insert(response, i);
+        }
+    }
+
+    /// Demotes the provider that served `response`, if it is still tracked.
+    fn demote(&self, response: Served) {
+        let served = self.served.lock().unwrap().remove(&response);
+
+        if let Some(i) = served {
+            warn!(
+                target: "helios::execution",
+                "demoting execution rpc {} after an invalid response",
+                self.providers[i].url
+            );
+
+            self.penalize(i, PROVIDER_INVALID_PENALTY);
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<R: ExecutionRpc> ExecutionRpc for MultiRpc<R> {
+    /// Creates an rpc from a comma separated list of provider urls.
+    fn new(rpc: &str) -> Result<Self> {
+        let providers = rpc
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                Ok(Provider {
+                    url: url.to_string(),
+                    rpc: R::new(url)?,
+                    health: AtomicI64::new(PROVIDER_MAX_HEALTH),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if providers.is_empty() {
+            return Err(eyre!("no execution rpc provided"));
+        }
+
+        Ok(MultiRpc {
+            providers: Arc::new(providers),
+            strategies: HashMap::new(),
+            next: Arc::new(AtomicUsize::new(0)),
+            served: Arc::new(Mutex::new(HashMap::new())),
+            recovered: Arc::new(Mutex::new(Instant::now())),
+        })
+    }
+
+    fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    fn report_invalid_account(&self, address: &Address, block: u64) {
+        self.demote(Served::Account(*address, block));
+    }
+
+    fn report_invalid_receipt(&self, tx_hash: &H256) {
+        self.demote(Served::Receipt(*tx_hash));
+    }
+
+    fn report_invalid_receipts(&self, block: u64) {
+        self.demote(Served::Receipts(block));
+    }
+
+    fn report_invalid_logs(&self, block: u64) {
+        self.demote(Served::Logs(block));
+    }
+
+    async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        block: u64,
+    ) -> Result<EIP1186ProofResponse> {
+        let (i, proof) = self
+            .request("eth_getProof", |rpc| rpc.get_proof(address, slots, block))
+            .await?;
+
+        self.record_served(std::iter::once(Served::Account(*address, block)), i);
+        Ok(proof)
+    }
+
+    async fn get_proofs(
+        &self,
+        accounts: &[AccessListItem],
+        block: u64,
+    ) -> Result<Vec<EIP1186ProofResponse>> {
+        let (i, proofs) = self
+            .request("eth_getProof", |rpc| rpc.get_proofs(accounts, block))
+            .await?;
+
+        self.record_served(
+            accounts
+                .iter()
+                .map(|account| Served::Account(account.address, block)),
+            i,
+        );
+        Ok(proofs)
+    }
+
+    async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList> {
+        self.request("eth_createAccessList", |rpc| {
+            rpc.create_access_list(opts, block)
+        })
+        .await
+        .map(|(_, list)| list)
+    }
+
+    async fn get_code(&self, address: &Address, block: u64) -> Result<Vec<u8>> {
+        let (i, code) = self
+            .request("eth_getCode", |rpc| rpc.get_code(address, block))
+            .await?;
+
+        self.record_served(std::iter::once(Served::Account(*address, block)), i);
+        Ok(code)
+    }
+
+    async fn get_codes(&self, addresses: &[Address], block: u64) -> Result<Vec<Vec<u8>>> {
+        let (i, codes) = self
+            .request("eth_getCode", |rpc| rpc.get_codes(addresses, block))
+            .await?;
+
+        self.record_served(
+            addresses
+                .iter()
+                .map(|address| Served::Account(*address, block)),
+            i,
+        );
+        Ok(codes)
+    }
+
+    async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
+        self.request("eth_sendRawTransaction", |rpc| {
+            rpc.send_raw_transaction(bytes)
+        })
+        .await
+        .map(|(_, hash)| hash)
+    }
+
+    async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TransactionReceipt>> {
+        let (i, receipt) = self
+            .request("eth_getTransactionReceipt", |rpc| {
+                rpc.get_transaction_receipt(tx_hash)
+            })
+            .await?;
+
+        self.record_served(std::iter::once(Served::Receipt(*tx_hash)), i);
+        Ok(receipt)
+    }
+
+    async fn get_transaction_receipts(
+        &self,
+        tx_hashes: &[H256],
+    ) -> Result<Vec<Option<TransactionReceipt>>> {
+        let (i, receipts) = self
+            .request("eth_getTransactionReceipt", |rpc| {
+                rpc.get_transaction_receipts(tx_hashes)
+            })
+            .await?;
+
+        let blocks = receipts
+            .iter()
+            .flatten()
+            .filter_map(|receipt| receipt.block_number);
+
+        self.record_served(blocks.map(|block| Served::Receipts(block.as_u64())), i);
+        Ok(receipts)
+    }
+
+    async fn get_block_receipts(&self, block: u64) -> Result<Vec<TransactionReceipt>> {
+        let (i, receipts) = self
+            .request("eth_getBlockReceipts", |rpc| rpc.get_block_receipts(block))
+            .await?;
+
+        self.record_served(std::iter::once(Served::Receipts(block)), i);
+        Ok(receipts)
+    }
+
+    async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
+        self.request("eth_getTransactionByHash", |rpc| {
+            rpc.get_transaction(tx_hash)
+        })
+        .await
+        .map(|(_, tx)| tx)
+    }
+
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>> {
+        self.request("eth_getBlockByNumber", |rpc| rpc.get_block(block, full_tx))
+            .await
+            .map(|(_, block)| block)
+    }
+
+    async fn get_blocks(&self, blocks: &[u64]) -> Result<Vec<Option<Block>>> {
+        self.request("eth_getBlockByNumber", |rpc| rpc.get_blocks(blocks))
+            .await
+            .map(|(_, blocks)| blocks)
+    }
+
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        let (i, logs) = self
+            .request("eth_getLogs", |rpc| rpc.get_logs(filter))
+            .await?;
+
+        let blocks = logs.iter().filter_map(|log| log.block_number);
+        self.record_served(blocks.map(|block| Served::Logs(block.as_u64())), i);
+        Ok(logs)
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        self.request("eth_chainId", |rpc| rpc.chain_id())
+            .await
+            .map(|(_, chain_id)| chain_id)
+    }
+
+    async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: u64,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        self.request("eth_feeHistory", |rpc| {
+            rpc.get_fee_history(block_count, last_block, reward_percentiles)
+        })
+        .await
+        .map(|(_, history)| history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::rpc::mock_rpc::MockRpc;
+
+    fn get_rpc() -> MultiRpc<MockRpc> {
+        MultiRpc::new("testdata/, ./testdata/").unwrap()
+    }
+
+    #[test]
+    fn test_new_splits_urls() {
+        let rpc = get_rpc();
+        assert_eq!(rpc.provider_count(), 2);
+        assert!(MultiRpc::<MockRpc>::new(" , ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_response_demotes_provider() {
+        let rpc = get_rpc();
+        let address = Address::zero();
+
+        let (i, _) = rpc
+            .request("eth_getProof", |rpc| rpc.get_proof(&address, &[], 1))
+            .await
+            .unwrap();
+
+        rpc.record_served(std::iter::once(Served::Account(address, 1)), i);
+        rpc.report_invalid_account(&address, 1);
+
+        // the demoted provider is now tried last
+        let candidates = rpc.candidates(RpcStrategy::Failover);
+        assert_eq!(candidates.last(), Some(&i));
+        assert_eq!(rpc.candidates(RpcStrategy::Race), vec![1 - i]);
+    }
+
+    #[test]
+    fn test_invalid_receipts_and_logs_demote_provider() {
+        let rpc = get_rpc();
+
+        rpc.record_served(std::iter::once(Served::Receipts(1)), 0);
+        rpc.record_served(std::iter::once(Served::Logs(1)), 1);
+        rpc.report_invalid_receipts(1);
+        assert_eq!(rpc.candidates(RpcStrategy::Race), vec![1]);
+
+        rpc.report_invalid_logs(1);
+        let health = rpc.health();
+        assert_eq!(health[0].1, 0);
+        assert_eq!(health[1].1, 0);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_every_provider() {
+        let rpc = get_rpc();
+        let address = Address::zero();
+        let sent = AtomicUsize::new(0);
+
+        rpc.request("eth_sendRawTransaction", |rpc| {
+            sent.fetch_add(1, Ordering::Relaxed);
+            rpc.get_proof(&address, &[], 1)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(sent.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_health_recovers_over_time() {
+        let rpc = get_rpc();
+        rpc.penalize(0, PROVIDER_INVALID_PENALTY);
+
+        // requests alone do not restore health
+        for _ in 0..PROVIDER_MAX_HEALTH {
+            rpc.candidates(RpcStrategy::Failover);
+        }
+
+        assert_eq!(rpc.health()[0].1, 0);
+
+        let elapsed = Duration::from_millis(PROVIDER_RECOVERY_INTERVAL_MS * 10);
+        *rpc.recovered.lock().unwrap() = Instant::now() - elapsed;
+        rpc.candidates(RpcStrategy::Failover);
+
+        assert_eq!(rpc.health()[0].1, 10);
+        assert_eq!(rpc.health()[1].1, PROVIDER_MAX_HEALTH);
+    }
+
+    #[test]
+    fn test_round_robin_rotates() {
+        let rpc = get_rpc();
+
+        let first = rpc.candidates(RpcStrategy::RoundRobin);
+        let second = rpc.candidates(RpcStrategy::RoundRobin);
+        assert_ne!(first[0], second[0]);
+    }
+}
@@ -0,0 +1,289 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common::errors::RpcError;
+use common::types::{Block, BlockTag};
+use ethers::prelude::Address;
+use ethers::providers::{JsonRpcClient, Middleware, Provider, ProviderError, RpcError as _, Ws};
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{
+    BlockId, BlockNumber, Bytes, EIP1186ProofResponse, FeeHistory, Filter, Log, Transaction,
+    TransactionReceipt, H256,
+};
+use eyre::Result;
+use tokio::sync::Mutex;
+
+#[cfg(not(target_arch = "wasm32"))]
+use ethers::providers::Ipc;
+
+use crate::types::CallOpts;
+
+use super::http_rpc::{access_list_request, block_id};
+use super::ExecutionRpc;
+
+/// An execution rpc over a websocket.
+pub type WsRpc = SocketRpc<Ws>;
+
+/// An execution rpc over a unix socket or windows named pipe.
+#[cfg(not(target_arch = "wasm32"))]
+pub type IpcRpc = SocketRpc<Ipc>;
+
+/// A transport that holds a persistent connection to the execution rpc.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Connect: JsonRpcClient + Sized + 'static {
+    async fn connect(url: &str) -> Result<Self>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Connect for Ws {
+    async fn connect(url: &str) -> Result<Self> {
+        Ok(Ws::connect(url)
+            .await
+            .map_err(|e| RpcError::new("connect", e))?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Connect for Ipc {
+    async fn connect(url: &str) -> Result<Self> {
+        let path = url.strip_prefix("ipc://").unwrap_or(url);
+        Ok(Ipc::connect(path)
+            .await
+            .map_err(|e| RpcError::new("connect", e))?)
+    }
+}
+
+/// An execution rpc over a persistent connection. The connection is opened on the first
+/// request, and shared between clones. If it fails, it is dropped and the next request opens
+/// a new one.
+pub struct SocketRpc<T: JsonRpcClient> {
+    url: String,
+    provider: Arc<Mutex<Option<Arc<Provider<T>>>>>,
+}
+
+impl<T: JsonRpcClient> Clone for SocketRpc<T> {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            provider: self.provider.clone(),
+        }
+    }
+}
+
+impl<T: Connect> SocketRpc<T> {
+    /// Returns the underlying provider, connecting to the execution rpc if needed. This can be
+    /// used to open subscriptions upstream.
+    pub async fn provider(&self) -> Result<Arc<Provider<T>>> {
+        let mut provider = self.provider.lock().await;
+        if let Some(provider) = provider.as_ref() {
+            return Ok(provider.clone());
+        }
+
+        let connected = Arc::new(Provider::new(T::connect(&self.url).await?));
+        *provider = Some(connected.clone());
+
+        Ok(connected)
+    }
+
+    /// Sends a request over the connection. Transport errors drop the connection, so the next
+    /// request reconnects, while errors returned by the execution rpc keep it open.
+    async fn request<R, F, Fut>(&self, method: &str, f: F) -> Result<R>
+    where
+        F: FnOnce(Arc<Provider<T>>) -> Fut,
+        Fut: Future<Output = Result<R, ProviderError>>,
+    {
+        let provider = self.provider().await?;
+        match f(provider.clone()).await {
+            Ok(res) => Ok(res),
+            Err(err) => {
+                if is_transport_error(&err) {
+                    let mut current = self.provider.lock().await;
+                    // another request may have already reconnected
+                    if current
+                        .as_ref()
+                        .map_or(false, |p| Arc::ptr_eq(p, &provider))
+                    {
+                        *current = None;
+                    }
+                }
+
+                Err(RpcError::new(method, err).into())
+            }
+        }
+    }
+}
+
+fn is_transport_error(err: &ProviderError) -> bool {
+    matches!(err, ProviderError::JsonRpcClientError(_))
+        && err.as_error_response().is_none()
+        && err.as_serde_error().is_none()
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: Connect> ExecutionRpc for SocketRpc<T> {
+    fn new(rpc: &str) -> Result<Self> {
+        Ok(SocketRpc {
+            url: rpc.to_string(),
+            provider: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    async fn get_proof(
+        &self,
+        address: &Address,
+        slots: &[H256],
+        block: u64,
+    ) -> Result<EIP1186ProofResponse> {
+        let block = Some(BlockId::from(block));
+        self.request("get_proof", |provider| async move {
+            provider.get_proof(*address, slots.to_vec(), block).await
+        })
+        .await
+    }
+
+    async fn create_access_list(&self, opts: &CallOpts, block: BlockTag) -> Result<AccessList> {
+        let block = block_id(block);
+        let tx = access_list_request(opts);
+
+        let list = self
+            .request("create_access_list", |provider| async move {
+                provider.create_access_list(&tx, Some(block)).await
+            })
+            .await?;
+
+        Ok(list.access_list)
+    }
+
+    async fn get_code(&self, address: &Address, block: u64) -> Result<Vec<u8>> {
+        let block = Some(BlockId::from(block));
+        let code = self
+            .request("get_code", |provider| async move {
+                provider.get_code(*address, block).await
+            })
+            .await?;
+
+        Ok(code.to_vec())
+    }
+
+    async fn send_raw_transaction(&self, bytes: &[u8]) -> Result<H256> {
+        let bytes = Bytes::from(bytes.to_owned());
+        self.request("send_raw_transaction", |provider| async move {
+            let tx = provider.send_raw_transaction(bytes).await?;
+            Ok::<_, ProviderError>(tx.tx_hash())
+        })
+        .await
+    }
+
+    async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TransactionReceipt>> {
+        self.request("get_transaction_receipt", |provider| async move {
+            provider.get_transaction_receipt(*tx_hash).await
+        })
+        .await
+    }
+
+    async fn get_block_receipts(&self, block: u64) -> Result<Vec<TransactionReceipt>> {
+        self.request("get_block_receipts", |provider| async move {
+            provider.get_block_receipts(block).await
+        })
+        .await
+    }
+
+    async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<Transaction>> {
+        self.request("get_transaction", |provider| async move {
+            provider.get_transaction(*tx_hash).await
+        })
+        .await
+    }
+
+    async fn get_block(&self, block: u64, full_tx: bool) -> Result<Option<Block>> {
+        self.request("get_block", |provider| async move {
+            let block = if full_tx {
+                provider.get_block_with_txs(block).await?.map(Block::from)
+            } else {
+                provider.get_block(block).await?.map(Block::from)
+            };
+
+            Ok::<_, ProviderError>(block)
+        })
+        .await
+    }
+
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        self.request("get_logs", |provider| async move {
+            provider.get_logs(filter).await
+        })
+        .await
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        let chain_id = self
+            .request("chain_id", |provider| async move {
+                provider.get_chainid().await
+            })
+            .await?;
+
+        Ok(chain_id.as_u64())
+    }
+
+    async fn get_fee_history(
+        &self,
+        block_count: u64,
+        last_block: u64,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        let block = BlockNumber::from(last_block);
+        self.request("fee_history", |provider| async move {
+            provider
+                .fee_history(block_count, block, reward_percentiles)
+                .await
+        })
+        .await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reconnects_after_connection_error() {
+        let path = std::env::temp_dir().join(format!("helios-{}.ipc", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        std::thread::spawn(move || {
+            // the first connection is closed straight away
+            drop(listener.accept().unwrap());
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap();
+            let request: Value = serde_json::from_slice(&request[..read]).unwrap();
+
+            let response = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": "0x1",
+            });
+
+            stream.write_all(response.to_string().as_bytes()).unwrap();
+            let _ = stream.read(&mut [0; 1]);
+        });
+
+        let rpc = IpcRpc::new(path.to_str().unwrap()).unwrap();
+        assert!(rpc.chain_id().await.is_err());
+        assert_eq!(rpc.chain_id().await.unwrap(), 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -7,54 +7,110 @@ use common::types::{Block, BlockTag, Transactions};
 use ethers::types::{Address, Transaction, H256, U256};
 use tokio::{
     select,
-    sync::{mpsc::Receiver, watch, RwLock},
+    sync::{broadcast, mpsc::Receiver, watch, RwLock},
 };
 
+use crate::constants::BLOCK_SUBSCRIPTION_CAPACITY;
+
 #[derive(Clone)]
 pub struct State {
     inner: Arc<RwLock<Inner>>,
+    block_send: broadcast::Sender<Block>,
+    finalized_block_send: broadcast::Sender<Block>,
+    history_length: u64,
 }
 
 impl State {
     pub fn new(
         mut block_recv: Receiver<Block>,
         mut finalized_block_recv: watch::Receiver<Option<Block>>,
+        mut safe_block_recv: watch::Receiver<Option<Block>>,
         history_length: u64,
     ) -> Self {
         let inner = Arc::new(RwLock::new(Inner::new(history_length)));
         let inner_ref = inner.clone();
 
+        let (block_send, _) = broadcast::channel(BLOCK_SUBSCRIPTION_CAPACITY);
+        let (finalized_block_send, _) = broadcast::channel(BLOCK_SUBSCRIPTION_CAPACITY);
+        let block_send_ref = block_send.clone();
+        let finalized_block_send_ref = finalized_block_send.clone();
+
         tokio::spawn(async move {
             loop {
                 select! {
                     block = block_recv.recv() => {
                         if let Some(block) = block {
-                            inner_ref.write().await.push_block(block);
+                            inner_ref.write().await.push_block(block.clone());
+                            let _ = block_send_ref.send(block);
                         }
                     },
                     _ = finalized_block_recv.changed() => {
                         let block = finalized_block_recv.borrow_and_update().clone();
                         if let Some(block) = block {
-                            inner_ref.write().await.push_finalized_block(block);
+                            inner_ref.write().await.push_finalized_block(block.clone());
+                            let _ = finalized_block_send_ref.send(block);
                         }
 
                     }
+                    _ = safe_block_recv.changed() => {
+                        let block = safe_block_recv.borrow_and_update().clone();
+                        if let Some(block) = block {
+                            inner_ref.write().await.safe_block = Some(block);
+                        }
+                    }
                 }
             }
         });
 
-        Self { inner }
+        Self {
+            inner,
+            block_send,
+            finalized_block_send,
+            history_length,
+        }
+    }
+
+    /// Returns the number of recent blocks kept in the block window.
+    pub fn history_length(&self) -> u64 {
+        self.history_length
     }
 
     pub async fn push_block(&self, block: Block) {
-        self.inner.write().await.push_block(block);
+        self.inner.write().await.push_block(block.clone());
+        let _ = self.block_send.send(block);
+    }
+
+    pub async fn push_finalized_block(&self, block: Block) {
+        self.inner.write().await.push_finalized_block(block.clone());
+        let _ = self.finalized_block_send.send(block);
+    }
+
+    pub async fn push_safe_block(&self, block: Block) {
+        self.inner.write().await.safe_block = Some(block);
+    }
+
+    // subscriptions
+
+    /// Returns a receiver of every new optimistic block, in the order they are received.
+    /// Receivers that fall too far behind skip the oldest blocks.
+    pub fn subscribe_blocks(&self) -> broadcast::Receiver<Block> {
+        self.block_send.subscribe()
+    }
+
+    /// Returns a receiver of every new finalized block.
+    pub fn subscribe_finalized_blocks(&self) -> broadcast::Receiver<Block> {
+        self.finalized_block_send.subscribe()
     }
 
     // full block fetch
 
+    /// Resolves a block parameter to a block in the block window. Helios does not see the
+    /// mempool, so `pending` resolves to the latest block. `safe` resolves to the justified
+    /// block reported by the consensus rpc, or to the finalized block if that block is not on
+    /// the verified chain.
     pub async fn get_block(&self, tag: BlockTag) -> Option<Block> {
         match tag {
-            BlockTag::Latest => self
+            BlockTag::Latest | BlockTag::Pending => self
                 .inner
                 .read()
                 .await
@@ -63,7 +119,10 @@ impl State {
                 .map(|entry| entry.1)
                 .cloned(),
             BlockTag::Finalized => self.inner.read().await.finalized_block.clone(),
+            BlockTag::Safe => self.inner.read().await.safe_block().cloned(),
+            BlockTag::Earliest => self.inner.read().await.blocks.get(&0).cloned(),
             BlockTag::Number(number) => self.inner.read().await.blocks.get(&number).cloned(),
+            BlockTag::Hash(hash) => self.get_block_by_hash(hash).await,
         }
     }
 
@@ -73,6 +132,7 @@ impl State {
             .hashes
             .get(&hash)
             .and_then(|number| inner.blocks.get(number))
+            .filter(|block| block.hash == hash)
             .cloned()
     }
 
@@ -95,6 +155,16 @@ impl State {
             .cloned()
     }
 
+    /// Returns the block in the block window that includes the transaction.
+    pub async fn get_transaction_block(&self, hash: H256) -> Option<Block> {
+        let inner = self.inner.read().await;
+        inner
+            .txs
+            .get(&hash)
+            .and_then(|loc| inner.blocks.get(&loc.block))
+            .cloned()
+    }
+
     pub async fn get_transaction_by_block_and_index(
         &self,
         block_hash: H256,
@@ -149,6 +219,7 @@ impl State {
 struct Inner {
     blocks: BTreeMap<u64, Block>,
     finalized_block: Option<Block>,
+    safe_block: Option<Block>,
     hashes: HashMap<H256, u64>,
     txs: HashMap<H256, TransactionLocation>,
     history_length: u64,
@@ -199,6 +270,25 @@ impl Inner {
         }
     }
 
+    /// Light client updates do not prove the justified checkpoint, so the justified block is
+    /// only trusted if it matches a verified block in the window and is not behind the
+    /// finalized block.
+    fn safe_block(&self) -> Option<&Block> {
+        let finalized = self.finalized_block.as_ref();
+        let justified = self.safe_block.as_ref().filter(|safe| {
+            let number = safe.number.as_u64();
+            let verified = self
+                .blocks
+                .get(&number)
+                .map_or(false, |block| block.hash == safe.hash);
+            let behind = finalized.map_or(false, |block| number < block.number.as_u64());
+
+            verified && !behind
+        });
+
+        justified.or(finalized)
+    }
+
     fn remove_block(&mut self, number: u64) {
         if let Some(block) = self.blocks.remove(&number) {
             self.hashes.remove(&block.hash);
@@ -0,0 +1,414 @@
+use std::{collections::HashMap, sync::Arc};
+
+use common::types::{Block, BlockTag, Transactions};
+use common::utils::decode_transaction;
+use ethers::types::{Address, H256, U256};
+use eyre::Result;
+use tokio::select;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use crate::constants::{
+    BLOCK_SUBSCRIPTION_CAPACITY, TRACKED_TX_DROP_BLOCKS, TRACKED_TX_REBROADCAST_BLOCKS,
+    TRACKED_TX_RETENTION_BLOCKS,
+};
+use crate::errors::ExecutionError;
+use crate::rpc::ExecutionRpc;
+use crate::ExecutionClient;
+
+/// Where a submitted transaction stands, as seen from verified blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Not included in a verified block yet. Transactions return to pending if the block
+    /// including them is reorged out.
+    Pending,
+    Included {
+        block_number: u64,
+        block_hash: H256,
+    },
+    Finalized {
+        block_number: u64,
+        block_hash: H256,
+    },
+    /// Not included within the drop timeout. Dropped transactions that show up in a later
+    /// block are still reported as included.
+    Dropped,
+    /// Another transaction from the same sender with the same nonce was included instead.
+    /// Transactions return to pending if the block including the replacement is reorged out.
+    Replaced {
+        by: H256,
+    },
+}
+
+/// A change in the status of a tracked transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionEvent {
+    pub hash: H256,
+    pub status: TransactionStatus,
+}
+
+/// How settled a transaction must be before it is considered confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Confirmation {
+    /// Included in a verified block.
+    #[default]
+    Included,
+    /// Included in a verified block with at least this many verified blocks on top of it,
+    /// counting the including block.
+    Blocks(u64),
+    /// Included in a finalized block.
+    Finalized,
+}
+
+/// Tracks the transactions submitted through helios. Every verified block is checked for the
+/// inclusion or replacement of pending transactions, and every finalized block for the
+/// finality of included ones. Each status change is sent to subscribers. Pending transactions
+/// can be rebroadcast periodically, and are considered dropped if they are not included
+/// within a timeout. Included transactions whose finality can't be checked are forgotten
+/// once their block is well behind the finalized block.
+#[derive(Clone)]
+pub struct TransactionTracker<R: ExecutionRpc> {
+    execution: Arc<ExecutionClient<R>>,
+    inner: Arc<RwLock<HashMap<H256, TrackedTransaction>>>,
+    event_send: broadcast::Sender<TransactionEvent>,
+    rebroadcast: bool,
+}
+
+struct TrackedTransaction {
+    raw: Vec<u8>,
+    from: Address,
+    nonce: U256,
+    status: TransactionStatus,
+    submitted_block: u64,
+    last_broadcast_block: u64,
+    last_update_block: u64,
+}
+
+impl<R: ExecutionRpc> TransactionTracker<R> {
+    pub fn new(execution: Arc<ExecutionClient<R>>, rebroadcast: bool) -> Self {
+        let (event_send, _) = broadcast::channel(BLOCK_SUBSCRIPTION_CAPACITY);
+        let tracker = Self {
+            execution,
+            inner: Arc::new(RwLock::new(HashMap::new())),
+            event_send,
+            rebroadcast,
+        };
+
+        let mut blocks = tracker.execution.subscribe_blocks();
+        let mut finalized_blocks = tracker.execution.subscribe_finalized_blocks();
+        let tracker_ref = tracker.clone();
+
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    block = blocks.recv() => match block {
+                        Ok(block) => tracker_ref.on_block(&block).await,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                    block = finalized_blocks.recv() => match block {
+                        Ok(block) => tracker_ref.on_finalized_block(&block).await,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+
+        tracker
+    }
+
+    /// Starts tracking a raw transaction that was just sent to the execution rpc, and returns
+    /// its hash.
+    pub async fn track(&self, raw: &[u8]) -> Result<H256> {
+        let tx = decode_transaction(raw)?;
+        let latest = self.latest_block_number().await;
+
+        let tracked = TrackedTransaction {
+            raw: raw.to_vec(),
+            from: tx.from,
+            nonce: tx.nonce,
+            status: TransactionStatus::Pending,
+            submitted_block: latest,
+            last_broadcast_block: latest,
+            last_update_block: latest,
+        };
+
+        self.inner.write().await.entry(tx.hash).or_insert(tracked);
+        Ok(tx.hash)
+    }
+
+    pub async fn status(&self, hash: &H256) -> Option<TransactionStatus> {
+        self.inner
+            .read()
+            .await
+            .get(hash)
+            .map(|tracked| tracked.status)
+    }
+
+    /// Returns a receiver of every status change of a tracked transaction.
+    pub fn subscribe(&self) -> broadcast::Receiver<TransactionEvent> {
+        self.event_send.subscribe()
+    }
+
+    /// Waits until a tracked transaction meets `confirmation`, and returns its status. Fails if
+    /// the transaction is dropped or replaced first.
+    pub async fn wait(&self, hash: &H256, confirmation: Confirmation) -> Result<TransactionStatus> {
+        let mut events = self.subscribe();
+        let mut blocks = self.execution.subscribe_blocks();
+
+        loop {
+            let status = self
+                .status(hash)
+                .await
+                .ok_or(ExecutionError::TransactionNotTracked(hash.to_string()))?;
+
+            match status {
+                TransactionStatus::Dropped => {
+                    return Err(ExecutionError::TransactionDropped(hash.to_string()).into())
+                }
+                TransactionStatus::Replaced { by } => {
+                    return Err(ExecutionError::TransactionReplaced(
+                        hash.to_string(),
+                        by.to_string(),
+                    )
+                    .into())
+                }
+                _ => {}
+            }
+
+            if self.is_confirmed(&status, confirmation).await {
+                return Ok(status);
+            }
+
+            // every wake up rechecks the status, so missed events are harmless
+            let closed = select! {
+                event = events.recv() => matches!(event, Err(RecvError::Closed)),
+                block = blocks.recv() => matches!(block, Err(RecvError::Closed)),
+            };
+
+            if closed {
+                return Err(ExecutionError::TransactionNotTracked(hash.to_string()).into());
+            }
+        }
+    }
+
+    async fn is_confirmed(&self, status: &TransactionStatus, confirmation: Confirmation) -> bool {
+        match (status, confirmation) {
+            (TransactionStatus::Finalized { .. }, _) => true,
+            (TransactionStatus::Included { .. }, Confirmation::Included) => true,
+            (TransactionStatus::Included { block_number, .. }, Confirmation::Blocks(blocks)) => {
+                let latest = self.latest_block_number().await;
+                (latest + 1).saturating_sub(*block_number) >= blocks
+            }
+            _ => false,
+        }
+    }
+
+    async fn on_block(&self, block: &Block) {
+        let number = block.number.as_u64();
+        let txs = match &block.transactions {
+            Transactions::Full(txs) => txs,
+            Transactions::Hashes(_) => unreachable!(),
+        };
+
+        let mut events = Vec::new();
+        let mut rebroadcasts = Vec::new();
+
+        {
+            let mut inner = self.inner.write().await;
+
+            for (hash, tracked) in inner.iter_mut() {
+                // a new block at or below the block including the transaction or its
+                // replacement replaces that block
+                let settled_block = match tracked.status {
+                    TransactionStatus::Included { block_number, .. } => Some(block_number),
+                    TransactionStatus::Replaced { .. } => Some(tracked.last_update_block),
+                    _ => None,
+                };
+
+                if matches!(settled_block, Some(block_number) if block_number >= number) {
+                    tracked.status = TransactionStatus::Pending;
+                    tracked.last_update_block = number;
+                    events.push(TransactionEvent {
+                        hash: *hash,
+                        status: tracked.status,
+                    });
+                }
+
+                if !matches!(
+                    tracked.status,
+                    TransactionStatus::Pending | TransactionStatus::Dropped
+                ) {
+                    continue;
+                }
+
+                let included = txs.iter().find(|tx| tx.hash == *hash);
+                let replacement = txs
+                    .iter()
+                    .find(|tx| tx.from == tracked.from && tx.nonce == tracked.nonce);
+
+                let status = if included.is_some() {
+                    TransactionStatus::Included {
+                        block_number: number,
+                        block_hash: block.hash,
+                    }
+                } else if let Some(replacement) = replacement {
+                    TransactionStatus::Replaced {
+                        by: replacement.hash,
+                    }
+                } else if tracked.status == TransactionStatus::Dropped {
+                    continue;
+                } else if number.saturating_sub(tracked.submitted_block) >= TRACKED_TX_DROP_BLOCKS {
+                    TransactionStatus::Dropped
+                } else {
+                    if self.rebroadcast
+                        && number.saturating_sub(tracked.last_broadcast_block)
+                            >= TRACKED_TX_REBROADCAST_BLOCKS
+                    {
+                        tracked.last_broadcast_block = number;
+                        rebroadcasts.push((*hash, tracked.raw.clone()));
+                    }
+
+                    continue;
+                };
+
+                tracked.status = status;
+                tracked.last_update_block = number;
+                events.push(TransactionEvent {
+                    hash: *hash,
+                    status,
+                });
+            }
+
+            // settled transactions are forgotten once they are old enough
+            inner.retain(|_, tracked| {
+                matches!(
+                    tracked.status,
+                    TransactionStatus::Pending | TransactionStatus::Included { .. }
+                ) || number.saturating_sub(tracked.last_update_block) < TRACKED_TX_RETENTION_BLOCKS
+            });
+        }
+
+        for event in events {
+            info!(
+                target: "helios::execution",
+                "transaction {:?} status changed: {:?}",
+                event.hash,
+                event.status
+            );
+
+            let _ = self.event_send.send(event);
+        }
+
+        for (hash, raw) in rebroadcasts {
+            if let Err(err) = self.execution.send_raw_transaction(&raw).await {
+                debug!(
+                    target: "helios::execution",
+                    "failed to rebroadcast transaction {:?}: {}",
+                    hash,
+                    err
+                );
+            }
+        }
+    }
+
+    async fn on_finalized_block(&self, finalized: &Block) {
+        let finalized_number = finalized.number.as_u64();
+        let mut events = Vec::new();
+
+        let included = self
+            .inner
+            .read()
+            .await
+            .iter()
+            .filter_map(|(hash, tracked)| match tracked.status {
+                TransactionStatus::Included {
+                    block_number,
+                    block_hash,
+                } if block_number <= finalized_number => Some((*hash, block_number, block_hash)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (hash, block_number, block_hash) in included {
+            // the including block must be the one that was finalized at its height
+            let finalized_hash = if block_number == finalized_number {
+                Some(finalized.hash)
+            } else {
+                match self
+                    .execution
+                    .get_block(BlockTag::Number(block_number), false)
+                    .await
+                {
+                    Ok(block) => Some(block.hash),
+                    Err(_) => self
+                        .execution
+                        .get_historical_block_hash(block_number)
+                        .await
+                        .ok(),
+                }
+            };
+
+            if finalized_hash != Some(block_hash) {
+                if finalized_hash.is_none()
+                    && finalized_number - block_number >= TRACKED_TX_RETENTION_BLOCKS
+                {
+                    debug!(
+                        target: "helios::execution",
+                        "forgetting transaction {:?}: could not check the finality of block {}",
+                        hash,
+                        block_number
+                    );
+
+                    let mut inner = self.inner.write().await;
+                    let status = TransactionStatus::Included {
+                        block_number,
+                        block_hash,
+                    };
+
+                    if inner.get(&hash).map(|tracked| tracked.status) == Some(status) {
+                        inner.remove(&hash);
+                    }
+                }
+
+                continue;
+            }
+
+            let mut inner = self.inner.write().await;
+            if let Some(tracked) = inner.get_mut(&hash) {
+                if let TransactionStatus::Included { .. } = tracked.status {
+                    tracked.status = TransactionStatus::Finalized {
+                        block_number,
+                        block_hash,
+                    };
+                    tracked.last_update_block = finalized_number;
+                    events.push(TransactionEvent {
+                        hash,
+                        status: tracked.status,
+                    });
+                }
+            }
+        }
+
+        for event in events {
+            info!(
+                target: "helios::execution",
+                "transaction {:?} status changed: {:?}",
+                event.hash,
+                event.status
+            );
+
+            let _ = self.event_send.send(event);
+        }
+    }
+
+    async fn latest_block_number(&self) -> u64 {
+        self.execution
+            .get_block(BlockTag::Latest, false)
+            .await
+            .map(|block| block.number.as_u64())
+            .unwrap_or_default()
+    }
+}
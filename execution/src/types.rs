@@ -1,6 +1,6 @@
 use std::{collections::HashMap, fmt};
 
-use ethers::types::{Address, Bytes, H256, U256};
+use ethers::types::{transaction::eip2930::AccessList, Address, Bytes, Log, H256, U256, U64};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Clone)]
@@ -34,3 +34,76 @@ impl fmt::Debug for CallOpts {
             .finish()
     }
 }
+
+/// A transaction to be completed and signed by the client, as accepted by
+/// `eth_sendTransaction` and `eth_signTransaction`. Missing fields are filled in before signing.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionOpts {
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub gas: Option<U256>,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub value: Option<U256>,
+    #[serde(alias = "input")]
+    pub data: Option<Bytes>,
+    pub nonce: Option<U256>,
+    pub access_list: Option<AccessList>,
+    pub chain_id: Option<U64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    pub number: Option<U64>,
+    pub time: Option<U64>,
+    pub gas_limit: Option<U64>,
+    pub fee_recipient: Option<Address>,
+    pub prev_randao: Option<H256>,
+    pub base_fee_per_gas: Option<U256>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationBlock {
+    #[serde(default)]
+    pub block_overrides: Option<BlockOverrides>,
+    pub calls: Vec<CallOpts>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationPayload {
+    pub block_state_calls: Vec<SimulationBlock>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedBlock {
+    pub number: U64,
+    pub timestamp: U64,
+    pub gas_used: U64,
+    pub calls: Vec<SimulatedCall>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedCall {
+    pub status: U64,
+    pub return_data: Bytes,
+    pub gas_used: U64,
+    pub logs: Vec<Log>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The result of `eth_getFilterChanges`. Log filters return logs, while block and pending
+/// transaction filters return hashes.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FilterChanges {
+    Logs(Vec<Log>),
+    Hashes(Vec<H256>),
+}
@@ -1,11 +1,16 @@
 use std::str::FromStr;
+use std::sync::Arc;
 
 use common::types::{Block, BlockTag, Transactions};
-use ethers::types::{Address, Filter, Transaction, H256, U256};
+use common::utils::decode_transaction;
+use ethers::types::{Address, Filter, Transaction, TransactionReceipt, H256, U256};
 
 use ethers::utils::rlp::{Decodable, Rlp};
+use execution::errors::ExecutionError;
 use execution::rpc::mock_rpc::MockRpc;
 use execution::state::State;
+use execution::tracker::{Confirmation, TransactionStatus, TransactionTracker};
+use execution::types::{CallOpts, FilterChanges};
 use execution::ExecutionClient;
 use tokio::sync::mpsc::channel;
 use tokio::sync::watch;
@@ -13,7 +18,8 @@ use tokio::sync::watch;
 fn create_state() -> State {
     let (_, block_recv) = channel(256);
     let (_, finalized_recv) = watch::channel(None);
-    State::new(block_recv, finalized_recv, 64)
+    let (_, safe_recv) = watch::channel(None);
+    State::new(block_recv, finalized_recv, safe_recv, 64)
 }
 
 fn create_client(state: State) -> ExecutionClient<MockRpc> {
@@ -63,6 +69,137 @@ async fn test_get_account_bad_proof() {
     assert!(account_res.is_err());
 }
 
+#[tokio::test]
+async fn test_get_account_proof_for_other_address() {
+    let state = create_state();
+
+    // the mock rpc serves the valid proof of 0x14f9... for every address
+    let address = Address::from_low_u64_be(1);
+    let block = Block {
+        state_root: H256::from_str(
+            "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+        )
+        .unwrap(),
+        ..Default::default()
+    };
+
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    let err = execution
+        .get_account(&address, None, BlockTag::Latest)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(ExecutionError::InvalidAccountProof(requested, _)) if *requested == address
+    ));
+}
+
+#[tokio::test]
+async fn test_get_proof() {
+    let state = create_state();
+
+    let address = Address::from_str("14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+    let block = Block {
+        state_root: H256::from_str(
+            "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+        )
+        .unwrap(),
+        ..Default::default()
+    };
+
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    let proof = execution
+        .get_proof(&address, &[], BlockTag::Latest)
+        .await
+        .unwrap();
+
+    assert_eq!(proof.address, address);
+    assert_eq!(
+        proof.balance,
+        U256::from_str_radix("48c27395000", 16).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_get_proof_for_other_address() {
+    let state = create_state();
+
+    let address = Address::from_low_u64_be(1);
+    let block = Block {
+        state_root: H256::from_str(
+            "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+        )
+        .unwrap(),
+        ..Default::default()
+    };
+
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    let err = execution
+        .get_proof(&address, &[], BlockTag::Latest)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(ExecutionError::InvalidAccountProof(..))
+    ));
+}
+
+#[tokio::test]
+async fn test_get_proof_missing_slots() {
+    let state = create_state();
+
+    let address = Address::from_str("14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+    let block = Block {
+        state_root: H256::from_str(
+            "0xaa02f5db2ee75e3da400d10f3c30e894b6016ce8a2501680380a907b6674ce0d",
+        )
+        .unwrap(),
+        ..Default::default()
+    };
+
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    // the mock rpc serves a proof without storage proofs, dropping the requested slot
+    let slot = H256::from_low_u64_be(1);
+    let err = execution
+        .get_proof(&address, &[slot], BlockTag::Latest)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(ExecutionError::ProofResponseMismatch(requested)) if *requested == address
+    ));
+
+    // the rejected proof was not cached, so the slot is still not served
+    assert!(execution
+        .get_account(&address, Some(&[slot]), BlockTag::Latest)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_get_proof_bad_state_root() {
+    let state = create_state();
+
+    let address = Address::from_str("14f9D4aF749609c1438528C0Cce1cC3f6D411c47").unwrap();
+    state.push_block(Block::default()).await;
+
+    let execution = create_client(state);
+    let proof_res = execution.get_proof(&address, &[], BlockTag::Latest).await;
+
+    assert!(proof_res.is_err());
+}
+
 #[tokio::test]
 async fn test_get_tx() {
     let state = create_state();
@@ -78,7 +215,7 @@ async fn test_get_tx() {
     state.push_block(block).await;
 
     let execution = create_client(state);
-    let tx = execution.get_transaction(hash).await.unwrap();
+    let tx = execution.get_transaction(hash).await.unwrap().unwrap();
 
     assert_eq!(tx.hash(), tx_hash);
 }
@@ -93,17 +230,113 @@ async fn test_get_tx_not_included() {
     state.push_block(block).await;
 
     let execution = create_client(state);
-    let tx_res = execution.get_transaction(tx_hash).await;
+    let tx_res = execution.get_transaction(tx_hash).await.unwrap();
 
     assert!(tx_res.is_none());
 }
 
+#[tokio::test]
+async fn test_get_tx_outside_window() {
+    let state = create_state();
+    let tx_hash =
+        H256::from_str("2dac1b27ab58b493f902dda8b63979a112398d747f1761c0891777c0983e591f").unwrap();
+
+    // testdata/blocks holds the chain of headers from the transaction's block to the parent
+    // of the finalized block
+    let parent_hash =
+        H256::from_str("fda9bcbb2f957dd9fa9ef49069b8f903c2f333a4f5cc0b004a5947cfc7d62c41").unwrap();
+
+    let finalized = Block {
+        number: 7530936.into(),
+        parent_hash,
+        ..Default::default()
+    };
+
+    state.push_finalized_block(finalized).await;
+    let execution = create_client(state);
+
+    let tx = execution.get_transaction(tx_hash).await.unwrap().unwrap();
+    let sender = Address::from_str("4281ecf07378ee595c564a59048801330f3084ee").unwrap();
+
+    assert_eq!(tx.hash, tx_hash);
+    assert_eq!(tx.from, sender);
+    assert_eq!(tx.block_number, Some(7530933.into()));
+    assert_eq!(tx.transaction_index, Some(0.into()));
+}
+
+#[tokio::test]
+async fn test_get_tx_outside_window_bad_header() {
+    let state = create_state();
+    let tx_hash =
+        H256::from_str("2dac1b27ab58b493f902dda8b63979a112398d747f1761c0891777c0983e591f").unwrap();
+
+    let finalized = Block {
+        number: 7530936.into(),
+        parent_hash: H256::from_low_u64_be(1),
+        ..Default::default()
+    };
+
+    state.push_finalized_block(finalized).await;
+    let execution = create_client(state);
+
+    assert!(execution.get_transaction(tx_hash).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_tx_weeks_old() {
+    let state = create_state();
+    let tx_hash =
+        H256::from_str("2dac1b27ab58b493f902dda8b63979a112398d747f1761c0891777c0983e591f").unwrap();
+
+    let parent_hash =
+        H256::from_str("fda9bcbb2f957dd9fa9ef49069b8f903c2f333a4f5cc0b004a5947cfc7d62c41").unwrap();
+
+    state
+        .push_finalized_block(Block {
+            number: 7530936.into(),
+            parent_hash,
+            ..Default::default()
+        })
+        .await;
+
+    let execution = create_client(state.clone());
+    execution.get_transaction(tx_hash).await.unwrap().unwrap();
+
+    // two weeks later the headers in between are not served, but the block was proven before
+    state
+        .push_finalized_block(Block {
+            number: (7530936 + 100_800).into(),
+            parent_hash: H256::from_low_u64_be(1),
+            ..Default::default()
+        })
+        .await;
+
+    let tx = execution.get_transaction(tx_hash).await.unwrap().unwrap();
+    assert_eq!(tx.block_number, Some(7530933.into()));
+
+    // blocks beyond the depth limit are rejected before walking any headers
+    state
+        .push_finalized_block(Block {
+            number: (7530936 + 300_000).into(),
+            ..Default::default()
+        })
+        .await;
+
+    let err = execution.get_transaction(tx_hash).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(ExecutionError::HistoricalBlockTooOld(7530933, _))
+    ));
+}
+
 #[tokio::test]
 async fn test_get_logs() {
     let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
 
     let block = Block {
         number: 7530933.into(),
+        hash: H256::from_str("6663f197e991f5a0bb235f33ec554b9bd48c37b4f5002d7ac2abdfa99f86ac14")
+            .unwrap(),
         receipts_root: H256::from_str(
             "dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f",
         )
@@ -127,6 +360,87 @@ async fn test_get_logs() {
     assert!(logs[0].transaction_hash.unwrap() == tx_hash);
 }
 
+#[tokio::test]
+async fn test_get_logs_wrong_block() {
+    let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+
+    // the receipts match, but the logs claim to come from a different block
+    let block = Block {
+        number: 7530933.into(),
+        receipts_root: H256::from_str(
+            "dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f",
+        )
+        .unwrap(),
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block).await;
+
+    let execution = create_client(state);
+    let logs_res = execution.get_logs(&Filter::new()).await;
+
+    assert!(logs_res.is_err());
+}
+
+#[tokio::test]
+async fn test_get_logs_too_many() {
+    let block = Block {
+        number: 7530933.into(),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block).await;
+
+    let execution = create_client(state).with_max_logs(0);
+    let logs_res = execution.get_logs(&Filter::new()).await;
+
+    assert!(logs_res.is_err());
+}
+
+#[tokio::test]
+async fn test_get_logs_completeness() {
+    let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+
+    let receipt: TransactionReceipt =
+        serde_json::from_str(include_str!("../testdata/receipt.json")).unwrap();
+
+    let block = Block {
+        number: 7530933.into(),
+        logs_bloom: receipt.logs_bloom.as_bytes().to_vec().into(),
+        receipts_root: H256::from_str(
+            "dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f",
+        )
+        .unwrap(),
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block).await;
+
+    let execution = create_client(state).with_logs_completeness(true);
+
+    let token = Address::from_str("326c977e6efc84e512bb9c30f76e30c160ed06fb").unwrap();
+    let logs = execution
+        .get_logs(&Filter::new().address(token))
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].transaction_hash, Some(receipt.transaction_hash));
+
+    // the bloom rules out this address, so the block is skipped entirely
+    let logs = execution
+        .get_logs(&Filter::new().address(Address::zero()))
+        .await
+        .unwrap();
+
+    assert!(logs.is_empty());
+}
+
 #[tokio::test]
 async fn test_get_receipt() {
     let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
@@ -208,6 +522,62 @@ async fn test_get_block() {
     assert_eq!(block.number.as_u64(), 12345);
 }
 
+#[tokio::test]
+async fn test_get_block_by_tag() {
+    let block = |number: u64| Block {
+        number: number.into(),
+        hash: H256::from_low_u64_be(number),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block(1)).await;
+    state.push_block(block(2)).await;
+    state.push_block(block(3)).await;
+    state.push_finalized_block(block(1)).await;
+    let execution = create_client(state.clone());
+
+    let get_number = |tag: &str| {
+        let tag: BlockTag = serde_json::from_str(tag).unwrap();
+        let execution = execution.clone();
+        async move { execution.get_block(tag, false).await.map(|b| b.number) }
+    };
+
+    assert_eq!(get_number(r#""pending""#).await.unwrap(), 3.into());
+
+    // the justified block is only used once it matches a verified block
+    assert_eq!(get_number(r#""safe""#).await.unwrap(), 1.into());
+    state.push_safe_block(block(2)).await;
+    assert_eq!(get_number(r#""safe""#).await.unwrap(), 2.into());
+    state
+        .push_safe_block(Block {
+            hash: H256::from_low_u64_be(4),
+            ..block(3)
+        })
+        .await;
+    assert_eq!(get_number(r#""safe""#).await.unwrap(), 1.into());
+
+    assert_eq!(
+        get_number(r#"{ "blockNumber": "0x2" }"#).await.unwrap(),
+        2.into()
+    );
+
+    let tag = format!(r#"{{ "blockHash": "{:?}" }}"#, H256::from_low_u64_be(1));
+    assert_eq!(get_number(&tag).await.unwrap(), 1.into());
+
+    let tag = format!(r#"{{ "blockHash": "{:?}" }}"#, H256::from_low_u64_be(4));
+    assert!(get_number(&tag).await.is_err());
+
+    let tag = format!(
+        r#"{{ "blockHash": "{:?}", "requireCanonical": true }}"#,
+        H256::from_low_u64_be(1)
+    );
+    assert!(serde_json::from_str::<BlockTag>(&tag).is_err());
+
+    // the genesis block is never in the block window
+    assert!(get_number(r#""earliest""#).await.is_err());
+}
+
 #[tokio::test]
 async fn test_get_tx_by_block_hash_and_index() {
     let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
@@ -240,3 +610,407 @@ async fn test_get_tx_by_block_hash_and_index() {
 
     assert_eq!(tx.hash(), tx_hash);
 }
+
+#[tokio::test]
+async fn test_create_access_list_unsupported() {
+    let state = create_state();
+    state.push_block(Block::default()).await;
+    let execution = create_client(state);
+
+    let opts = CallOpts {
+        from: None,
+        to: None,
+        gas: None,
+        gas_price: None,
+        value: None,
+        data: None,
+    };
+
+    // the mock rpc rejects eth_createAccessList, and the error is left to the caller
+    let list = execution.create_access_list(&opts, BlockTag::Latest).await;
+
+    assert!(list.is_err());
+}
+
+#[tokio::test]
+async fn test_block_filter_changes() {
+    let state = create_state();
+    let block = |number: u64| Block {
+        number: number.into(),
+        hash: H256::from_low_u64_be(number),
+        ..Default::default()
+    };
+
+    state.push_block(block(1)).await;
+    let execution = create_client(state.clone());
+
+    let filter_id = execution.get_new_block_filter().await.unwrap();
+
+    state.push_block(block(2)).await;
+    state.push_block(block(3)).await;
+
+    let changes = execution.get_filter_changes(&filter_id).await.unwrap();
+    let expected = vec![H256::from_low_u64_be(2), H256::from_low_u64_be(3)];
+    assert_eq!(changes, FilterChanges::Hashes(expected));
+
+    let changes = execution.get_filter_changes(&filter_id).await.unwrap();
+    assert_eq!(changes, FilterChanges::Hashes(Vec::new()));
+
+    assert!(execution.uninstall_filter(&filter_id).await.unwrap());
+    assert!(execution.get_filter_changes(&filter_id).await.is_err());
+}
+
+fn logs_block(receipts_root: H256) -> Block {
+    let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+
+    Block {
+        number: 7530933.into(),
+        hash: H256::from_str("6663f197e991f5a0bb235f33ec554b9bd48c37b4f5002d7ac2abdfa99f86ac14")
+            .unwrap(),
+        receipts_root,
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_log_filter_changes_retried_after_error() {
+    let state = create_state();
+    state
+        .push_block(Block {
+            number: 7530932.into(),
+            ..Default::default()
+        })
+        .await;
+
+    let execution = create_client(state.clone());
+    let filter_id = execution.get_new_filter(&Filter::new()).await.unwrap();
+
+    // the receipts do not match the receipts root, so the logs cannot be verified
+    state.push_block(logs_block(H256::zero())).await;
+
+    assert!(execution.get_filter_changes(&filter_id).await.is_err());
+    assert!(execution.get_filter_changes(&filter_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_log_filter_changes_at_block_hash() {
+    let state = create_state();
+    state
+        .push_block(Block {
+            number: 7530932.into(),
+            ..Default::default()
+        })
+        .await;
+
+    let execution = create_client(state.clone());
+    let receipts_root =
+        H256::from_str("dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f").unwrap();
+    let block = logs_block(receipts_root);
+    let filter = Filter::new().at_block_hash(block.hash);
+    let filter_id = execution.get_new_filter(&filter).await.unwrap();
+
+    state.push_block(block).await;
+
+    match execution.get_filter_changes(&filter_id).await.unwrap() {
+        FilterChanges::Logs(logs) => assert_eq!(logs.len(), 1),
+        changes => panic!("unexpected filter changes: {:?}", changes),
+    }
+
+    let changes = execution.get_filter_changes(&filter_id).await.unwrap();
+    assert_eq!(changes, FilterChanges::Logs(Vec::new()));
+}
+
+#[tokio::test]
+async fn test_subscribe_blocks() {
+    let state = create_state();
+    let execution = create_client(state.clone());
+
+    let mut blocks = execution.subscribe_blocks();
+    let mut finalized_blocks = execution.subscribe_finalized_blocks();
+
+    let block = Block {
+        number: 1.into(),
+        hash: H256::from_low_u64_be(1),
+        ..Default::default()
+    };
+
+    state.push_block(block.clone()).await;
+    assert_eq!(blocks.recv().await.unwrap().hash, block.hash);
+    assert!(finalized_blocks.try_recv().is_err());
+
+    state.push_finalized_block(block.clone()).await;
+    assert_eq!(finalized_blocks.recv().await.unwrap().hash, block.hash);
+}
+
+#[tokio::test]
+async fn test_get_block_receipts() {
+    let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+
+    let block = Block {
+        number: 7530933.into(),
+        receipts_root: H256::from_str(
+            "dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f",
+        )
+        .unwrap(),
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    // the mock rpc rejects eth_getBlockReceipts, so receipts are fetched individually
+    let receipts = execution
+        .get_block_receipts(BlockTag::Latest)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let tx_hash =
+        H256::from_str("2dac1b27ab58b493f902dda8b63979a112398d747f1761c0891777c0983e591f").unwrap();
+
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].transaction_hash, tx_hash);
+
+    let receipts = execution
+        .get_block_receipts(BlockTag::Number(1))
+        .await
+        .unwrap();
+
+    assert!(receipts.is_none());
+}
+
+#[tokio::test]
+async fn test_get_fee_history() {
+    let tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+
+    let block = Block {
+        number: 7530933.into(),
+        base_fee_per_gas: 1_000_000_000.into(),
+        gas_used: 51818.into(),
+        gas_limit: 30_000_000.into(),
+        receipts_root: H256::from_str(
+            "dd82a78eccb333854f0c99e5632906e092d8a49c27a21c25cae12b82ec2a113f",
+        )
+        .unwrap(),
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    };
+
+    let state = create_state();
+    state.push_block(block).await;
+    let execution = create_client(state);
+
+    let fee_history = execution
+        .get_fee_history(4, BlockTag::Latest, &[25.0, 75.0])
+        .await
+        .unwrap();
+
+    // the tip is capped by the max fee once the base fee is paid
+    let tip = U256::from(1_500_000_016_u64);
+
+    assert_eq!(fee_history.oldest_block, U256::from(7530933));
+    assert_eq!(
+        fee_history.base_fee_per_gas,
+        vec![U256::from(1_000_000_000), U256::from(875_431_817)]
+    );
+    assert_eq!(fee_history.gas_used_ratio, vec![51818.0 / 30_000_000.0]);
+    assert_eq!(fee_history.reward, vec![vec![tip, tip]]);
+
+    let fee_history = execution
+        .get_fee_history(4, BlockTag::Latest, &[75.0, 25.0])
+        .await;
+
+    assert!(fee_history.is_err());
+}
+
+#[tokio::test]
+async fn test_gas_price_oracle() {
+    let mut tx = Transaction::decode(&Rlp::new(&hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap())).unwrap();
+    tx.recover_from_mut().unwrap();
+
+    let state = create_state();
+    let execution = create_client(state.clone());
+
+    state
+        .push_block(Block {
+            number: 7530932.into(),
+            base_fee_per_gas: 1_000_000_000.into(),
+            ..Default::default()
+        })
+        .await;
+
+    // without transactions to sample, the default priority fee is suggested
+    assert_eq!(
+        execution.get_priority_fee().await.unwrap(),
+        U256::from(1_000_000_000)
+    );
+
+    state
+        .push_block(Block {
+            number: 7530933.into(),
+            base_fee_per_gas: 1_000_000_000.into(),
+            transactions: Transactions::Full(vec![tx]),
+            ..Default::default()
+        })
+        .await;
+
+    assert_eq!(
+        execution.get_priority_fee().await.unwrap(),
+        U256::from(1_500_000_016_u64)
+    );
+    assert_eq!(
+        execution.get_gas_price().await.unwrap(),
+        U256::from(2_500_000_016_u64)
+    );
+}
+
+#[tokio::test]
+async fn test_track_transaction() {
+    let raw = hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap();
+    let tx = decode_transaction(&raw).unwrap();
+
+    let state = create_state();
+    let execution = Arc::new(create_client(state.clone()));
+    let tracker = TransactionTracker::new(execution, false);
+
+    let hash = tracker.track(&raw).await.unwrap();
+    assert_eq!(hash, tx.hash);
+    assert_eq!(
+        tracker.status(&hash).await,
+        Some(TransactionStatus::Pending)
+    );
+
+    let block = Block {
+        number: 7530933.into(),
+        hash: H256::repeat_byte(1),
+        transactions: Transactions::Full(vec![tx]),
+        ..Default::default()
+    };
+
+    state.push_block(block.clone()).await;
+    let status = tracker.wait(&hash, Confirmation::Included).await.unwrap();
+    assert_eq!(
+        status,
+        TransactionStatus::Included {
+            block_number: 7530933,
+            block_hash: H256::repeat_byte(1),
+        }
+    );
+
+    state.push_finalized_block(block).await;
+    let status = tracker.wait(&hash, Confirmation::Finalized).await.unwrap();
+    assert_eq!(
+        status,
+        TransactionStatus::Finalized {
+            block_number: 7530933,
+            block_hash: H256::repeat_byte(1),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_track_replaced_transaction() {
+    let raw = hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap();
+
+    // a different transaction from the same sender with the same nonce
+    let mut replacement = decode_transaction(&raw).unwrap();
+    replacement.hash = H256::repeat_byte(2);
+
+    let state = create_state();
+    let execution = Arc::new(create_client(state.clone()));
+    let tracker = TransactionTracker::new(execution, false);
+    let hash = tracker.track(&raw).await.unwrap();
+
+    state
+        .push_block(Block {
+            number: 7530933.into(),
+            transactions: Transactions::Full(vec![replacement]),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(tracker.wait(&hash, Confirmation::Included).await.is_err());
+    assert_eq!(
+        tracker.status(&hash).await,
+        Some(TransactionStatus::Replaced {
+            by: H256::repeat_byte(2)
+        })
+    );
+
+    // the block including the replacement is reorged out by one including the transaction
+    let mut events = tracker.subscribe();
+    state
+        .push_block(Block {
+            number: 7530933.into(),
+            hash: H256::repeat_byte(3),
+            transactions: Transactions::Full(vec![decode_transaction(&raw).unwrap()]),
+            ..Default::default()
+        })
+        .await;
+
+    assert_eq!(
+        events.recv().await.unwrap().status,
+        TransactionStatus::Pending
+    );
+    assert_eq!(
+        events.recv().await.unwrap().status,
+        TransactionStatus::Included {
+            block_number: 7530933,
+            block_hash: H256::repeat_byte(3),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_track_transaction_without_finality() {
+    let raw = hex::decode("02f8b20583623355849502f900849502f91082ea6094326c977e6efc84e512bb9c30f76e30c160ed06fb80b844a9059cbb0000000000000000000000007daccf9b3c1ae2fa5c55f1c978aeef700bc83be0000000000000000000000000000000000000000000000001158e460913d00000c080a0e1445466b058b6f883c0222f1b1f3e2ad9bee7b5f688813d86e3fa8f93aa868ca0786d6e7f3aefa8fe73857c65c32e4884d8ba38d0ecfb947fbffb82e8ee80c167").unwrap();
+    let tx = decode_transaction(&raw).unwrap();
+
+    let state = create_state();
+    let execution = Arc::new(create_client(state.clone()));
+    let tracker = TransactionTracker::new(execution, false);
+    let hash = tracker.track(&raw).await.unwrap();
+
+    state
+        .push_block(Block {
+            number: 7530933.into(),
+            hash: H256::repeat_byte(1),
+            transactions: Transactions::Full(vec![tx]),
+            ..Default::default()
+        })
+        .await;
+
+    tracker.wait(&hash, Confirmation::Included).await.unwrap();
+
+    // the including block leaves the block window and can't be proven from the mock rpc
+    for number in 7530934..=7530997 {
+        state
+            .push_block(Block {
+                number: number.into(),
+                hash: H256::from_low_u64_be(number),
+                ..Default::default()
+            })
+            .await;
+    }
+
+    state
+        .push_finalized_block(Block {
+            number: 7530997.into(),
+            hash: H256::from_low_u64_be(7530997),
+            ..Default::default()
+        })
+        .await;
+
+    for _ in 0..1000 {
+        if tracker.status(&hash).await.is_none() {
+            break;
+        }
+
+        tokio::task::yield_now().await;
+    }
+
+    assert!(tracker.status(&hash).await.is_none());
+}
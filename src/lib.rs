@@ -61,7 +61,10 @@ pub mod config {
 
 pub mod types {
     pub use common::types::{Block, BlockTag, Transactions};
-    pub use execution::types::{Account, CallOpts};
+    pub use execution::types::{
+        Account, BlockOverrides, CallOpts, FilterChanges, SimulatedBlock, SimulatedCall,
+        SimulationBlock,
+    };
 }
 
 pub mod errors {